@@ -0,0 +1,5 @@
+#![warn(clippy::pedantic)]
+
+fn main() -> alpha_shell::lsp::Result<()> {
+  alpha_shell::lsp::run()
+}
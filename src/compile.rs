@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use crate::{
+  diagnostic::{Diagnostic, Diagnostics},
+  lint::{self, Lint, LintLevel},
+  parse::{self as parser, import, node::Node},
+  tokenize::tokenize,
+  transpile::{transpile_with_options, FloatBackend, Target},
+};
+
+/// Settings for [`compile`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+  pub target: Target,
+  /// Repeated once per nesting level at the start of each generated line
+  pub indent: String,
+  /// Start the generated script with the target's shebang line
+  pub shebang: bool,
+  /// Reject calls to commands that aren't functions, shell builtins or
+  /// declared with `external fn`
+  pub strict: bool,
+  /// Command evaluating float arithmetic for targets without floats, which
+  /// reject it otherwise
+  pub float: Option<FloatBackend>,
+  /// Lints that are allowed or denied instead of warned about
+  pub lints: HashMap<Lint, LintLevel>,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      target: Target::default(),
+      indent: "  ".to_owned(),
+      shebang: false,
+      strict: false,
+      float: None,
+      lints: HashMap::new(),
+    }
+  }
+}
+
+impl Options {
+  pub fn new(target: Target) -> Self {
+    Self {
+      target,
+      ..Self::default()
+    }
+  }
+
+  pub fn with_indent(mut self, indent: &str) -> Self {
+    indent.clone_into(&mut self.indent);
+    self
+  }
+
+  pub fn with_shebang(mut self, shebang: bool) -> Self {
+    self.shebang = shebang;
+    self
+  }
+
+  pub fn with_strict(mut self, strict: bool) -> Self {
+    self.strict = strict;
+    self
+  }
+
+  pub fn with_float(mut self, float: Option<FloatBackend>) -> Self {
+    self.float = float;
+    self
+  }
+
+  pub fn with_lint(mut self, lint: Lint, level: LintLevel) -> Self {
+    self.lints.insert(lint, level);
+    self
+  }
+}
+
+/// Result of a successful [`compile`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+  /// Complete script, ending with a newline
+  pub code: String,
+  pub warnings: Vec<Diagnostic>,
+  /// Paths of the files imported by the source, directly or not
+  pub imports: Vec<String>,
+}
+
+/// Parses `AlphaShell` `source` to its syntax tree, with every error found.
+/// Only `strict` is read from `options`
+pub fn parse(source: &str, options: &Options) -> Result<Vec<Node>, Diagnostics> {
+  let tokens = tokenize(source).map_err(|e| vec![e.into()])?;
+  if options.strict {
+    parser::parse_strict(&tokens)
+  } else {
+    parser::parse(&tokens)
+  }
+  .map_err(|e| e.into_iter().map(Into::into).collect())
+}
+
+/// Compiles `AlphaShell` `source` to a shell script, stopping at the first
+/// stage that fails with all of its errors, denied lints included
+pub fn compile(source: &str, options: &Options) -> Result<Output, Diagnostics> {
+  let tree = parse(source, options)?;
+  let (code, transpile_warnings) =
+    transpile_with_options(&tree, options).map_err(|e| vec![e.into()])?;
+
+  let mut warnings = lint::check(&tree);
+  warnings.extend(transpile_warnings);
+
+  let (warnings, errors) = lint::apply(warnings, &options.lints);
+  if !errors.is_empty() {
+    return Err(errors);
+  }
+
+  let code = if options.shebang {
+    format!("{}\n{code}\n", options.target.shebang())
+  } else {
+    format!("{code}\n")
+  };
+
+  Ok(Output {
+    code,
+    warnings,
+    imports: import::files(&tree),
+  })
+}
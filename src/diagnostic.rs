@@ -0,0 +1,191 @@
+use std::fmt::Write;
+
+use crate::{
+  lint::Lint,
+  types::{Position, Span},
+};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Level {
+  Error,
+  Warning,
+}
+
+impl Level {
+  fn name(self) -> &'static str {
+    match self {
+      Level::Error => "error",
+      Level::Warning => "warning",
+    }
+  }
+
+  fn color(self) -> &'static str {
+    match self {
+      Level::Error => RED,
+      Level::Warning => YELLOW,
+    }
+  }
+}
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Error or warning pointing into the source code, shared by the lexer,
+/// the parser and the transpiler
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  pub level: Level,
+  pub message: String,
+  pub span: Option<Span>,
+  /// Imported file the span points into, `None` for the main file
+  pub file: Option<String>,
+  /// Short text shown next to the underline
+  pub label: Option<String>,
+  pub notes: Vec<String>,
+  pub help: Option<String>,
+  /// Lint the warning comes from, which can be allowed or denied
+  pub lint: Option<Lint>,
+}
+
+/// Every error that stopped a stage of the compiler
+pub type Diagnostics = Vec<Diagnostic>;
+
+impl Diagnostic {
+  fn new(level: Level, message: &str) -> Self {
+    Self {
+      level,
+      message: message.to_owned(),
+      span: None,
+      file: None,
+      label: None,
+      notes: vec![],
+      help: None,
+      lint: None,
+    }
+  }
+
+  pub fn error(message: &str) -> Self {
+    Self::new(Level::Error, message)
+  }
+
+  pub fn warning(message: &str) -> Self {
+    Self::new(Level::Warning, message)
+  }
+
+  pub fn with_span(mut self, span: Span) -> Self {
+    self.span = Some(span);
+    self
+  }
+
+  pub fn with_label(mut self, label: &str) -> Self {
+    self.label = Some(label.to_owned());
+    self
+  }
+
+  pub fn with_note(mut self, note: &str) -> Self {
+    self.notes.push(note.to_owned());
+    self
+  }
+
+  pub fn with_help(mut self, help: &str) -> Self {
+    self.help = Some(help.to_owned());
+    self
+  }
+
+  pub fn with_lint(mut self, lint: Lint) -> Self {
+    self.lint = Some(lint);
+    self
+  }
+
+  pub fn in_file(mut self, file: &str) -> Self {
+    self.file = Some(file.to_owned());
+    self
+  }
+
+  /// Renders the diagnostic in the style of rustc, `name` and `source` being
+  /// the main file, used unless the diagnostic comes from an imported file
+  pub fn render(&self, name: &str, source: &str, color: bool) -> String {
+    let paint = |style: &str, text: &str| {
+      if color {
+        format!("{style}{text}{RESET}")
+      } else {
+        text.to_owned()
+      }
+    };
+
+    let level = self.level;
+    let header = match self.lint {
+      Some(lint) => format!("{}[{}]", level.name(), lint.name()),
+      None => level.name().to_owned(),
+    };
+    let mut output = format!(
+      "{}{}\n",
+      paint(level.color(), &header),
+      paint(BOLD, &format!(": {}", self.message))
+    );
+
+    let name = self.file.as_deref().unwrap_or(name);
+    let snippet = self.span.and_then(|span| {
+      let Position(line, _) = span.start;
+      source.lines().nth(line).map(|text| (span, text))
+    });
+
+    let width = snippet.map_or(0, |(span, _)| (span.start.0 + 1).to_string().len());
+    let gutter = paint(BLUE, &format!("{:width$} |", ""));
+
+    if let Some(span) = self.span {
+      let arrow = paint(BLUE, &format!("{:width$}-->", ""));
+      let _ = writeln!(output, "{arrow} {name}:{span}");
+    }
+
+    if let Some((span, text)) = snippet {
+      let Span {
+        start: Position(line, column),
+        end,
+      } = span;
+
+      let length = text.chars().count();
+      let underline = if end.0 == line && end.1 > column {
+        end.1 - column
+      } else if end.0 > line {
+        length.saturating_sub(column).max(1)
+      } else {
+        1
+      };
+
+      // keep tabs so the underline lines up with the source
+      let padding: String = text
+        .chars()
+        .take(column)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+      let mut marker = paint(level.color(), &"^".repeat(underline));
+      if let Some(label) = &self.label {
+        marker += &paint(level.color(), &format!(" {label}"));
+      }
+
+      let number = paint(BLUE, &format!("{:width$} |", line + 1));
+
+      let _ = writeln!(output, "{gutter}");
+      let _ = writeln!(output, "{number} {text}");
+      let _ = writeln!(output, "{gutter} {padding}{marker}");
+    }
+
+    if !self.notes.is_empty() || self.help.is_some() {
+      let _ = writeln!(output, "{gutter}");
+    }
+
+    let equals = paint(BLUE, &format!("{:width$} =", ""));
+    for note in &self.notes {
+      let _ = writeln!(output, "{equals} {}: {note}", paint(BOLD, "note"));
+    }
+    if let Some(help) = &self.help {
+      let _ = writeln!(output, "{equals} {}: {help}", paint(BOLD, "help"));
+    }
+
+    output
+  }
+}
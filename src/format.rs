@@ -0,0 +1,985 @@
+use std::{collections::VecDeque, fmt::Write};
+
+use crate::{
+  diagnostic::Diagnostic,
+  parse::{
+    self,
+    block::Block,
+    declaration::{Declaration, Type},
+    function::{Function, Param},
+    function_call::{FunctionCall, Next, Redirect, RedirectKind},
+    node::{Node, NodeKind},
+    r#for::{For, Foreach},
+    r#if::{Else, If, IfLet},
+    r#try::Try,
+    r#while::{While, WhileLet},
+    signal::Signal,
+    switch::{Case, Switch},
+    value::{AssignmentOperator, BinaryOperator, Literal, UnaryOperator, Value, ValueKind},
+  },
+  tokenize::tokenize_with_comments,
+  types::{Comment, Position, Span, StringToken, Token, TT},
+};
+
+const INDENT: &str = "  ";
+
+/// Prints the source back in the canonical style, keeping its comments
+pub fn format(source: &str) -> Result<String, Vec<Diagnostic>> {
+  let (tokens, comments) = tokenize_with_comments(source).map_err(|e| vec![e.into()])?;
+  let tree = parse::parse_syntax(&tokens)
+    .map_err(|errors| errors.into_iter().map(Diagnostic::from).collect::<Vec<_>>())?;
+
+  let mut formatter = Formatter::new(source, &tokens, comments.clone());
+  formatter.statements(&tree);
+  formatter.comments_before(Position(usize::MAX, 0));
+
+  let mut output = formatter.lines.join("\n");
+  if !output.is_empty() {
+    output.push('\n');
+  }
+
+  check(&tokens, &comments, &output)?;
+
+  Ok(output)
+}
+
+/// Makes sure the formatted code has the tokens and comments of the source,
+/// so a bug in the formatter can't change what a file does
+fn check(tokens: &[Token], comments: &[Comment], output: &str) -> Result<(), Vec<Diagnostic>> {
+  let (formatted, formatted_comments) =
+    tokenize_with_comments(output).map_err(|e| vec![e.into()])?;
+
+  if token_types(tokens) == token_types(&formatted)
+    && texts(comments).eq(texts(&formatted_comments))
+  {
+    return Ok(());
+  }
+
+  Err(vec![Diagnostic::error(
+    "Formatting would change the code or lose comments, the file is left as it is",
+  )])
+}
+
+/// `r#type` with the positions interpolated strings keep reset, as they move
+/// along with the string
+fn without_spans(r#type: &TT) -> TT {
+  let TT::InterpolatedString(parts) = r#type else {
+    return r#type.clone();
+  };
+
+  let parts = parts
+    .iter()
+    .map(|part| match part {
+      StringToken::Variable(name, _) => StringToken::Variable(name.clone(), Span::default()),
+      StringToken::Expression(tokens) => StringToken::Expression(
+        tokens
+          .iter()
+          .map(|token| Token {
+            r#type: without_spans(&token.r#type),
+            span: Span::default(),
+          })
+          .collect(),
+      ),
+      StringToken::Text(text) => StringToken::Text(text.clone()),
+    })
+    .collect();
+
+  TT::InterpolatedString(parts)
+}
+
+/// Token types, without the commas the formatter adds or removes at the end
+/// of lists
+fn token_types(tokens: &[Token]) -> Vec<TT> {
+  tokens
+    .iter()
+    .enumerate()
+    .filter(|(i, token)| {
+      token.r#type != TT::Comma
+        || !matches!(
+          tokens.get(i + 1).map(|token| &token.r#type),
+          Some(TT::RBracket | TT::RBrace | TT::RParen)
+        )
+    })
+    .map(|(_, token)| without_spans(&token.r#type))
+    .collect()
+}
+
+struct Formatter<'a> {
+  source: Vec<Vec<char>>,
+  tokens: &'a [Token],
+  comments: VecDeque<Comment>,
+  lines: Vec<String>,
+  indent: usize,
+  /// Source line of the last thing printed, used to keep blank lines and
+  /// trailing comments
+  last_line: Option<usize>,
+  /// Nothing was printed in the current block yet
+  block_start: bool,
+}
+
+impl<'a> Formatter<'a> {
+  fn new(source: &str, tokens: &'a [Token], comments: Vec<Comment>) -> Self {
+    Self {
+      source: source.lines().map(|line| line.chars().collect()).collect(),
+      tokens,
+      comments: comments.into(),
+      lines: Vec::new(),
+      indent: 0,
+      last_line: None,
+      block_start: true,
+    }
+  }
+
+  /// Source code of a span, like a literal. Only multi-line strings span
+  /// several lines, whose indentation is left to [`Self::line`]
+  fn text(&self, span: Span) -> String {
+    let Span {
+      start: Position(line, start),
+      end: Position(end_line, end),
+    } = span;
+
+    if line == end_line {
+      return self.source[line][start..end].iter().collect();
+    }
+
+    let mut lines = vec![self.source[line][start..].iter().collect::<String>()];
+    lines.extend(
+      self.source[line + 1..end_line]
+        .iter()
+        .map(|line| line.iter().collect::<String>()),
+    );
+    let closing = self.source[end_line][..end].iter().collect::<String>();
+
+    // the text goes one level deeper than the closing quotes
+    let indent = lines[1..]
+      .iter()
+      .filter(|line| !line.trim().is_empty())
+      .map(|line| line.len() - line.trim_start().len())
+      .min()
+      .unwrap_or(0);
+
+    for line in &mut lines[1..] {
+      if !line.trim().is_empty() {
+        *line = format!(
+          "{INDENT}{}",
+          line.get(indent..).unwrap_or(line.trim_start())
+        );
+      }
+    }
+
+    lines.push(closing.trim_start().to_owned());
+
+    lines.join("\n")
+  }
+
+  fn token_index(&self, position: Position) -> usize {
+    self
+      .tokens
+      .partition_point(|token| token.span.start < position)
+  }
+
+  /// Token right before `position`
+  fn previous_token(&self, position: Position) -> Option<&Token> {
+    let index = self.token_index(position);
+    index.checked_sub(1).map(|index| &self.tokens[index])
+  }
+
+  /// Start of the first `{` at or after `position`
+  fn open_brace(&self, position: Position) -> Position {
+    self.tokens[self.token_index(position)..]
+      .iter()
+      .find(|token| token.r#type == TT::LBrace)
+      .map_or(position, |token| token.span.start)
+  }
+
+  /// Start of the `}` matching the `{` at `open`
+  fn close_brace(&self, open: Position) -> Position {
+    let mut level = 0;
+
+    for token in &self.tokens[self.token_index(open)..] {
+      match token.r#type {
+        TT::LBrace => level += 1,
+        TT::RBrace if level == 1 => return token.span.start,
+        TT::RBrace => level -= 1,
+        _ => {}
+      }
+    }
+
+    open
+  }
+
+  /// Start of the first `)` at or after `position`
+  fn close_paren(&self, position: Position) -> Position {
+    self.tokens[self.token_index(position)..]
+      .iter()
+      .find(|token| token.r#type == TT::RParen)
+      .map_or(position, |token| token.span.start)
+  }
+
+  /// Takes the comments starting before `position`
+  fn take_comments(&mut self, position: Position) -> Vec<Comment> {
+    let mut comments = Vec::new();
+
+    while self
+      .comments
+      .front()
+      .is_some_and(|comment| comment.span.start < position)
+    {
+      comments.extend(self.comments.pop_front());
+    }
+
+    comments
+  }
+
+  /// Formats the items of a list or the arguments of a call along with the
+  /// comments between them, `close` being where the closing bracket is.
+  /// Returns the comments after the last item too.
+  fn items<T>(
+    &mut self,
+    items: impl IntoIterator<Item = (Span, T)>,
+    close: Position,
+    mut format: impl FnMut(&mut Self, T) -> String,
+  ) -> (Vec<Item>, Vec<Comment>) {
+    let mut formatted: Vec<Item> = Vec::new();
+
+    for (span, item) in items {
+      let mut leading = self.take_comments(span.start);
+      if let Some(last) = formatted.last_mut() {
+        last.trailing = self.trailing(&mut leading, last.end, span.start);
+      }
+
+      formatted.push(Item {
+        leading,
+        text: format(self, item),
+        trailing: Vec::new(),
+        end: span.end,
+      });
+    }
+
+    let mut closing = self.take_comments(close);
+    if let Some(last) = formatted.last_mut() {
+      last.trailing = self.trailing(&mut closing, last.end, close);
+    }
+
+    (formatted, closing)
+  }
+
+  /// Takes the comments of `comments` going with the item ending at `end`
+  /// rather than the one starting at `next`, those before the comma and the
+  /// ones ending the line of the item
+  fn trailing(&self, comments: &mut Vec<Comment>, end: Position, next: Position) -> Vec<Comment> {
+    let separator = self
+      .tokens
+      .get(self.token_index(end))
+      .map_or(next, |token| token.span.start);
+
+    let count = comments
+      .iter()
+      .take_while(|comment| {
+        comment.span.start < separator || (comment.span.start.0 == end.0 && next.0 > end.0)
+      })
+      .count();
+
+    comments.drain(..count).collect()
+  }
+
+  /// Pushes indented lines, `text` being more than one for values like
+  /// arrays written over several lines
+  fn line(&mut self, text: &str) {
+    let indent = INDENT.repeat(self.indent);
+
+    for line in text.lines() {
+      if line.is_empty() {
+        self.lines.push(String::new());
+      } else {
+        self.lines.push(format!("{indent}{line}"));
+      }
+    }
+
+    self.block_start = false;
+  }
+
+  /// Keeps a single blank line where the source had at least one
+  fn blank_line(&mut self, line: usize) {
+    if !self.block_start && self.last_line.is_some_and(|last| line > last + 1) {
+      self.lines.push(String::new());
+    }
+  }
+
+  /// Prints the comments starting before `position`, next to the last line
+  /// if they were on the same line in the source
+  fn comments_before(&mut self, position: Position) {
+    while self
+      .comments
+      .front()
+      .is_some_and(|comment| comment.span.start < position)
+    {
+      let comment = self.comments.pop_front().unwrap();
+      let Position(line, _) = comment.span.start;
+
+      match self.lines.last_mut() {
+        Some(last) if self.last_line == Some(line) && !self.block_start => {
+          last.push(' ');
+          last.push_str(&comment.text);
+        }
+        _ => {
+          // lines after the first of a block comment are kept as they are
+          self.blank_line(line);
+          self
+            .lines
+            .push(format!("{}{}", INDENT.repeat(self.indent), comment.text));
+          self.block_start = false;
+        }
+      }
+
+      self.last_line = Some(comment.span.end.0);
+    }
+  }
+
+  fn statements(&mut self, nodes: &[Node]) {
+    let mut previous: Option<Span> = None;
+
+    for node in nodes {
+      // `import "a", "b";` is parsed into a node per file
+      if matches!(node.kind, NodeKind::Import(_) | NodeKind::Source(_))
+        && previous == Some(node.span)
+      {
+        continue;
+      }
+      previous = Some(node.span);
+
+      self.comments_before(node.span.start);
+      self.blank_line(node.span.start.0);
+      self.statement(node);
+      self.last_line = Some(node.span.end.0);
+    }
+  }
+
+  /// Prints `header {` and the block up to its closing brace, or `header {}`
+  /// when it's empty. Returns where the closing brace is.
+  fn block(&mut self, header: &str, nodes: &[Node], open: Position) -> Position {
+    let close = self.close_brace(open);
+    let has_comments = self
+      .comments
+      .front()
+      .is_some_and(|comment| comment.span.start < close);
+
+    if nodes.is_empty() && !has_comments {
+      self.line(&format!("{header} {{}}"));
+    } else {
+      self.line(&format!("{header} {{"));
+      self.last_line = Some(open.0);
+
+      // comments right after the brace stay there
+      let first = nodes.first().map_or(close, |node| node.span.start);
+      while self
+        .comments
+        .front()
+        .is_some_and(|comment| comment.span.start.0 == open.0 && comment.span.start < first)
+      {
+        let position = self.comments[0].span.end;
+        self.comments_before(position);
+      }
+
+      self.indent += 1;
+      self.block_start = true;
+
+      self.statements(nodes);
+      self.comments_before(close);
+
+      self.indent -= 1;
+      self.line("}");
+    }
+
+    self.last_line = Some(close.0);
+    close
+  }
+
+  /// Continues the line closing the last block, like `} else`
+  fn continue_block(&mut self, keyword: &str) -> String {
+    let last = self.lines.pop().unwrap_or_default();
+    format!("{} {keyword}", last.trim_start())
+  }
+
+  fn block_node(&mut self, header: &str, node: &Node) {
+    if let NodeKind::Block(nodes) = &node.kind {
+      self.block(header, nodes, node.span.start);
+    }
+  }
+
+  fn statement(&mut self, node: &Node) {
+    let start = node.span.start;
+
+    match &node.kind {
+      NodeKind::Declaration(Declaration {
+        r#type,
+        name,
+        value,
+      }) => {
+        let keyword = match r#type {
+          Type::Let => "let",
+          Type::Export => "export",
+        };
+        let value = match &value.kind {
+          NodeKind::Value(value) => self.value(value),
+          _ => String::new(),
+        };
+
+        self.line(&format!("{keyword} {name} = {value};"));
+      }
+      NodeKind::Value(value) | NodeKind::Expression(value) => {
+        let value = self.value(value);
+        self.line(&format!("{value};"));
+      }
+      NodeKind::FunctionCall(call) => {
+        let call = self.call(call, Some(start));
+        self.line(&format!("{call};"));
+      }
+      NodeKind::Function(Function {
+        name,
+        params,
+        block,
+      }) => {
+        let params = params
+          .iter()
+          .map(|param| self.param(param))
+          .collect::<Vec<_>>()
+          .join(", ");
+
+        self.block_node(&format!("fn {name}({params})"), block);
+      }
+      NodeKind::Block(nodes) => self.statements(nodes),
+      NodeKind::If(r#if) => self.r#if(r#if, "if"),
+      NodeKind::IfLet(IfLet {
+        name,
+        call,
+        block,
+        r#else,
+      }) => {
+        let header = format!("if let {name} = {}", self.call(call, None));
+        let open = self.open_brace(call_end(call, start));
+        let close = self.block(&header, block, open);
+
+        if let Some(block) = r#else {
+          self.r#else(block, close);
+        }
+      }
+      NodeKind::For(r#for) => self.r#for(r#for),
+      NodeKind::Foreach(Foreach {
+        variable,
+        iterable,
+        block,
+      }) => {
+        let header = format!("for {variable} in {}", self.value(iterable));
+        self.block(&header, block, self.open_brace(iterable.span.end));
+      }
+      NodeKind::While(While { condition, block }) => {
+        let header = format!("while {}", self.value(condition));
+        self.block(&header, block, self.open_brace(condition.span.end));
+      }
+      NodeKind::WhileLet(WhileLet { name, call, block }) => {
+        let header = format!("while let {name} = {}", self.call(call, None));
+        let open = self.open_brace(call_end(call, start));
+        self.block(&header, block, open);
+      }
+      NodeKind::Return(value) => {
+        let value = self.value(value);
+        self.line(&format!("return {value};"));
+      }
+      NodeKind::Continue => self.line("continue;"),
+      NodeKind::Break => self.line("break;"),
+      NodeKind::Switch(switch) => self.switch(switch),
+      NodeKind::Try(r#try) => self.r#try(r#try, start),
+      NodeKind::Defer(nodes) => self.defer(nodes, start),
+      NodeKind::Signal(signal) => self.signal(signal, start),
+      NodeKind::Import(_) | NodeKind::Source(_) => {
+        let keyword = match node.kind {
+          NodeKind::Import(_) => "import",
+          _ => "source",
+        };
+        let files = self.strings_in(node.span, |r#type| matches!(r#type, TT::String(_)));
+
+        self.line(&format!("{keyword} {};", files.join(", ")));
+      }
+      NodeKind::External(_) | NodeKind::ExternalFunction(_) => {
+        let keyword = match node.kind {
+          NodeKind::External(_) => "external",
+          _ => "external fn",
+        };
+        let names = self.strings_in(node.span, |r#type| {
+          matches!(r#type, TT::String(_) | TT::Identifier(_))
+        });
+
+        self.line(&format!("{keyword} {};", names.join(", ")));
+      }
+    }
+  }
+
+  fn r#for(&mut self, r#for: &For) {
+    let For {
+      start,
+      end,
+      step,
+      variable,
+      block,
+    } = r#for;
+
+    let mut header = format!(
+      "for {variable} in {}..{}",
+      self.value(start),
+      self.value(end)
+    );
+    // the parser fills in a step of 1 at the end when there's none
+    if step.span != end.span {
+      let _ = write!(header, "..{}", self.value(step));
+    }
+
+    self.block_node(&header, block);
+  }
+
+  /// Source code of the tokens in `span` matching `filter`
+  fn strings_in(&self, span: Span, filter: impl Fn(&TT) -> bool) -> Vec<String> {
+    self.tokens[self.token_index(span.start)..self.token_index(span.end)]
+      .iter()
+      .filter(|token| filter(&token.r#type))
+      .map(|token| self.text(token.span))
+      .collect()
+  }
+
+  fn r#if(&mut self, r#if: &If, keyword: &str) {
+    let If {
+      condition,
+      block,
+      r#else,
+    } = r#if;
+
+    let header = format!("{keyword} {}", self.value(condition));
+    let close = self.block(&header, block, self.open_brace(condition.span.end));
+
+    match r#else {
+      Some(Else::Else(block)) => self.r#else(block, close),
+      Some(Else::Elif(r#if)) => {
+        let keyword = self.continue_block("elif");
+        self.r#if(r#if, &keyword);
+      }
+      None => {}
+    }
+  }
+
+  fn r#try(&mut self, r#try: &Try, start: Position) {
+    let Try {
+      block,
+      error,
+      catch,
+      finally,
+    } = r#try;
+
+    let close = self.block("try", block, self.open_brace(start));
+
+    let keyword = match error {
+      Some(error) => format!("catch {error}"),
+      None => "catch".to_owned(),
+    };
+    let header = self.continue_block(&keyword);
+    let close = self.block(&header, catch, self.open_brace(close));
+
+    if let Some(finally) = finally {
+      let header = self.continue_block("finally");
+      self.block(&header, finally, self.open_brace(close));
+    }
+  }
+
+  fn defer(&mut self, nodes: &[Node], start: Position) {
+    match nodes {
+      // `defer cmd();` is kept without braces
+      [Node {
+        kind: NodeKind::FunctionCall(call),
+        span,
+      }] if self.tokens[self.token_index(start) + 1].r#type != TT::LBrace => {
+        let call = self.call(call, Some(span.start));
+        self.line(&format!("defer {call};"));
+      }
+      _ => {
+        self.block("defer", nodes, self.open_brace(start));
+      }
+    }
+  }
+
+  fn signal(&mut self, Signal { signals, block }: &Signal, start: Position) {
+    let signals = signals
+      .iter()
+      .map(|signal| format!("\"{signal}\""))
+      .collect::<Vec<_>>()
+      .join(", ");
+
+    self.block(
+      &format!("on signal({signals})"),
+      block,
+      self.open_brace(start),
+    );
+  }
+
+  /// Prints an `else` block following the block closed at `close`
+  fn r#else(&mut self, block: &Block, close: Position) {
+    let header = self.continue_block("else");
+    self.block(&header, block, self.open_brace(close));
+  }
+
+  fn switch(&mut self, Switch { arg, cases }: &Switch) {
+    let open = self.open_brace(arg.span.end);
+    let close = self.close_brace(open);
+
+    let arg = self.value(arg);
+    self.line(&format!("switch {arg} {{"));
+    self.last_line = Some(open.0);
+    self.indent += 1;
+    self.block_start = true;
+
+    for Case { block, .. } in cases {
+      self.comments_before(block.span.start);
+      self.blank_line(block.span.start.0);
+
+      // the value is kept as written, quotes included
+      let value = self
+        .previous_token(block.span.start)
+        .map(|token| self.text(token.span))
+        .unwrap_or_default();
+
+      self.block_node(&format!("case {value}"), block);
+    }
+
+    self.comments_before(close);
+    self.indent -= 1;
+    self.line("}");
+    self.last_line = Some(close.0);
+  }
+
+  /// Whether the call is one the parser made up for a value piped into a
+  /// command, like `"text" | cat()` becoming `printf("text") | cat()`, or
+  /// a multi-line string becoming a `<<` call.
+  /// `start` is where the call starts in the source, if known.
+  fn is_desugared(&self, call: &FunctionCall, start: Option<Position>) -> bool {
+    let [arg] = &call.args[..] else {
+      return false;
+    };
+
+    if !matches!(call.name.as_str(), "printf" | "echo" | "<<") {
+      return false;
+    }
+
+    match start {
+      Some(start) => arg.span.start == start,
+      None => self
+        .previous_token(arg.span.start)
+        .is_some_and(|token| token.r#type != TT::LParen),
+    }
+  }
+
+  fn call(&mut self, call: &FunctionCall, start: Option<Position>) -> String {
+    let mut output = if self.is_desugared(call, start) {
+      match &call.args[0].kind {
+        // `$(...) | cmd()` is wrapped in an `echo` call with the same flags
+        ValueKind::FunctionCall(inner) => self.call_head(inner),
+        _ => self.value(&call.args[0]),
+      }
+    } else {
+      self.call_head(call)
+    };
+
+    for redirect in &call.redirects {
+      output += " | ";
+      output += &self.redirect(redirect);
+    }
+
+    if let Some(Next::Call(next)) = &call.next {
+      let _ = write!(output, " | {}", self.call(next, None));
+    }
+
+    if call.is_daemon {
+      output += " &";
+    }
+
+    output
+  }
+
+  fn redirect(&mut self, redirect: &Redirect) -> String {
+    let (kind, file) = match redirect {
+      Redirect::File(kind, file) => (kind, file),
+      Redirect::ErrorToOutput => return "2>&1".to_owned(),
+      Redirect::OutputToError => return ">&2".to_owned(),
+    };
+
+    let operator = match kind {
+      // `| "file"` is kept without the operator it can do without
+      RedirectKind::Output
+        if self
+          .previous_token(file.span.start)
+          .is_some_and(|token| token.r#type == TT::Pipe) =>
+      {
+        return self.value(file);
+      }
+      RedirectKind::Input => "<",
+      RedirectKind::Output => ">",
+      RedirectKind::Append => ">>",
+      RedirectKind::Error => "2>",
+      RedirectKind::AppendError => "2>>",
+      RedirectKind::All => "&>",
+      RedirectKind::AppendAll => "&>>",
+    };
+
+    format!("{operator} {}", self.value(file))
+  }
+
+  fn param(&mut self, param: &Param) -> String {
+    let mut output = if param.named {
+      format!("--{}", param.name)
+    } else {
+      param.name.clone()
+    };
+
+    if let Some(r#type) = param.r#type {
+      let _ = write!(output, ": {type}");
+    }
+
+    if let Some(default) = &param.default {
+      let _ = write!(output, " = {}", self.value(default));
+    }
+
+    output
+  }
+
+  /// The call itself, without what it's piped into
+  fn call_head(&mut self, FunctionCall { name, args, .. }: &FunctionCall) -> String {
+    let Some(last) = args.last() else {
+      return format!("{name}()");
+    };
+
+    let close = self.close_paren(last.span.end);
+    let (items, closing) = self.items(args.iter().map(|arg| (arg.span, arg)), close, Self::value);
+
+    // only comments ending a line put the arguments on lines of their own
+    let span = Span::new(close, close);
+    format!("{name}{}", list(span, &items, &closing, "(", ")"))
+  }
+
+  fn value(&mut self, value: &Value) -> String {
+    match &value.kind {
+      ValueKind::Literal(Literal::Array(items)) => {
+        let (items, closing) = self.items(
+          items.iter().map(|item| (item.span, item)),
+          value.span.end,
+          Self::value,
+        );
+
+        list(value.span, &items, &closing, "[", "]")
+      }
+      ValueKind::Literal(Literal::Map(items)) => {
+        // the key is kept as written, quotes included
+        let keys = items
+          .iter()
+          .filter_map(|(_, item)| {
+            self
+              .previous_token(item.span.start)
+              .and_then(|colon| self.previous_token(colon.span.start))
+          })
+          .map(|key| (key.span.start, self.text(key.span)))
+          .collect::<Vec<_>>();
+
+        let (items, closing) = self.items(
+          keys
+            .into_iter()
+            .zip(items)
+            .map(|((start, key), (_, item))| (Span::new(start, item.span.end), (key, item))),
+          value.span.end,
+          |formatter, (key, item)| format!("{key}: {}", formatter.value(item)),
+        );
+
+        list(value.span, &items, &closing, "{ ", " }")
+      }
+      ValueKind::Literal(_) | ValueKind::Identifier(_) => self.text(value.span),
+      ValueKind::UnaryExpression(operator, value) => {
+        let value = self.value(value);
+
+        match operator {
+          UnaryOperator::Not => format!("!{value}"),
+          // `-a` and `--a` are flags
+          UnaryOperator::Minus
+            if value.starts_with(|c: char| c.is_ascii_alphabetic() || c == '-') =>
+          {
+            format!("- {value}")
+          }
+          UnaryOperator::Minus => format!("-{value}"),
+        }
+      }
+      ValueKind::BinaryExpression(left, operator, right) => format!(
+        "{} {} {}",
+        self.value(left),
+        binary_operator(operator),
+        self.value(right)
+      ),
+      ValueKind::TernaryExpression(condition, left, right) => format!(
+        "{} ? {} : {}",
+        self.value(condition),
+        self.value(left),
+        self.value(right)
+      ),
+      ValueKind::MemberExpression(value, member) => {
+        format!("{}[{}]", self.value(value), self.value(member))
+      }
+      ValueKind::Assignment(left, operator, right) => format!(
+        "{} {} {}",
+        self.value(left),
+        assignment_operator(operator),
+        self.value(right)
+      ),
+      ValueKind::FunctionCall(call) => self.call(call, Some(value.span.start)),
+      ValueKind::Parenthesized(value) => format!("({})", self.value(value)),
+      ValueKind::NamedArgument(name, value) => format!("--{name}={}", self.value(value)),
+      ValueKind::Run(call) => {
+        // the call starts after `run(`
+        let start = self
+          .tokens
+          .get(self.token_index(value.span.start) + 2)
+          .map(|token| token.span.start);
+
+        format!("run({})", self.call(call, start))
+      }
+      ValueKind::Field(value, field) => format!("{}.{field}", self.value(value)),
+    }
+  }
+}
+
+/// Item of a list or argument of a call
+struct Item {
+  /// Comments on the lines before the item
+  leading: Vec<Comment>,
+  text: String,
+  /// Comments after the item, like `1, // one`
+  trailing: Vec<Comment>,
+  end: Position,
+}
+
+fn is_line_comment(comment: &Comment) -> bool {
+  comment.text.starts_with("//")
+}
+
+fn texts(comments: &[Comment]) -> impl Iterator<Item = &str> {
+  comments.iter().map(|comment| comment.text.as_str())
+}
+
+/// Items on one line if they were written on one line, otherwise one per
+/// line with a trailing comma. Comments ending a line always put the items on
+/// lines of their own.
+fn list(span: Span, items: &[Item], closing: &[Comment], open: &str, close: &str) -> String {
+  let comments = || {
+    items
+      .iter()
+      .flat_map(|item| item.leading.iter().chain(&item.trailing))
+      .chain(closing)
+  };
+
+  if items.is_empty() && closing.is_empty() {
+    return format!("{}{}", open.trim(), close.trim());
+  }
+
+  if span.start.0 == span.end.0 && !comments().any(is_line_comment) {
+    let items = items
+      .iter()
+      .map(|item| {
+        texts(&item.leading)
+          .chain([item.text.as_str()])
+          .chain(texts(&item.trailing))
+          .collect::<Vec<_>>()
+          .join(" ")
+      })
+      .chain(texts(closing).map(str::to_owned))
+      .collect::<Vec<_>>();
+
+    return format!("{open}{}{close}", items.join(", "));
+  }
+
+  let mut output = format!("{}\n", open.trim());
+
+  // nested lines are indented relative to the line the list starts on
+  for item in items {
+    for comment in &item.leading {
+      let _ = writeln!(output, "{INDENT}{}", comment.text);
+    }
+
+    for (i, line) in item.text.lines().enumerate() {
+      if i > 0 {
+        output.push('\n');
+      }
+      let _ = write!(output, "{INDENT}{line}");
+    }
+    output.push(',');
+
+    for comment in &item.trailing {
+      let _ = write!(output, " {}", comment.text);
+    }
+    output.push('\n');
+  }
+
+  for comment in closing {
+    let _ = writeln!(output, "{INDENT}{}", comment.text);
+  }
+
+  output + close.trim()
+}
+
+/// Where the arguments of a call and the ones it's piped into end
+fn call_end(call: &FunctionCall, default: Position) -> Position {
+  let end = call
+    .args
+    .iter()
+    .map(|arg| arg.span.end)
+    .max()
+    .unwrap_or(default);
+
+  let end = call
+    .redirects
+    .iter()
+    .filter_map(|redirect| match redirect {
+      Redirect::File(_, file) => Some(file.span.end),
+      _ => None,
+    })
+    .max()
+    .map_or(end, |redirect| redirect.max(end));
+
+  match &call.next {
+    Some(Next::Call(next)) => call_end(next, end),
+    _ => end,
+  }
+}
+
+fn binary_operator(operator: &BinaryOperator) -> &'static str {
+  match operator {
+    BinaryOperator::Add => "+",
+    BinaryOperator::Sub => "-",
+    BinaryOperator::Multiply => "*",
+    BinaryOperator::Divide => "/",
+    BinaryOperator::Modulo => "%",
+    BinaryOperator::Power => "**",
+    BinaryOperator::Equal => "==",
+    BinaryOperator::NotEqual => "!=",
+    BinaryOperator::Greater => ">",
+    BinaryOperator::GreaterEqual => ">=",
+    BinaryOperator::Less => "<",
+    BinaryOperator::LessEqual => "<=",
+    BinaryOperator::RegexMatch => "~",
+    BinaryOperator::And => "&&",
+    BinaryOperator::Or => "||",
+  }
+}
+
+fn assignment_operator(operator: &AssignmentOperator) -> &'static str {
+  match operator {
+    AssignmentOperator::Assignment => "=",
+    AssignmentOperator::AddAssignment => "+=",
+    AssignmentOperator::SubAssignment => "-=",
+    AssignmentOperator::MultiplyAssignment => "*=",
+    AssignmentOperator::DivideAssignment => "/=",
+    AssignmentOperator::ModuloAssignment => "%=",
+    AssignmentOperator::PowerAssignment => "**=",
+  }
+}
@@ -0,0 +1,443 @@
+//! Types of variables, inferred from the values they are declared with so the
+//! transpiler doesn't have to guess how to expand them
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+  parse::{
+    declaration::{Declaration, Type},
+    function::{Function, ParamType},
+    function_call::{FunctionCall, Next, Redirect},
+    import::Import,
+    interpolation::StringPart,
+    node::{Node, NodeKind},
+    r#for::{For, Foreach},
+    r#if::{Else, If, IfLet},
+    r#try::Try,
+    r#while::{While, WhileLet},
+    signal::Signal,
+    switch::Switch,
+    value::{BinaryOperator, Literal, UnaryOperator, Value, ValueKind},
+  },
+  types::Span,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+  String,
+  Int,
+  Float,
+  Bool,
+  Array,
+  Map,
+  /// Output, errors and exit code of a command given by `run()`
+  Result,
+}
+
+impl fmt::Display for ValueType {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ValueType::String => write!(f, "string"),
+      ValueType::Int => write!(f, "int"),
+      ValueType::Float => write!(f, "float"),
+      ValueType::Bool => write!(f, "bool"),
+      ValueType::Array => write!(f, "array"),
+      ValueType::Map => write!(f, "map"),
+      ValueType::Result => write!(f, "result"),
+    }
+  }
+}
+
+impl From<ParamType> for ValueType {
+  fn from(r#type: ParamType) -> Self {
+    match r#type {
+      ParamType::String => ValueType::String,
+      ParamType::Int => ValueType::Int,
+      ParamType::Bool => ValueType::Bool,
+    }
+  }
+}
+
+/// Types of the declarations and of the identifiers referring to them, by
+/// file and span, for the variables whose type is known
+#[derive(Debug, Default)]
+pub struct Types(HashMap<(Option<String>, Span), ValueType>);
+
+impl Types {
+  /// `file` being the imported file the span points into, if any
+  pub fn get(&self, file: Option<&str>, span: Span) -> Option<ValueType> {
+    self.0.get(&(file.map(ToOwned::to_owned), span)).copied()
+  }
+}
+
+pub fn infer(tree: &[Node]) -> Types {
+  let mut inferrer = Inferrer::default();
+
+  inferrer.scoped_block(tree);
+
+  inferrer.types
+}
+
+#[derive(Debug, Default)]
+struct Inferrer {
+  /// `None` for variables of unknown type, which still hide outer ones
+  scopes: Vec<HashMap<String, Option<ValueType>>>,
+  /// imported files being walked
+  files: Vec<String>,
+  /// inside `$()`, where `+` adds numbers
+  arithmetic: bool,
+  types: Types,
+}
+
+impl Inferrer {
+  fn record(&mut self, span: Span, r#type: Option<ValueType>) {
+    if let Some(r#type) = r#type {
+      let file = self.files.last().cloned();
+      self.types.0.insert((file, span), r#type);
+    }
+  }
+
+  fn declare(&mut self, name: &str, span: Span, r#type: Option<ValueType>) {
+    self.record(span, r#type);
+
+    if let Some(scope) = self.scopes.last_mut() {
+      scope.insert(name.to_owned(), r#type);
+    }
+  }
+
+  fn lookup(&self, name: &str) -> Option<ValueType> {
+    self
+      .scopes
+      .iter()
+      .rev()
+      .find_map(|scope| scope.get(name))
+      .copied()
+      .flatten()
+  }
+
+  fn scoped_block(&mut self, nodes: &[Node]) {
+    self.scopes.push(HashMap::new());
+    for node in nodes {
+      self.node(node);
+    }
+    self.scopes.pop();
+  }
+
+  /// Block with a variable of its own, like the one of a loop
+  fn bound_block(&mut self, name: &str, span: Span, r#type: ValueType, nodes: &[Node]) {
+    self.scopes.push(HashMap::new());
+    self.declare(name, span, Some(r#type));
+    self.scoped_block(nodes);
+    self.scopes.pop();
+  }
+
+  fn node(&mut self, node: &Node) {
+    match &node.kind {
+      NodeKind::Value(value) | NodeKind::Expression(value) | NodeKind::Return(value) => {
+        self.value(value);
+      }
+      NodeKind::Import(Import { path, tree }) => {
+        self.files.push(path.clone());
+        self.scoped_block(tree);
+        self.files.pop();
+      }
+      NodeKind::Block(nodes) => self.scoped_block(nodes),
+      NodeKind::Function(function) => self.function(function),
+      NodeKind::FunctionCall(call) => {
+        self.call(call);
+      }
+      NodeKind::Declaration(Declaration {
+        r#type: declaration,
+        name,
+        value,
+      }) => {
+        let r#type = match &value.kind {
+          NodeKind::Value(value) => self.value(value),
+          _ => None,
+        };
+
+        self.record(node.span, r#type);
+
+        // exported variables outlive the block declaring them
+        let scope = match declaration {
+          Type::Let => self.scopes.last_mut(),
+          Type::Export => self.scopes.first_mut(),
+        };
+        if let Some(scope) = scope {
+          scope.insert(name.clone(), r#type);
+        }
+      }
+      NodeKind::If(r#if) => self.r#if(r#if),
+      NodeKind::IfLet(IfLet {
+        name,
+        call,
+        block,
+        r#else,
+      }) => {
+        self.call(call);
+        self.bound_block(name, node.span, ValueType::String, block);
+
+        if let Some(r#else) = r#else {
+          self.scoped_block(r#else);
+        }
+      }
+      NodeKind::For(r#for) => {
+        let For {
+          start,
+          end,
+          step,
+          variable,
+          block,
+        } = &**r#for;
+
+        for bound in [start, end, step] {
+          self.value(bound);
+        }
+
+        self.scopes.push(HashMap::new());
+        self.declare(variable, node.span, Some(ValueType::Int));
+        self.node(block);
+        self.scopes.pop();
+      }
+      NodeKind::Foreach(Foreach {
+        variable,
+        iterable,
+        block,
+      }) => {
+        self.value(iterable);
+        self.bound_block(variable, node.span, ValueType::String, block);
+      }
+      NodeKind::While(While { condition, block }) => {
+        self.value(condition);
+        self.scoped_block(block);
+      }
+      NodeKind::WhileLet(WhileLet { name, call, block }) => {
+        self.call(call);
+        self.bound_block(name, node.span, ValueType::String, block);
+      }
+      NodeKind::Switch(Switch { arg, cases }) => {
+        self.value(arg);
+
+        for case in cases {
+          self.node(&case.block);
+        }
+      }
+      NodeKind::Try(r#try) => self.r#try(r#try, node.span),
+      NodeKind::Defer(block) | NodeKind::Signal(Signal { block, .. }) => self.scoped_block(block),
+      NodeKind::External(names) => {
+        for name in names {
+          self.declare(name, node.span, None);
+        }
+      }
+      NodeKind::Source(_)
+      | NodeKind::Continue
+      | NodeKind::Break
+      | NodeKind::ExternalFunction(_) => {}
+    }
+  }
+
+  fn function(&mut self, Function { params, block, .. }: &Function) {
+    self.scopes.push(HashMap::new());
+
+    for param in params {
+      if let Some(default) = &param.default {
+        self.value(default);
+      }
+
+      // untyped parameters may be given anything
+      self.declare(&param.variable(), param.span, param.r#type.map(Into::into));
+    }
+
+    // the body shares the scope of the parameters
+    match &block.kind {
+      NodeKind::Block(nodes) => {
+        for node in nodes {
+          self.node(node);
+        }
+      }
+      _ => self.node(block),
+    }
+
+    self.scopes.pop();
+  }
+
+  fn r#try(&mut self, r#try: &Try, span: Span) {
+    let Try {
+      block,
+      error,
+      catch,
+      finally,
+    } = r#try;
+
+    self.scoped_block(block);
+
+    match error {
+      Some(error) => self.bound_block(error, span, ValueType::Int, catch),
+      None => self.scoped_block(catch),
+    }
+
+    if let Some(finally) = finally {
+      self.scoped_block(finally);
+    }
+  }
+
+  fn r#if(&mut self, r#if: &If) {
+    self.value(&r#if.condition);
+    self.scoped_block(&r#if.block);
+
+    match &r#if.r#else {
+      Some(Else::Else(block)) => self.scoped_block(block),
+      Some(Else::Elif(elif)) => self.r#if(elif),
+      None => {}
+    }
+  }
+
+  /// Type of the output of the call
+  fn call(&mut self, call: &FunctionCall) -> ValueType {
+    let arithmetic = self.arithmetic;
+    self.arithmetic = call.name == "$";
+
+    let mut float = false;
+    for arg in &call.args {
+      float |= self.value(arg) == Some(ValueType::Float);
+    }
+
+    self.arithmetic = arithmetic;
+
+    for redirect in &call.redirects {
+      if let Redirect::File(_, file) = redirect {
+        self.value(file);
+      }
+    }
+
+    if let Some(Next::Call(next)) = &call.next {
+      self.call(next);
+    }
+
+    match call.name.as_str() {
+      "$" if float => ValueType::Float,
+      "$" => ValueType::Int,
+      _ => ValueType::String,
+    }
+  }
+
+  fn value(&mut self, value: &Value) -> Option<ValueType> {
+    let r#type = match &value.kind {
+      ValueKind::Literal(literal) => Some(self.literal(literal)),
+      ValueKind::Identifier(name) => self.lookup(name),
+      ValueKind::UnaryExpression(UnaryOperator::Not, value) => {
+        self.value(value);
+        Some(ValueType::Bool)
+      }
+      ValueKind::UnaryExpression(UnaryOperator::Minus, value) => match self.value(value) {
+        Some(ValueType::Float) => Some(ValueType::Float),
+        _ => Some(ValueType::Int),
+      },
+      ValueKind::BinaryExpression(left, operator, right) => {
+        let types = (self.value(left), self.value(right));
+
+        match operator {
+          // outside of arithmetic, `+` joins strings
+          BinaryOperator::Add => match types {
+            (Some(ValueType::Int), Some(ValueType::Int)) => Some(ValueType::Int),
+            (Some(ValueType::Float), _) | (_, Some(ValueType::Float)) if self.arithmetic => {
+              Some(ValueType::Float)
+            }
+            _ if self.arithmetic => Some(ValueType::Int),
+            _ => Some(ValueType::String),
+          },
+          operator if operator.is_arithmetic() => match types {
+            (Some(ValueType::Float), _) | (_, Some(ValueType::Float)) => Some(ValueType::Float),
+            _ => Some(ValueType::Int),
+          },
+          _ => Some(ValueType::Bool),
+        }
+      }
+      ValueKind::TernaryExpression(condition, left, right) => {
+        self.value(condition);
+        let left = self.value(left);
+        let right = self.value(right);
+
+        if left == right {
+          left
+        } else {
+          Some(ValueType::String)
+        }
+      }
+      ValueKind::MemberExpression(left, right) => {
+        self.value(left);
+        self.value(right);
+
+        Some(ValueType::String)
+      }
+      ValueKind::Assignment(left, _, right) => {
+        self.value(left);
+
+        // so the transpiler can check it against the type of the variable
+        let r#type = self.value(right);
+        self.record(right.span, r#type);
+        r#type
+      }
+      ValueKind::FunctionCall(call) => Some(self.call(call)),
+      ValueKind::Run(call) => {
+        self.call(call);
+        Some(ValueType::Result)
+      }
+      ValueKind::Field(value, field) => {
+        self.value(value);
+
+        Some(match field.as_str() {
+          "code" => ValueType::Int,
+          _ => ValueType::String,
+        })
+      }
+      ValueKind::Parenthesized(value) => self.value(value),
+      ValueKind::NamedArgument(_, value) => {
+        self.value(value);
+        None
+      }
+    };
+
+    if let ValueKind::Identifier(_) = value.kind {
+      self.record(value.span, r#type);
+    }
+
+    r#type
+  }
+
+  fn literal(&mut self, literal: &Literal) -> ValueType {
+    match literal {
+      Literal::Int(_) => ValueType::Int,
+      Literal::Float(_) => ValueType::Float,
+      Literal::Bool(_) => ValueType::Bool,
+      Literal::String(_) | Literal::RawString(_) => ValueType::String,
+      Literal::Interpolated(parts) => {
+        for part in parts {
+          match part {
+            StringPart::Text(_) => {}
+            StringPart::Variable(name, span) => self.record(*span, self.lookup(name)),
+            StringPart::Expression(value) => {
+              self.value(value);
+            }
+          }
+        }
+
+        ValueType::String
+      }
+      Literal::Array(values) => {
+        for value in values {
+          self.value(value);
+        }
+
+        ValueType::Array
+      }
+      Literal::Map(entries) => {
+        for (_, value) in entries {
+          self.value(value);
+        }
+
+        ValueType::Map
+      }
+    }
+  }
+}
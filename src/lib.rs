@@ -0,0 +1,60 @@
+//! Compiler from `AlphaShell` to POSIX sh, bash and zsh
+//!
+//! ```
+//! use alpha_shell::{compile, Options, Target};
+//!
+//! let output = compile("echo(\"hi\");", &Options::new(Target::Bash)).unwrap();
+//! assert_eq!(output.code, "echo \"hi\"\n");
+//! ```
+//!
+//! [`parse`] gives the syntax tree instead, made of [`Node`]s and [`Value`]s.
+//!
+//! The default `cli` feature adds what the `ash` and `ash-lsp` binaries use on
+//! top, the formatter and the language server. Turn it off to leave out their
+//! dependencies.
+
+#![warn(clippy::pedantic)]
+#![allow(
+  clippy::implicit_hasher,
+  clippy::missing_errors_doc,
+  clippy::missing_panics_doc,
+  clippy::must_use_candidate,
+  clippy::return_self_not_must_use
+)]
+
+mod compile;
+mod diagnostic;
+#[cfg(feature = "cli")]
+mod format;
+mod infer;
+mod lint;
+#[cfg(feature = "cli")]
+pub mod lsp;
+mod parse;
+mod tokenize;
+mod transpile;
+mod types;
+
+pub use compile::{compile, parse, Options, Output};
+pub use diagnostic::{Diagnostic, Diagnostics, Level};
+#[cfg(feature = "cli")]
+pub use format::format;
+pub use lint::{Lint, LintLevel};
+pub use parse::{
+  block::Block,
+  declaration::{Declaration, Type as DeclarationType},
+  function::{Function, Param, ParamType},
+  function_call::{FunctionCall, Next, Redirect, RedirectKind},
+  import::Import,
+  interpolation::StringPart,
+  node::{Node, NodeKind},
+  r#for::{For, Foreach},
+  r#if::{Else, If, IfLet},
+  r#try::Try,
+  r#while::{While, WhileLet},
+  signal::Signal,
+  switch::{Case, Switch},
+  value::{AssignmentOperator, BinaryOperator, Literal, UnaryOperator, Value, ValueKind},
+};
+pub use transpile::{FloatBackend, Target};
+pub use types::{Position, Span};
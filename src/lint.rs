@@ -0,0 +1,564 @@
+//! Warnings about code that is valid but probably doesn't do what was meant,
+//! found by walking the tree between parsing and transpiling
+
+use std::collections::HashMap;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+use crate::{
+  diagnostic::{Diagnostic, Diagnostics, Level},
+  infer::{infer, Types, ValueType},
+  parse::{
+    declaration::{Declaration, Type},
+    function::Function,
+    function_call::{FunctionCall, Next, Redirect},
+    import::Import,
+    interpolation::StringPart,
+    node::{Node, NodeKind},
+    r#for::{For, Foreach},
+    r#if::{Else, If, IfLet},
+    r#try::Try,
+    r#while::{While, WhileLet},
+    signal::Signal,
+    switch::Switch,
+    value::{AssignmentOperator, BinaryOperator, Literal, UnaryOperator, Value, ValueKind},
+  },
+  types::Span,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Lint {
+  /// Strings in arithmetic, or numbers joined as strings outside of it
+  StringArithmetic,
+  /// Assignment to a variable declared with `export`
+  ReassignedExport,
+  /// Variable declared with `let` that is never read
+  UnusedVariable,
+  /// Statements following `return`, `break` or `continue`
+  UnreachableCode,
+  /// Variable hiding one of an outer block
+  ShadowedVariable,
+}
+
+impl Lint {
+  /// Name taken by `-W` and `-A`
+  pub fn name(self) -> &'static str {
+    match self {
+      Lint::StringArithmetic => "string-arithmetic",
+      Lint::ReassignedExport => "reassigned-export",
+      Lint::UnusedVariable => "unused-variable",
+      Lint::UnreachableCode => "unreachable-code",
+      Lint::ShadowedVariable => "shadowed-variable",
+    }
+  }
+}
+
+/// What becomes of the warnings of a lint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LintLevel {
+  Allow,
+  #[default]
+  Warn,
+  Deny,
+}
+
+/// Drops the warnings of allowed lints and turns the ones of denied lints
+/// into errors, returning the warnings left and the errors
+pub fn apply(
+  diagnostics: Vec<Diagnostic>,
+  levels: &HashMap<Lint, LintLevel>,
+) -> (Vec<Diagnostic>, Diagnostics) {
+  let mut warnings = Vec::new();
+  let mut errors = Vec::new();
+
+  for mut diagnostic in diagnostics {
+    let level = diagnostic
+      .lint
+      .and_then(|lint| levels.get(&lint).copied())
+      .unwrap_or_default();
+
+    match level {
+      LintLevel::Allow => {}
+      LintLevel::Warn => warnings.push(diagnostic),
+      LintLevel::Deny => {
+        diagnostic.level = Level::Error;
+        errors.push(diagnostic);
+      }
+    }
+  }
+
+  (warnings, errors)
+}
+
+/// Warnings for `tree`, in the order of the code they point to
+pub fn check(tree: &[Node]) -> Vec<Diagnostic> {
+  let mut linter = Linter {
+    types: infer(tree),
+    ..Linter::default()
+  };
+
+  linter.push_scope();
+  linter.block(tree);
+  linter.pop_scope();
+
+  let mut diagnostics = linter.diagnostics;
+  diagnostics.sort_by_key(|diagnostic| {
+    (
+      diagnostic.file.clone(),
+      diagnostic.span.map(|span| span.start),
+    )
+  });
+
+  diagnostics
+}
+
+/// How the shell treats a value, which decides what `+` does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+  String,
+  Arithmetic,
+  Condition,
+}
+
+#[derive(Debug)]
+struct Variable {
+  span: Span,
+  /// declared with `let`, so it's expected to be read
+  checked: bool,
+  exported: bool,
+  read: bool,
+}
+
+#[derive(Debug, Default)]
+struct Linter {
+  scopes: Vec<HashMap<String, Variable>>,
+  /// imported files being walked
+  files: Vec<String>,
+  types: Types,
+  diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter {
+  fn warn(&mut self, lint: Lint, mut diagnostic: Diagnostic) {
+    if let Some(file) = self.files.last() {
+      diagnostic = diagnostic.in_file(file);
+    }
+
+    self.diagnostics.push(diagnostic.with_lint(lint));
+  }
+
+  fn push_scope(&mut self) {
+    self.scopes.push(HashMap::new());
+  }
+
+  fn pop_scope(&mut self) {
+    let scope = self.scopes.pop().unwrap_or_default();
+
+    for (name, variable) in scope {
+      self.check_read(&name, &variable);
+    }
+  }
+
+  fn check_read(&mut self, name: &str, variable: &Variable) {
+    if variable.checked && !variable.read {
+      self.warn(
+        Lint::UnusedVariable,
+        Diagnostic::warning(&format!("Unused variable {name}"))
+          .with_span(variable.span)
+          .with_help(&format!(
+            "remove it, or name it `_{name}` if it's unused on purpose"
+          )),
+      );
+    }
+  }
+
+  fn declare(&mut self, name: &str, span: Span, checked: bool) {
+    let variable = Variable {
+      span,
+      checked: checked && !name.starts_with('_'),
+      exported: false,
+      read: false,
+    };
+
+    let Some(scope) = self.scopes.last_mut() else {
+      return;
+    };
+
+    if let Some(previous) = scope.insert(name.to_owned(), variable) {
+      self.check_read(name, &previous);
+    }
+  }
+
+  /// Exported variables outlive the block declaring them
+  fn export(&mut self, name: &str, span: Span) {
+    if let Some(scope) = self.scopes.first_mut() {
+      scope.insert(
+        name.to_owned(),
+        Variable {
+          span,
+          checked: false,
+          exported: true,
+          read: false,
+        },
+      );
+    }
+  }
+
+  fn lookup(&mut self, name: &str) -> Option<&mut Variable> {
+    self
+      .scopes
+      .iter_mut()
+      .rev()
+      .find_map(|scope| scope.get_mut(name))
+  }
+
+  fn read(&mut self, name: &str) {
+    if let Some(variable) = self.lookup(name) {
+      variable.read = true;
+    }
+  }
+
+  fn scoped_block(&mut self, nodes: &[Node]) {
+    self.push_scope();
+    self.block(nodes);
+    self.pop_scope();
+  }
+
+  fn block(&mut self, nodes: &[Node]) {
+    let mut unreachable = false;
+
+    for (i, node) in nodes.iter().enumerate() {
+      self.node(node);
+
+      let keyword = match node.kind {
+        NodeKind::Return(_) => "return",
+        NodeKind::Break => "break",
+        NodeKind::Continue => "continue",
+        _ => continue,
+      };
+
+      if let (false, Some(next), Some(last)) = (unreachable, nodes.get(i + 1), nodes.last()) {
+        unreachable = true;
+
+        self.warn(
+          Lint::UnreachableCode,
+          Diagnostic::warning("Unreachable code")
+            .with_span(Span::new(next.span.start, last.span.end))
+            .with_note(&format!("it follows a `{keyword}`")),
+        );
+      }
+    }
+  }
+
+  fn node(&mut self, node: &Node) {
+    match &node.kind {
+      NodeKind::Value(value) | NodeKind::Expression(value) | NodeKind::Return(value) => {
+        self.value(value, Context::String);
+      }
+      // a sourced script can read any of them
+      NodeKind::Source(_) => {
+        for scope in &mut self.scopes {
+          for variable in scope.values_mut() {
+            variable.read = true;
+          }
+        }
+      }
+      NodeKind::Import(Import { path, tree }) => {
+        self.files.push(path.clone());
+        self.scoped_block(tree);
+        self.files.pop();
+      }
+      NodeKind::Block(nodes) => self.scoped_block(nodes),
+      NodeKind::Function(function) => self.function(function),
+      NodeKind::FunctionCall(call) => self.call(call),
+      NodeKind::Declaration(Declaration {
+        r#type,
+        name,
+        value,
+      }) => {
+        self.node(value);
+
+        match r#type {
+          Type::Let => self.declare(name, node.span, true),
+          Type::Export => self.export(name, node.span),
+        }
+      }
+      NodeKind::If(r#if) => self.r#if(r#if),
+      NodeKind::IfLet(IfLet {
+        name,
+        call,
+        block,
+        r#else,
+      }) => {
+        self.call(call);
+        self.bound_block(name, node.span, true, block);
+
+        if let Some(r#else) = r#else {
+          self.scoped_block(r#else);
+        }
+      }
+      NodeKind::For(r#for) => {
+        let For {
+          start,
+          end,
+          step,
+          variable,
+          block,
+        } = &**r#for;
+
+        for bound in [start, end, step] {
+          self.value(bound, Context::Arithmetic);
+        }
+
+        self.push_scope();
+        self.declare(variable, node.span, false);
+        self.node(block);
+        self.pop_scope();
+      }
+      NodeKind::Foreach(Foreach {
+        variable,
+        iterable,
+        block,
+      }) => {
+        self.value(iterable, Context::String);
+        self.bound_block(variable, node.span, false, block);
+      }
+      NodeKind::While(While { condition, block }) => {
+        self.value(condition, Context::Condition);
+        self.scoped_block(block);
+      }
+      NodeKind::WhileLet(WhileLet { name, call, block }) => {
+        self.call(call);
+        self.bound_block(name, node.span, true, block);
+      }
+      NodeKind::Switch(Switch { arg, cases }) => {
+        self.value(arg, Context::String);
+
+        for case in cases {
+          self.node(&case.block);
+        }
+      }
+      NodeKind::Try(r#try) => self.r#try(r#try, node.span),
+      NodeKind::Defer(block) | NodeKind::Signal(Signal { block, .. }) => self.scoped_block(block),
+      NodeKind::External(names) => {
+        for name in names {
+          self.declare(name, node.span, false);
+        }
+      }
+      NodeKind::Continue | NodeKind::Break | NodeKind::ExternalFunction(_) => {}
+    }
+  }
+
+  fn function(&mut self, Function { params, block, .. }: &Function) {
+    self.push_scope();
+
+    for param in params {
+      if let Some(default) = &param.default {
+        self.value(default, Context::String);
+      }
+      self.declare(&param.variable(), param.span, false);
+    }
+
+    // the body shares the scope of the parameters
+    match &block.kind {
+      NodeKind::Block(nodes) => self.block(nodes),
+      _ => self.node(block),
+    }
+
+    self.pop_scope();
+  }
+
+  /// Block with a variable of its own, like the one of a loop
+  fn bound_block(&mut self, name: &str, span: Span, checked: bool, nodes: &[Node]) {
+    self.push_scope();
+    self.declare(name, span, checked);
+    self.scoped_block(nodes);
+    self.pop_scope();
+  }
+
+  fn r#try(&mut self, r#try: &Try, span: Span) {
+    let Try {
+      block,
+      error,
+      catch,
+      finally,
+    } = r#try;
+
+    self.scoped_block(block);
+
+    match error {
+      Some(error) => self.bound_block(error, span, true, catch),
+      None => self.scoped_block(catch),
+    }
+
+    if let Some(finally) = finally {
+      self.scoped_block(finally);
+    }
+  }
+
+  fn r#if(&mut self, r#if: &If) {
+    self.value(&r#if.condition, Context::Condition);
+    self.scoped_block(&r#if.block);
+
+    match &r#if.r#else {
+      Some(Else::Else(block)) => self.scoped_block(block),
+      Some(Else::Elif(elif)) => self.r#if(elif),
+      None => {}
+    }
+  }
+
+  fn call(&mut self, call: &FunctionCall) {
+    let context = if call.name == "$" {
+      Context::Arithmetic
+    } else {
+      Context::String
+    };
+
+    for arg in &call.args {
+      self.value(arg, context);
+    }
+
+    for redirect in &call.redirects {
+      if let Redirect::File(_, file) = redirect {
+        self.value(file, Context::String);
+      }
+    }
+
+    if let Some(Next::Call(next)) = &call.next {
+      self.call(next);
+    }
+  }
+
+  fn value(&mut self, value: &Value, context: Context) {
+    match &value.kind {
+      ValueKind::Literal(Literal::Interpolated(parts)) => {
+        for part in parts {
+          match part {
+            StringPart::Text(_) => {}
+            StringPart::Variable(name, _) => self.read(name),
+            StringPart::Expression(value) => self.value(value, Context::String),
+          }
+        }
+      }
+      ValueKind::Literal(Literal::Array(values)) => {
+        for value in values {
+          self.value(value, context);
+        }
+      }
+      ValueKind::Literal(Literal::Map(entries)) => {
+        for (_, value) in entries {
+          self.value(value, context);
+        }
+      }
+      ValueKind::Literal(_) => {}
+      ValueKind::Identifier(name) => self.read(name),
+      ValueKind::UnaryExpression(_, value) | ValueKind::Parenthesized(value) => {
+        self.value(value, context);
+      }
+      ValueKind::BinaryExpression(left, operator, right) => {
+        if *operator == BinaryOperator::Add
+          && context == Context::String
+          && (is_number(left) || is_number(right))
+        {
+          self.warn_concatenation("`+` joins strings outside of arithmetic", value.span);
+        }
+
+        // the other operators switch to arithmetic on their own
+        let context = if context == Context::String && operator.is_arithmetic() {
+          match operator {
+            BinaryOperator::Add => Context::String,
+            _ => Context::Arithmetic,
+          }
+        } else {
+          context
+        };
+
+        self.value(left, context);
+        self.value(right, context);
+      }
+      ValueKind::TernaryExpression(condition, left, right) => {
+        self.value(condition, Context::Condition);
+        self.value(left, context);
+        self.value(right, context);
+      }
+      ValueKind::MemberExpression(left, right) => {
+        self.value(left, context);
+        self.value(right, Context::String);
+      }
+      ValueKind::Assignment(left, operator, right) => {
+        self.assignment(left, operator, right, context, value.span);
+      }
+      ValueKind::FunctionCall(call) | ValueKind::Run(call) => self.call(call),
+      ValueKind::NamedArgument(_, value) => self.value(value, Context::String),
+      ValueKind::Field(value, _) => self.value(value, context),
+    }
+  }
+
+  fn assignment(
+    &mut self,
+    left: &Value,
+    operator: &AssignmentOperator,
+    right: &Value,
+    context: Context,
+    span: Span,
+  ) {
+    let ValueKind::Identifier(name) = &left.kind else {
+      // assigning to an element reads the array or map
+      self.value(left, context);
+      self.value(right, context);
+      return;
+    };
+
+    if *operator != AssignmentOperator::Assignment {
+      self.read(name);
+    }
+
+    if self.lookup(name).is_some_and(|variable| variable.exported) {
+      self.warn(
+        Lint::ReassignedExport,
+        Diagnostic::warning(&format!("Exported variable {name} is reassigned"))
+          .with_span(span)
+          .with_note("every command run from here on sees the new value"),
+      );
+    }
+
+    // ints are added to in any context
+    let is_int = self
+      .types
+      .get(self.files.last().map(String::as_str), left.span)
+      == Some(ValueType::Int);
+
+    if *operator == AssignmentOperator::AddAssignment
+      && context == Context::String
+      && !is_int
+      && is_number(right)
+    {
+      self.warn_concatenation("`+=` appends to the string outside of arithmetic", span);
+    }
+
+    self.value(right, context);
+  }
+
+  fn warn_concatenation(&mut self, message: &str, span: Span) {
+    self.warn(
+      Lint::StringArithmetic,
+      Diagnostic::warning(message)
+        .with_span(span)
+        .with_help("wrap it in `$(...)` to add numbers"),
+    );
+  }
+}
+
+/// Literal that reads as a number
+fn is_number(value: &Value) -> bool {
+  match &value.kind {
+    ValueKind::Literal(Literal::Int(_) | Literal::Float(_)) => true,
+    ValueKind::Literal(Literal::String(string) | Literal::RawString(string)) => {
+      string.parse::<f64>().is_ok()
+    }
+    ValueKind::UnaryExpression(UnaryOperator::Minus, value) => is_number(value),
+    _ => false,
+  }
+}
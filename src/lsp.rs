@@ -0,0 +1,469 @@
+//! Language server speaking LSP over stdio, run by `ash-lsp`
+
+mod analysis;
+
+use std::{collections::HashMap, error::Error, fs};
+
+use lsp_server::{Connection, ExtractError, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+  notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+  },
+  request::{Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest},
+  CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse, DiagnosticSeverity,
+  DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams,
+  GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability,
+  InitializeParams, Location, MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams, Range,
+  ServerCapabilities, SymbolKind, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+
+use self::analysis::{contains, Analysis, Definition, DefinitionKind};
+use crate::{
+  diagnostic::{Diagnostic, Level},
+  transpile::Target,
+  types::{Position, Span},
+};
+use clap::ValueEnum;
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+struct Document {
+  text: String,
+  analysis: Analysis,
+  /// Definitions of the last version that parsed, so navigation keeps
+  /// working while typing
+  definitions: Vec<Definition>,
+}
+
+impl Document {
+  fn new(text: String, target: Target, previous: Option<Document>) -> Self {
+    let analysis = Analysis::new(&text, target);
+    let definitions = match (&analysis.definitions, previous) {
+      (Some(definitions), _) => definitions.clone(),
+      (None, Some(previous)) => previous.definitions,
+      (None, None) => vec![],
+    };
+
+    Self {
+      text,
+      analysis,
+      definitions,
+    }
+  }
+
+  fn definition_at(&self, position: Position) -> Option<&Definition> {
+    if self.analysis.definitions.is_some() {
+      return self.analysis.definition_at(position);
+    }
+
+    let (name, _) = self.analysis.identifier_at(position)?;
+
+    self
+      .definitions
+      .iter()
+      .filter(|definition| definition.name == name)
+      .max_by_key(|definition| definition.scope.start)
+  }
+
+  fn completions_at(&self, position: Position) -> Vec<&Definition> {
+    match self.analysis.definitions {
+      Some(_) => self.analysis.completions_at(position),
+      None => self.definitions.iter().collect(),
+    }
+  }
+}
+
+struct Server {
+  connection: Connection,
+  target: Target,
+  documents: HashMap<Url, Document>,
+}
+
+/// Serves the client connected to stdin and stdout until it exits
+pub fn run() -> Result<()> {
+  let (connection, io_threads) = Connection::stdio();
+
+  let capabilities = serde_json::to_value(ServerCapabilities {
+    text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+    definition_provider: Some(OneOf::Left(true)),
+    hover_provider: Some(HoverProviderCapability::Simple(true)),
+    completion_provider: Some(lsp_types::CompletionOptions::default()),
+    document_symbol_provider: Some(OneOf::Left(true)),
+    ..ServerCapabilities::default()
+  })?;
+  let params: InitializeParams = serde_json::from_value(connection.initialize(capabilities)?)?;
+
+  // `{ "target": "bash" }` picks the shell warnings are reported for
+  let target = params
+    .initialization_options
+    .as_ref()
+    .and_then(|options| options.get("target")?.as_str())
+    .and_then(|target| Target::from_str(target, true).ok())
+    .unwrap_or_default();
+
+  let mut server = Server {
+    connection,
+    target,
+    documents: HashMap::new(),
+  };
+  server.run()?;
+
+  drop(server);
+  io_threads.join()?;
+
+  Ok(())
+}
+
+impl Server {
+  fn run(&mut self) -> Result<()> {
+    while let Ok(message) = self.connection.receiver.recv() {
+      match message {
+        Message::Request(request) => {
+          if self.connection.handle_shutdown(&request)? {
+            return Ok(());
+          }
+          self.request(request)?;
+        }
+        Message::Notification(notification) => self.notification(notification)?,
+        Message::Response(_) => {}
+      }
+    }
+
+    Ok(())
+  }
+
+  fn request(&mut self, request: Request) -> Result<()> {
+    let request = match cast::<GotoDefinition>(request) {
+      Ok((id, params)) => return self.respond::<GotoDefinition>(id, self.definition(&params)),
+      Err(request) => request,
+    };
+    let request = match cast::<HoverRequest>(request) {
+      Ok((id, params)) => return self.respond::<HoverRequest>(id, self.hover(&params)),
+      Err(request) => request,
+    };
+    let request = match cast::<Completion>(request) {
+      Ok((id, params)) => return self.respond::<Completion>(id, self.completion(&params)),
+      Err(request) => request,
+    };
+    let request = match cast::<DocumentSymbolRequest>(request) {
+      Ok((id, params)) => {
+        return self.respond::<DocumentSymbolRequest>(id, self.document_symbols(&params))
+      }
+      Err(request) => request,
+    };
+
+    let response = Response::new_err(
+      request.id,
+      lsp_server::ErrorCode::MethodNotFound as i32,
+      format!("Unsupported request {}", request.method),
+    );
+    self.connection.sender.send(response.into())?;
+
+    Ok(())
+  }
+
+  fn respond<R: lsp_types::request::Request>(
+    &self,
+    id: RequestId,
+    result: R::Result,
+  ) -> Result<()> {
+    let response = Response::new_ok(id, result);
+    self.connection.sender.send(response.into())?;
+
+    Ok(())
+  }
+
+  fn notification(&mut self, notification: Notification) -> Result<()> {
+    match notification.method.as_str() {
+      DidOpenTextDocument::METHOD => {
+        let params: lsp_types::DidOpenTextDocumentParams =
+          serde_json::from_value(notification.params)?;
+        self.update(params.text_document.uri, params.text_document.text)
+      }
+      DidChangeTextDocument::METHOD => {
+        let params: lsp_types::DidChangeTextDocumentParams =
+          serde_json::from_value(notification.params)?;
+        // the whole document is sent with every change
+        match params.content_changes.into_iter().last() {
+          Some(change) => self.update(params.text_document.uri, change.text),
+          None => Ok(()),
+        }
+      }
+      DidCloseTextDocument::METHOD => {
+        let params: lsp_types::DidCloseTextDocumentParams =
+          serde_json::from_value(notification.params)?;
+        self.documents.remove(&params.text_document.uri);
+        self.publish(params.text_document.uri, vec![])
+      }
+      _ => Ok(()),
+    }
+  }
+
+  fn update(&mut self, uri: Url, text: String) -> Result<()> {
+    let previous = self.documents.remove(&uri);
+    let document = Document::new(text, self.target, previous);
+
+    let diagnostics = document
+      .analysis
+      .diagnostics
+      .iter()
+      .map(|diagnostic| convert_diagnostic(diagnostic, &document))
+      .collect();
+
+    self.documents.insert(uri.clone(), document);
+    self.publish(uri, diagnostics)
+  }
+
+  fn publish(&self, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Result<()> {
+    let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+    let notification = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+    self.connection.sender.send(notification.into())?;
+
+    Ok(())
+  }
+
+  /// Document and cursor position of a request
+  fn locate(&self, uri: &Url, position: lsp_types::Position) -> Option<(&Document, Position)> {
+    let document = self.documents.get(uri)?;
+    let position = from_lsp_position(&document.text, position);
+
+    Some((document, position))
+  }
+
+  fn definition(&self, params: &GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+    let params = &params.text_document_position_params;
+    let uri = &params.text_document.uri;
+    let (document, position) = self.locate(uri, params.position)?;
+    let definition = document.definition_at(position)?;
+
+    let location = match &definition.file {
+      Some(file) => {
+        let text = fs::read_to_string(file).ok()?;
+        Location::new(
+          Url::from_file_path(file).ok()?,
+          to_lsp_range(&text, definition.selection),
+        )
+      }
+      None => Location::new(
+        uri.clone(),
+        to_lsp_range(&document.text, definition.selection),
+      ),
+    };
+
+    Some(GotoDefinitionResponse::Scalar(location))
+  }
+
+  fn hover(&self, params: &HoverParams) -> Option<Hover> {
+    let params = &params.text_document_position_params;
+    let (document, position) = self.locate(&params.text_document.uri, params.position)?;
+    let definition = document.definition_at(position)?;
+    let (_, span) = document.analysis.identifier_at(position)?;
+
+    Some(Hover {
+      contents: HoverContents::Markup(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value: format!("```ash\n{}\n```", definition.signature()),
+      }),
+      range: Some(to_lsp_range(&document.text, span)),
+    })
+  }
+
+  fn completion(&self, params: &CompletionParams) -> Option<CompletionResponse> {
+    let params = &params.text_document_position;
+    let (document, position) = self.locate(&params.text_document.uri, params.position)?;
+
+    let items = document
+      .completions_at(position)
+      .into_iter()
+      .map(|definition| CompletionItem {
+        label: definition.name.clone(),
+        kind: Some(match definition.kind {
+          DefinitionKind::Function(_)
+          | DefinitionKind::External
+          | DefinitionKind::ExternalFunction => CompletionItemKind::FUNCTION,
+          _ => CompletionItemKind::VARIABLE,
+        }),
+        detail: Some(definition.signature()),
+        ..CompletionItem::default()
+      })
+      .collect();
+
+    Some(CompletionResponse::Array(items))
+  }
+
+  fn document_symbols(&self, params: &DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+    let document = self.documents.get(&params.text_document.uri)?;
+
+    let definitions: Vec<_> = document
+      .definitions
+      .iter()
+      .filter(|definition| {
+        definition.file.is_none()
+          && matches!(
+            definition.kind,
+            DefinitionKind::Function(_) | DefinitionKind::Variable | DefinitionKind::Export
+          )
+      })
+      .collect();
+
+    Some(DocumentSymbolResponse::Nested(symbols(
+      &definitions,
+      None,
+      &document.text,
+    )))
+  }
+}
+
+fn cast<R>(request: Request) -> std::result::Result<(RequestId, R::Params), Request>
+where
+  R: lsp_types::request::Request,
+{
+  match request.extract(R::METHOD) {
+    Ok(request) => Ok(request),
+    Err(ExtractError::MethodMismatch(request)) => Err(request),
+    // the id is lost with malformed params, so the request is dropped
+    Err(ExtractError::JsonError { method, error }) => {
+      eprintln!("Invalid params for {method}: {error}");
+      Err(Request::new(
+        RequestId::from(0),
+        method,
+        serde_json::Value::Null,
+      ))
+    }
+  }
+}
+
+/// Symbols directly inside `parent`, functions containing their locals
+#[allow(deprecated)]
+fn symbols(definitions: &[&Definition], parent: Option<Span>, text: &str) -> Vec<DocumentSymbol> {
+  // innermost function around the definition
+  let owner = |definition: &Definition| {
+    definitions
+      .iter()
+      .filter(|function| {
+        matches!(function.kind, DefinitionKind::Function(_))
+          && function.range != definition.range
+          && contains(function.range, definition.range.start)
+      })
+      .map(|function| function.range)
+      .max_by_key(|range| range.start)
+  };
+
+  definitions
+    .iter()
+    .filter(|definition| owner(definition) == parent)
+    .map(|definition| {
+      let children = match definition.kind {
+        DefinitionKind::Function(_) => Some(symbols(definitions, Some(definition.range), text)),
+        _ => None,
+      };
+
+      DocumentSymbol {
+        name: definition.name.clone(),
+        detail: Some(definition.signature()),
+        kind: match definition.kind {
+          DefinitionKind::Function(_) => SymbolKind::FUNCTION,
+          _ => SymbolKind::VARIABLE,
+        },
+        tags: None,
+        deprecated: None,
+        range: to_lsp_range(text, definition.range),
+        selection_range: to_lsp_range(text, definition.selection),
+        children,
+      }
+    })
+    .collect()
+}
+
+fn convert_diagnostic(diagnostic: &Diagnostic, document: &Document) -> lsp_types::Diagnostic {
+  let notes = diagnostic.notes.iter().map(|note| format!("note: {note}"));
+  let help = diagnostic.help.iter().map(|help| format!("help: {help}"));
+  let message: Vec<_> = [&diagnostic.message]
+    .into_iter()
+    .chain(&diagnostic.label)
+    .cloned()
+    .chain(notes)
+    .chain(help)
+    .collect();
+  let mut message = message.join("\n");
+
+  // errors in imported files are shown on the import
+  let range = match (&diagnostic.file, diagnostic.span) {
+    (Some(file), span) => {
+      if let Some(span) = span {
+        message = format!("{file}:{span}: {message}");
+      } else {
+        message = format!("{file}: {message}");
+      }
+
+      document
+        .analysis
+        .tokens
+        .iter()
+        .find(|token| matches!(&token.r#type, crate::types::TT::String(path) if path == file))
+        .map_or_else(Range::default, |token| {
+          to_lsp_range(&document.text, token.span)
+        })
+    }
+    (None, Some(span)) => to_lsp_range(&document.text, span),
+    (None, None) => Range::default(),
+  };
+
+  lsp_types::Diagnostic {
+    range,
+    severity: Some(match diagnostic.level {
+      Level::Error => DiagnosticSeverity::ERROR,
+      Level::Warning => DiagnosticSeverity::WARNING,
+    }),
+    source: Some("ash".to_owned()),
+    message,
+    ..lsp_types::Diagnostic::default()
+  }
+}
+
+/// Editors count columns in UTF-16 code units, the lexer in chars
+fn to_lsp_position(text: &str, Position(line, column): Position) -> lsp_types::Position {
+  let Some(text) = text.lines().nth(line) else {
+    let last = text.lines().count();
+    return lsp_types::Position::new(u32::try_from(last).unwrap_or(u32::MAX), 0);
+  };
+
+  let character: usize = text.chars().take(column).map(char::len_utf16).sum();
+
+  lsp_types::Position::new(
+    u32::try_from(line).unwrap_or(u32::MAX),
+    u32::try_from(character).unwrap_or(u32::MAX),
+  )
+}
+
+fn to_lsp_range(text: &str, span: Span) -> Range {
+  let start = to_lsp_position(text, span.start);
+  let mut end = to_lsp_position(text, span.end);
+
+  // point at least at one character
+  if end == start {
+    end.character += 1;
+  }
+
+  Range::new(start, end)
+}
+
+fn from_lsp_position(text: &str, position: lsp_types::Position) -> Position {
+  let line = position.line as usize;
+  let character = position.character as usize;
+
+  let column = text.lines().nth(line).map_or(0, |text| {
+    let mut units = 0;
+
+    text
+      .chars()
+      .take_while(|c| {
+        units += c.len_utf16();
+        units <= character
+      })
+      .count()
+  });
+
+  Position(line, column)
+}
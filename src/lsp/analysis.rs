@@ -0,0 +1,465 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+  diagnostic::Diagnostic,
+  lint,
+  parse::{
+    self,
+    declaration::{Declaration, Type},
+    function::Function,
+    import::Import,
+    node::{Node, NodeKind},
+    r#if::{Else, If},
+    r#try::Try,
+    signal::Signal,
+  },
+  tokenize::tokenize,
+  transpile::{transpile, Target},
+  types::{Position, Span, StringToken, Token, TT},
+};
+
+/// End of any document, used for names visible until the end of the file
+const END: Position = Position(usize::MAX, 0);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefinitionKind {
+  /// Parameters as written
+  Function(Vec<String>),
+  Variable,
+  Export,
+  Parameter,
+  LoopVariable,
+  External,
+  ExternalFunction,
+}
+
+#[derive(Debug, Clone)]
+pub struct Definition {
+  pub name: String,
+  pub kind: DefinitionKind,
+  /// The name itself
+  pub selection: Span,
+  /// The whole statement defining the name
+  pub range: Span,
+  /// Where the name can be used
+  pub scope: Span,
+  /// Imported file the definition comes from, `None` for the document
+  pub file: Option<PathBuf>,
+}
+
+impl Definition {
+  /// Line shown when hovering or completing the name
+  pub fn signature(&self) -> String {
+    let name = &self.name;
+
+    match &self.kind {
+      DefinitionKind::Function(params) => format!("fn {name}({})", params.join(", ")),
+      DefinitionKind::Variable => format!("let {name}"),
+      DefinitionKind::Export => format!("export {name}"),
+      DefinitionKind::Parameter => format!("(parameter) {name}"),
+      DefinitionKind::LoopVariable => format!("(loop variable) {name}"),
+      DefinitionKind::External => format!("external {name}"),
+      DefinitionKind::ExternalFunction => format!("external fn {name}"),
+    }
+  }
+
+  fn visible_at(&self, position: Position) -> bool {
+    contains(self.scope, position)
+  }
+}
+
+/// Everything the server knows about one version of a document
+#[derive(Debug, Default)]
+pub struct Analysis {
+  pub tokens: Vec<Token>,
+  pub diagnostics: Vec<Diagnostic>,
+  /// `None` when the document doesn't parse
+  pub definitions: Option<Vec<Definition>>,
+}
+
+impl Analysis {
+  pub fn new(text: &str, target: Target) -> Self {
+    let tokens = match tokenize(text) {
+      Ok(tokens) => tokens,
+      Err(error) => {
+        return Self {
+          diagnostics: vec![error.into()],
+          ..Self::default()
+        }
+      }
+    };
+
+    let tree = match parse::parse(&tokens) {
+      Ok(tree) => tree,
+      Err(errors) => {
+        return Self {
+          tokens,
+          diagnostics: errors.into_iter().map(Into::into).collect(),
+          definitions: None,
+        }
+      }
+    };
+
+    let diagnostics = match transpile(&tree, target) {
+      Ok((_, warnings)) => lint::check(&tree).into_iter().chain(warnings).collect(),
+      Err(error) => vec![error.into()],
+    };
+
+    let mut collector = Collector::new(text, &tokens, None);
+    collector.collect(&tree);
+
+    Self {
+      definitions: Some(collector.definitions),
+      tokens,
+      diagnostics,
+    }
+  }
+
+  /// Identifier the cursor is on, interpolated ones included
+  pub fn identifier_at(&self, position: Position) -> Option<(&str, Span)> {
+    identifier_in(&self.tokens, position)
+  }
+
+  /// Definition the name under the cursor refers to, the innermost one if
+  /// it's shadowed
+  pub fn definition_at(&self, position: Position) -> Option<&Definition> {
+    let (name, span) = self.identifier_at(position)?;
+
+    self
+      .definitions
+      .iter()
+      .flatten()
+      .filter(|definition| definition.name == name)
+      .filter(|definition| definition.selection == span || definition.visible_at(span.start))
+      .max_by_key(|definition| definition.scope.start)
+  }
+
+  /// Names that can be used at the cursor
+  pub fn completions_at(&self, position: Position) -> Vec<&Definition> {
+    let mut visible: Vec<&Definition> = Vec::new();
+
+    for definition in self.definitions.iter().flatten() {
+      if !definition.visible_at(position) {
+        continue;
+      }
+
+      match visible
+        .iter_mut()
+        .find(|other| other.name == definition.name)
+      {
+        Some(other) if other.scope.start < definition.scope.start => *other = definition,
+        Some(_) => {}
+        None => visible.push(definition),
+      }
+    }
+
+    visible
+  }
+}
+
+fn identifier_in(tokens: &[Token], position: Position) -> Option<(&str, Span)> {
+  tokens
+    .iter()
+    .filter(|token| contains(token.span, position))
+    .find_map(|token| match &token.r#type {
+      TT::Identifier(name) => Some((name.as_str(), token.span)),
+      TT::InterpolatedString(parts) => parts.iter().find_map(|part| match part {
+        StringToken::Variable(name, span) if contains(*span, position) => {
+          Some((name.as_str(), *span))
+        }
+        StringToken::Expression(tokens) => identifier_in(tokens, position),
+        _ => None,
+      }),
+      _ => None,
+    })
+}
+
+pub fn contains(span: Span, position: Position) -> bool {
+  span.start <= position && position <= span.end
+}
+
+struct Collector<'a> {
+  text: &'a str,
+  tokens: &'a [Token],
+  /// Regions between matching braces
+  blocks: Vec<Span>,
+  file: Option<PathBuf>,
+  definitions: Vec<Definition>,
+}
+
+impl<'a> Collector<'a> {
+  fn new(text: &'a str, tokens: &'a [Token], file: Option<PathBuf>) -> Self {
+    let mut blocks = Vec::new();
+    let mut open = Vec::new();
+
+    for token in tokens {
+      match token.r#type {
+        TT::LBrace => open.push(token.span.start),
+        TT::RBrace => {
+          if let Some(start) = open.pop() {
+            blocks.push(Span::new(start, token.span.end));
+          }
+        }
+        _ => {}
+      }
+    }
+
+    Self {
+      text,
+      tokens,
+      blocks,
+      file,
+      definitions: Vec::new(),
+    }
+  }
+
+  /// Innermost block around `position`, or the whole file
+  fn enclosing_block(&self, position: Position) -> Span {
+    self
+      .blocks
+      .iter()
+      .filter(|block| block.start < position && position < block.end)
+      .max_by_key(|block| block.start)
+      .copied()
+      .unwrap_or(Span::new(Position::default(), END))
+  }
+
+  /// The block following `position`, like the body of a loop
+  fn block_after(&self, position: Position) -> Span {
+    self
+      .blocks
+      .iter()
+      .filter(|block| block.start >= position)
+      .min_by_key(|block| block.start)
+      .copied()
+      .unwrap_or(Span::new(position, END))
+  }
+
+  /// Source text inside `span`
+  fn text(&self, span: Span) -> String {
+    let Span { start, end } = span;
+
+    self
+      .text
+      .lines()
+      .enumerate()
+      .skip(start.0)
+      .take(end.0 + 1 - start.0)
+      .map(|(i, line)| {
+        let from = if i == start.0 { start.1 } else { 0 };
+        let to = if i == end.0 { end.1 } else { usize::MAX };
+        line.chars().take(to).skip(from).collect::<String>()
+      })
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+
+  /// Span of the first `name` identifier at or after `position`
+  fn name_after(&self, position: Position, name: &str) -> Span {
+    self
+      .tokens
+      .iter()
+      .find(|token| {
+        token.span.start >= position
+          && matches!(&token.r#type, TT::Identifier(other) | TT::String(other) if other == name)
+      })
+      .map_or(Span::new(position, position), |token| token.span)
+  }
+
+  fn define(
+    &mut self,
+    name: &str,
+    kind: DefinitionKind,
+    selection: Span,
+    range: Span,
+    scope: Span,
+  ) {
+    self.definitions.push(Definition {
+      name: name.to_owned(),
+      kind,
+      selection,
+      range,
+      scope,
+      file: self.file.clone(),
+    });
+  }
+
+  fn collect(&mut self, nodes: &[Node]) {
+    for node in nodes {
+      self.collect_node(node);
+    }
+  }
+
+  fn collect_node(&mut self, node: &Node) {
+    let start = node.span.start;
+
+    match &node.kind {
+      NodeKind::Declaration(Declaration { r#type, name, .. }) => {
+        let selection = self.name_after(start, name);
+
+        let (kind, end) = match r#type {
+          // exports leak out of blocks
+          Type::Export => (DefinitionKind::Export, END),
+          Type::Let => (DefinitionKind::Variable, self.enclosing_block(start).end),
+        };
+
+        self.define(
+          name,
+          kind,
+          selection,
+          node.span,
+          Span::new(selection.start, end),
+        );
+      }
+      NodeKind::Function(Function {
+        name,
+        params,
+        block,
+      }) => {
+        let selection = self.name_after(start, name);
+        let scope = self.enclosing_block(start);
+
+        let signature = params.iter().map(|param| self.text(param.span)).collect();
+        self.define(
+          name,
+          DefinitionKind::Function(signature),
+          selection,
+          node.span,
+          scope,
+        );
+
+        for param in params {
+          // the name, with the dashes of a named parameter
+          let Position(line, column) = param.span.start;
+          let length = param.name.chars().count() + if param.named { 2 } else { 0 };
+          let param_selection = Span::new(param.span.start, Position(line, column + length));
+
+          let body = self.block_after(param_selection.end);
+          let scope = Span::new(param_selection.start, body.end);
+
+          self.define(
+            &param.variable(),
+            DefinitionKind::Parameter,
+            param_selection,
+            param_selection,
+            scope,
+          );
+        }
+
+        self.collect_node(block);
+      }
+      NodeKind::Block(nodes) => self.collect(nodes),
+      NodeKind::If(r#if) => self.collect_if(r#if),
+      NodeKind::IfLet(if_let) => {
+        self.define_bound(&if_let.name, DefinitionKind::Variable, start);
+        self.collect(&if_let.block);
+        if let Some(block) = &if_let.r#else {
+          self.collect(block);
+        }
+      }
+      NodeKind::For(r#for) => {
+        self.define_bound(&r#for.variable, DefinitionKind::LoopVariable, start);
+        self.collect_node(&r#for.block);
+      }
+      NodeKind::Foreach(foreach) => {
+        self.define_bound(&foreach.variable, DefinitionKind::LoopVariable, start);
+        self.collect(&foreach.block);
+      }
+      NodeKind::While(r#while) => self.collect(&r#while.block),
+      NodeKind::WhileLet(while_let) => {
+        self.define_bound(&while_let.name, DefinitionKind::Variable, start);
+        self.collect(&while_let.block);
+      }
+      NodeKind::Switch(switch) => {
+        for case in &switch.cases {
+          self.collect_node(&case.block);
+        }
+      }
+      NodeKind::Defer(block) | NodeKind::Signal(Signal { block, .. }) => self.collect(block),
+      NodeKind::Try(r#try) => self.collect_try(r#try, start),
+      NodeKind::Import(Import { path, tree }) => self.collect_import(path, tree, node.span),
+      NodeKind::External(names) | NodeKind::ExternalFunction(names) => {
+        let kind = match node.kind {
+          NodeKind::External(_) => DefinitionKind::External,
+          _ => DefinitionKind::ExternalFunction,
+        };
+        let scope_end = self.enclosing_block(start).end;
+
+        for name in names {
+          let selection = self.name_after(start, name);
+          let scope = Span::new(selection.start, scope_end);
+
+          self.define(name, kind.clone(), selection, selection, scope);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  fn collect_if(&mut self, r#if: &If) {
+    self.collect(&r#if.block);
+
+    match &r#if.r#else {
+      Some(Else::Else(block)) => self.collect(block),
+      Some(Else::Elif(r#if)) => self.collect_if(r#if),
+      None => {}
+    }
+  }
+
+  fn collect_try(&mut self, r#try: &Try, start: Position) {
+    self.collect(&r#try.block);
+
+    // bound after the try block, which could use the same name
+    if let Some(error) = &r#try.error {
+      let block = self.block_after(start);
+      self.define_bound(error, DefinitionKind::Variable, block.end);
+    }
+    self.collect(&r#try.catch);
+
+    if let Some(finally) = &r#try.finally {
+      self.collect(finally);
+    }
+  }
+
+  /// Defines a name bound for the block that follows, like a loop variable
+  fn define_bound(&mut self, name: &str, kind: DefinitionKind, start: Position) {
+    let selection = self.name_after(start, name);
+    let body = self.block_after(selection.end);
+
+    self.define(
+      name,
+      kind,
+      selection,
+      selection,
+      Span::new(selection.start, body.end),
+    );
+  }
+
+  /// Defines the functions and exports of an imported file, visible after
+  /// the import statement
+  fn collect_import(&mut self, path: &str, tree: &[Node], span: Span) {
+    let Ok(text) = fs::read_to_string(path) else {
+      return;
+    };
+    let Ok(tokens) = tokenize(&text) else {
+      return;
+    };
+
+    let file = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let mut collector = Collector::new(&text, &tokens, Some(file));
+    collector.collect(tree);
+
+    let scope = Span::new(span.end, self.enclosing_block(span.start).end);
+
+    for mut definition in collector.definitions {
+      let is_global = definition.scope.end == END
+        || (matches!(definition.kind, DefinitionKind::Function(_))
+          && definition.scope.start == Position::default());
+
+      if is_global {
+        definition.scope = scope;
+        self.definitions.push(definition);
+      }
+    }
+  }
+}
@@ -1,30 +1,33 @@
 #![warn(clippy::pedantic)]
 
 use std::{
+  collections::HashMap,
   fs::{self, File},
-  io::{self, prelude::Read, Write},
+  io::{self, prelude::Read, IsTerminal, Write},
+  iter,
   os::unix::prelude::PermissionsExt,
   path::{Path, PathBuf},
-  process,
-  time::Instant,
+  process, thread,
+  time::{Duration, Instant, SystemTime},
 };
 
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, Parser, Subcommand};
 
-mod types;
-
-mod tokenize;
-use tokenize::tokenize;
-
-mod parse;
-use parse::parse;
-
-mod transpile;
-use transpile::transpile;
+use alpha_shell::{compile, format, Diagnostic, FloatBackend, Lint, LintLevel, Options, Target};
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None, arg_required_else_help = true)]
+#[clap(
+  author,
+  version,
+  about,
+  long_about = None,
+  arg_required_else_help = true,
+  args_conflicts_with_subcommands = true
+)]
 struct Args {
+  #[clap(subcommand)]
+  command: Option<Command>,
+
   /// List of input files to be transpiled
   ///
   /// Use '-' to read from stdin.
@@ -36,7 +39,8 @@ struct Args {
   /// Ignored when multiple files are given.
   /// Overrides --output-directory for single file.
   /// Use '-' to output result to stdout (requires --quiet).
-  /// If no output file is given, input file name with '.zsh' extension is used instead
+  /// If no output file is given, input file name with the target's extension
+  /// (e.g. '.zsh') is used instead
   #[clap(short, long)]
   output: Option<PathBuf>,
 
@@ -48,16 +52,58 @@ struct Args {
   output_directory: Option<PathBuf>,
 
   /// Suppress info output
-  #[clap(short, long, action = ArgAction::Count)]
+  #[clap(short, long, action = ArgAction::Count, global = true)]
   quiet: u8,
 
   /// Verbose output
-  #[clap(short, long, action = ArgAction::Count)]
+  #[clap(short, long, action = ArgAction::Count, global = true)]
   verbose: u8,
 
   /// Make the output file executable and add shebang
   #[clap(short, long)]
   executable: bool,
+
+  /// Shell to generate code for
+  #[clap(short, long, value_enum, default_value_t)]
+  target: Target,
+
+  /// Reject calls to commands that aren't functions, shell builtins or
+  /// declared with `external fn`
+  #[clap(long)]
+  strict: bool,
+
+  /// Evaluate float arithmetic with an external command on targets without
+  /// floats, bash and sh
+  #[clap(long, value_name = "BACKEND", value_enum)]
+  float: Option<FloatBackend>,
+
+  /// Report the warnings of a lint as errors, e.g. `-W unused-variable`
+  #[clap(short = 'W', long = "deny", value_name = "LINT", value_enum)]
+  deny: Vec<Lint>,
+
+  /// Don't report the warnings of a lint
+  #[clap(short = 'A', long = "allow", value_name = "LINT", value_enum)]
+  allow: Vec<Lint>,
+
+  /// Keep running and transpile again whenever an input file or a file it
+  /// imports changes
+  #[clap(short, long)]
+  watch: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  /// Format files in place
+  Fmt {
+    /// List of files to be formatted
+    ///
+    /// Use '-' to format stdin to stdout.
+    files: Vec<PathBuf>,
+
+    /// Don't write anything, exit with an error if any file would change
+    #[clap(long)]
+    check: bool,
+  },
 }
 
 macro_rules! error {
@@ -98,107 +144,301 @@ macro_rules! verbose {
 fn main() {
   let args = Args::parse();
 
+  if let Some(Command::Fmt { files, check }) = &args.command {
+    run_fmt(&args, files, *check);
+    return;
+  }
+
   if args.files.is_empty() {
     error!("No files specified");
   }
 
-  if args.files.len() == 1 {
-    run_single(&args);
-  } else {
-    run_multiple(&args);
+  let jobs = jobs(&args).unwrap_or_else(|failure| exit(failure));
+
+  if args.watch {
+    watch(&args, &jobs);
   }
-}
 
-fn run_single(args: &Args) {
-  let file = &args.files[0];
+  for job in &jobs {
+    if let Err(failure) = run_job(&args, job) {
+      exit(failure);
+    }
+  }
+}
 
-  let output_path = if let Some(output) = &args.output {
-    output.clone()
-  } else if let Some(output_directory) = &args.output_directory {
-    create_directory(args, output_directory);
+/// Error that stopped a file from being transpiled
+enum Failure {
+  /// Error that still has to be printed
+  Message(String),
+  /// Diagnostics that were already reported, with the imported files they
+  /// point into
+  Reported(Vec<PathBuf>),
+}
 
-    output_directory
-      .join(file.file_name().unwrap())
-      .with_extension("zsh")
-  } else {
-    file.with_extension("zsh")
-  };
+type RunResult<T> = Result<T, Failure>;
 
-  let input: Box<dyn io::Read> = if is_std_io(file) {
-    Box::new(io::stdin())
-  } else {
-    Box::new(open_file(args, file))
-  };
+fn exit(failure: Failure) -> ! {
+  if let Failure::Message(message) = failure {
+    eprintln!("{message}");
+  }
 
-  let output: Box<dyn io::Write> = if is_std_io(&output_path) {
-    warn_if_not_quiet(args);
-    Box::new(io::stdout())
-  } else {
-    Box::new(create_file(args, &output_path))
-  };
+  process::exit(1)
+}
 
-  run_for_file(args, file, input, output);
+/// Input file and the path its code is written to
+struct Job {
+  input: PathBuf,
+  output: PathBuf,
 }
 
-fn run_multiple(args: &Args) {
+fn jobs(args: &Args) -> RunResult<Vec<Job>> {
+  if let [file] = args.files.as_slice() {
+    let output = if let Some(output) = &args.output {
+      output.clone()
+    } else if let Some(output_directory) = &args.output_directory {
+      create_directory(args, output_directory)?;
+
+      output_directory
+        .join(file.file_name().unwrap())
+        .with_extension(args.target.extension())
+    } else {
+      file.with_extension(args.target.extension())
+    };
+
+    return Ok(vec![Job {
+      input: file.clone(),
+      output,
+    }]);
+  }
+
   let output_directory = args
     .output_directory
     .clone()
     .unwrap_or_else(|| PathBuf::from("."));
 
-  create_directory(args, &output_directory);
-
-  for file in &args.files {
-    let input: Box<dyn io::Read> = if is_std_io(file) {
-      Box::new(io::stdin())
-    } else {
-      Box::new(open_file(args, file))
-    };
+  create_directory(args, &output_directory)?;
+
+  Ok(
+    args
+      .files
+      .iter()
+      .map(|file| Job {
+        input: file.clone(),
+        output: output_directory
+          .join(file.file_name().unwrap())
+          .with_extension(args.target.extension()),
+      })
+      .collect(),
+  )
+}
 
-    let output_path = output_directory
-      .join(file.file_name().unwrap())
-      .with_extension("zsh");
+/// Transpiles the job's input, returning the files it imports. The output
+/// is only written once the input compiles, leaving the last one otherwise.
+fn run_job(args: &Args, job: &Job) -> RunResult<Vec<PathBuf>> {
+  let input: Box<dyn io::Read> = if is_std_io(&job.input) {
+    Box::new(io::stdin())
+  } else {
+    Box::new(open_file(args, &job.input)?)
+  };
 
-    let output: Box<dyn io::Write> = Box::new(create_file(args, &output_path));
+  let (code, imports) = run_for_file(args, &job.input, input)?;
 
-    run_for_file(args, file, input, output);
+  if is_std_io(&job.output) {
+    warn_if_not_quiet(args);
+    write!(io::stdout(), "{code}").map_err(|e| Failure::Message(e.to_string()))?;
+  } else {
+    write_file(args, &job.output, &code)?;
   }
+
+  Ok(imports)
 }
 
+/// Transpiles the input, returning the code and the files it imports
 fn run_for_file(
   args: &Args,
   file_name: &Path,
   mut input: Box<dyn Read>,
-  mut output: Box<dyn Write>,
-) {
-  // use closure to better handle errors
-  let mut inner = move || -> io::Result<()> {
-    let input: &mut dyn io::Read = &mut input;
-    let output: &mut dyn io::Write = &mut output;
-    let contents = read(input)?;
-
-    let tokens = tokenize(&contents).unwrap_or_else(|e| error!("{e}"));
-    let tree = parse(&tokens).unwrap_or_else(|e| error!("{e}"));
-    let code = transpile(&tree).unwrap_or_else(|e| error!("{e}"));
-
-    if args.executable {
-      writeln!(output, "#!/usr/bin/env zsh")?;
+) -> RunResult<(String, Vec<PathBuf>)> {
+  let start = Instant::now();
+
+  let contents = read(&mut input).map_err(|e| Failure::Message(e.to_string()))?;
+
+  let options = Options::new(args.target)
+    .with_shebang(args.executable)
+    .with_strict(args.strict)
+    .with_float(args.float);
+  let options = args
+    .allow
+    .iter()
+    .map(|lint| (lint, LintLevel::Allow))
+    .chain(args.deny.iter().map(|lint| (lint, LintLevel::Deny)))
+    .fold(options, |options, (lint, level)| {
+      options.with_lint(*lint, level)
+    });
+  let compiled = compile(&contents, &options).map_err(|e| fail(&e, file_name, &contents))?;
+
+  for warning in &compiled.warnings {
+    report(warning, file_name, &contents);
+  }
+
+  let elapsed = start.elapsed();
+  info!(args, "Transpiled '{}' in {elapsed:?}", file_name.display());
+
+  let imports = compiled.imports.into_iter().map(PathBuf::from).collect();
+
+  Ok((compiled.code, imports))
+}
+
+/// How often watched files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Transpiles the jobs again whenever their input or a file it imports
+/// changes, reporting errors without stopping
+fn watch(args: &Args, jobs: &[Job]) -> ! {
+  if jobs.iter().any(|job| is_std_io(&job.input)) {
+    error!("Can't watch stdin");
+  }
+
+  let mut watched: Vec<_> = jobs
+    .iter()
+    .map(|job| {
+      let imports = run_watched(args, job, Vec::new());
+      modification_times(job, imports)
+    })
+    .collect();
+
+  info!(args, "Watching for changes");
+
+  loop {
+    thread::sleep(POLL_INTERVAL);
+
+    for (job, times) in jobs.iter().zip(&mut watched) {
+      let changed = times
+        .iter()
+        .any(|(path, time)| modification_time(path) != *time);
+
+      if changed {
+        let imports = times
+          .keys()
+          .filter(|path| **path != job.input)
+          .cloned()
+          .collect();
+        *times = modification_times(job, run_watched(args, job, imports));
+      }
     }
+  }
+}
 
-    writeln!(output, "{code}")?;
+/// Runs the job, returning the files to watch along with its input. When it
+/// fails, those are the last known imports and the files with errors, so
+/// fixing an import that never parsed transpiles the job again.
+fn run_watched(args: &Args, job: &Job, mut imports: Vec<PathBuf>) -> Vec<PathBuf> {
+  match run_job(args, job) {
+    Ok(imports) => imports,
+    Err(Failure::Message(message)) => {
+      eprintln!("{message}");
+      imports
+    }
+    Err(Failure::Reported(files)) => {
+      imports.extend(files);
+      imports
+    }
+  }
+}
 
-    Ok(())
-  };
+fn modification_times(job: &Job, imports: Vec<PathBuf>) -> HashMap<PathBuf, Option<SystemTime>> {
+  iter::once(job.input.clone())
+    .chain(imports)
+    .map(|path| {
+      let time = modification_time(&path);
+      (path, time)
+    })
+    .collect()
+}
 
-  let start = Instant::now();
-  let result = inner();
-  let elapsed = start.elapsed();
+fn modification_time(path: &Path) -> Option<SystemTime> {
+  fs::metadata(path)
+    .and_then(|metadata| metadata.modified())
+    .ok()
+}
 
-  if let Err(error) = result {
-    error!("{error}");
+fn run_fmt(args: &Args, files: &[PathBuf], check: bool) {
+  if files.is_empty() {
+    error!("No files specified");
   }
 
-  info!(args, "Transpiled '{}' in {elapsed:?}", file_name.display());
+  let mut unformatted = 0;
+
+  for file in files {
+    let contents = if is_std_io(file) {
+      read(&mut io::stdin())
+    } else {
+      verbose!(args, "Opening file: {}", file.display());
+      fs::read_to_string(file)
+    }
+    .unwrap_or_else(|e| error!("Couldn't read file '{}', error: '{e}'", file.display()));
+
+    let formatted = format(&contents).unwrap_or_else(|e| exit(fail(&e, file, &contents)));
+
+    if check {
+      if formatted != contents {
+        unformatted += 1;
+        println!("Would reformat '{}'", file.display());
+      }
+    } else if is_std_io(file) {
+      print!("{formatted}");
+    } else if formatted != contents {
+      fs::write(file, formatted)
+        .unwrap_or_else(|e| error!("Couldn't write file '{}', error: '{e}'", file.display()));
+      info!(args, "Formatted '{}'", file.display());
+    }
+  }
+
+  if unformatted > 0 {
+    process::exit(1);
+  }
+}
+
+/// Reports the errors that stopped `file_name` from being processed
+fn fail(diagnostics: &[Diagnostic], file_name: &Path, contents: &str) -> Failure {
+  for diagnostic in diagnostics {
+    report(diagnostic, file_name, contents);
+  }
+
+  if diagnostics.len() > 1 {
+    eprintln!(
+      "error: aborting due to {} previous errors",
+      diagnostics.len()
+    );
+  }
+
+  let files = diagnostics
+    .iter()
+    .filter_map(|diagnostic| diagnostic.file.as_ref())
+    .map(PathBuf::from)
+    .collect();
+
+  Failure::Reported(files)
+}
+
+/// Prints the diagnostic to stderr, with the snippet taken from the imported
+/// file it points into if there is one
+fn report(diagnostic: &Diagnostic, file_name: &Path, contents: &str) {
+  let name = if is_std_io(file_name) {
+    "<stdin>".to_owned()
+  } else {
+    file_name.display().to_string()
+  };
+
+  let imported = diagnostic
+    .file
+    .as_ref()
+    .and_then(|file| fs::read_to_string(file).ok());
+  let source = imported.as_deref().unwrap_or(contents);
+
+  let color = io::stderr().is_terminal();
+
+  eprint!("{}", diagnostic.render(&name, source, color));
 }
 
 fn is_std_io(path: &Path) -> bool {
@@ -211,42 +451,83 @@ fn warn_if_not_quiet(args: &Args) {
   }
 }
 
-fn create_directory(args: &Args, directory: &Path) {
+fn create_directory(args: &Args, directory: &Path) -> RunResult<()> {
   verbose!(args, "Creating directory '{}'", directory.display());
-  if let Err(e) = fs::create_dir_all(directory) {
-    error!(
+  fs::create_dir_all(directory).map_err(|e| {
+    Failure::Message(format!(
       "Couldn't create directory '{}', error: '{e}'",
       directory.display()
-    );
-  };
+    ))
+  })
 }
 
-fn open_file(args: &Args, path: &Path) -> File {
+fn open_file(args: &Args, path: &Path) -> RunResult<File> {
   verbose!(args, "Opening file: {}", path.display());
-  File::open(path)
-    .unwrap_or_else(|e| error!("Couldn't open file '{}', error: '{e}'", path.display()))
+  File::open(path).map_err(|e| {
+    Failure::Message(format!(
+      "Couldn't open file '{}', error: '{e}'",
+      path.display()
+    ))
+  })
 }
 
-fn create_file(args: &Args, path: &Path) -> File {
+fn create_file(args: &Args, path: &Path) -> RunResult<File> {
   if path.exists() {
     verbose!(args, "Removing file: {}", path.display());
-    if let Err(e) = fs::remove_file(path) {
-      error!("Couldn't remove file '{}', error: '{e}'", path.display());
-    }
+    fs::remove_file(path).map_err(|e| {
+      Failure::Message(format!(
+        "Couldn't remove file '{}', error: '{e}'",
+        path.display()
+      ))
+    })?;
   }
 
   verbose!(args, "Creating file: {}", path.display());
-  let file = File::create(path)
-    .unwrap_or_else(|e| error!("Couldn't create file '{}', error: '{e}'", path.display()));
+  let file = File::create(path).map_err(|e| {
+    Failure::Message(format!(
+      "Couldn't create file '{}', error: '{e}'",
+      path.display()
+    ))
+  })?;
 
-  make_executable(args, path).unwrap_or_else(|e| {
-    error!(
+  make_executable(args, path).map_err(|e| {
+    Failure::Message(format!(
       "Couldn't make file executable '{}', error: '{e}'",
       path.display()
-    );
-  });
+    ))
+  })?;
 
-  file
+  Ok(file)
+}
+
+/// Writes `code` to a temporary file next to `path` and moves it over `path`,
+/// so a failed write doesn't leave a truncated file behind
+fn write_file(args: &Args, path: &Path, code: &str) -> RunResult<()> {
+  let name = path.file_name().unwrap_or_default().to_string_lossy();
+  let temporary = path.with_file_name(format!(".{name}.tmp"));
+
+  create_file(args, &temporary)?
+    .write_all(code.as_bytes())
+    .map_err(|e| {
+      Failure::Message(format!(
+        "Couldn't write file '{}', error: '{e}'",
+        temporary.display()
+      ))
+    })?;
+
+  verbose!(
+    args,
+    "Moving file {} to {}",
+    temporary.display(),
+    path.display()
+  );
+  fs::rename(&temporary, path).map_err(|e| {
+    Failure::Message(format!(
+      "Couldn't move file '{}' to '{}', error: '{e}'",
+      temporary.display(),
+      path.display()
+    ))
+  })
 }
 
 fn make_executable(args: &Args, path: &Path) -> io::Result<()> {
@@ -1,17 +1,18 @@
-mod error;
+pub mod error;
 pub mod node;
-mod parse_helper;
+pub mod parse_helper;
 mod utils;
 
 use std::collections::HashSet;
 
 use error::{Error, ParserResult};
-use node::Node;
+use node::{Node, NodeKind};
 use parse_helper::ParseHelper;
 
 pub mod array;
 pub mod block;
 pub mod declaration;
+pub mod defer;
 pub mod expression;
 pub mod external;
 pub mod r#for;
@@ -19,9 +20,12 @@ pub mod function;
 pub mod function_call;
 pub mod r#if;
 pub mod import;
+pub mod interpolation;
 pub mod map;
 pub mod r#return;
+pub mod signal;
 pub mod switch;
+pub mod r#try;
 pub mod value;
 pub mod r#while;
 
@@ -30,73 +34,118 @@ use crate::{
   types::{
     Token,
     TokenType::{
-      Break, Continue, Dollar, Export, External, For, Function, Identifier, If, Import, LParen,
-      Let, Return, Source, Switch, While,
+      Break, Continue, Defer, Dollar, Export, External, For, Function, Identifier, If, Import,
+      LParen, Let, Return, Source, Switch, Try, While,
     },
     TT,
   },
 };
 
-pub fn parse(tokens: &[Token]) -> ParserResult<Vec<Node>> {
+/// Parses the whole file, returning every error found instead of stopping at
+/// the first one
+pub fn parse(tokens: &[Token]) -> Result<Vec<Node>, Vec<Error>> {
+  parse_file(tokens, false)
+}
+
+/// Like [`parse`], also rejecting calls to commands that are neither
+/// functions, shell builtins nor declared with `external fn`
+pub fn parse_strict(tokens: &[Token]) -> Result<Vec<Node>, Vec<Error>> {
+  parse_file(tokens, true)
+}
+
+/// Like [`parse`], only checking the syntax. Any name is taken as a variable,
+/// calls aren't checked and imported files aren't read, leaving their trees
+/// empty
+#[cfg(feature = "cli")]
+pub fn parse_syntax(tokens: &[Token]) -> Result<Vec<Node>, Vec<Error>> {
+  let mut ph = helper(tokens);
+  ph.syntax_only = true;
+
+  inner(ph).map(|(nodes, _)| nodes)
+}
+
+fn helper(tokens: &[Token]) -> ParseHelper {
   let variables = (0..10).map(|x| x.to_string()).collect();
 
-  inner(tokens, variables).map(|(nodes, _)| nodes)
+  ParseHelper::new(tokens.to_vec(), variables, function::scan(tokens))
+}
+
+fn parse_file(tokens: &[Token], strict: bool) -> Result<Vec<Node>, Vec<Error>> {
+  let mut ph = helper(tokens);
+  ph.strict = strict;
+
+  inner(ph).map(|(nodes, _)| nodes)
 }
 
 macro_rules! simple_token {
   ($ph:expr, $tok:expr) => {{
+    let start = $ph.start();
     $ph.advance();
     check_token!(&$ph, TT::Semicolon);
     $ph.advance();
-    Ok($tok)
+    Ok(Node::new($tok, $ph.span_from(start)))
   }};
 }
 
-pub fn inner(
-  tokens: &[Token],
-  variables: HashSet<String>,
-) -> ParserResult<(Vec<Node>, HashSet<String>)> {
-  let mut ph = ParseHelper::new(tokens.to_vec(), variables);
-
+pub fn inner(mut ph: ParseHelper) -> Result<(Vec<Node>, HashSet<String>), Vec<Error>> {
   let mut tree = vec![];
 
-  while let Some(token) = ph.peek(0) {
-    let node = match token {
-      Import | Source => {
-        let nodes = import::parse(&mut ph)?;
-        tree.extend_from_slice(&nodes);
-
-        Ok(Node::Empty)
+  while ph.peek(0).is_some() {
+    match statement(&mut ph) {
+      Ok(nodes) => tree.extend(nodes),
+      Err(error) => {
+        ph.errors.push(error);
+        ph.synchronize();
       }
-      Function => r#function::parse(&mut ph),
-      Export | Let => declaration::parse(&mut ph),
-      For => r#for::parse(&mut ph),
-      While => r#while::parse(&mut ph),
-      If => r#if::parse(&mut ph),
-      Return => r#return::parse(&mut ph),
-      Continue => simple_token!(ph, Node::Continue),
-      Break => simple_token!(ph, Node::Break),
-      External => external::parse(&mut ph),
-      Switch => switch::parse(&mut ph),
-
-      Identifier(..) | Dollar => {
-        if let Some(next) = ph.peek(1) {
-          match next {
-            LParen => function_call::parse(&mut ph),
-            _ => expression::parse(&mut ph),
-          }
-        } else {
-          return Err(Error::end(&ph));
+    }
+  }
+
+  if ph.errors.is_empty() {
+    Ok((tree, ph.exports))
+  } else {
+    Err(ph.errors)
+  }
+}
+
+fn statement(ph: &mut ParseHelper) -> ParserResult<Vec<Node>> {
+  let Some(token) = ph.peek(0) else {
+    return Ok(Vec::new());
+  };
+
+  let node = match token {
+    Import | Source => return import::parse(ph),
+    Function => r#function::parse(ph),
+    Export | Let => declaration::parse(ph),
+    For => r#for::parse(ph),
+    While => r#while::parse(ph),
+    If => r#if::parse(ph),
+    Return => r#return::parse(ph),
+    Continue => simple_token!(ph, NodeKind::Continue),
+    Break => simple_token!(ph, NodeKind::Break),
+    External => external::parse(ph),
+    Switch => switch::parse(ph),
+    Try => r#try::parse(ph),
+    Defer => defer::parse(ph),
+
+    Identifier(..) if signal::is_handler(ph) => signal::parse(ph),
+    Identifier(..) | Dollar => {
+      if let Some(next) = ph.peek(1) {
+        match next {
+          LParen => function_call::parse(ph),
+          _ => expression::parse(ph),
         }
+      } else {
+        return Err(Error::end(ph, "`(` or expression"));
       }
-      _ => return Err(Error::unexpected(&ph)),
-    };
-
-    match node? {
-      Node::Empty => {}
-      node => tree.push(node),
     }
-  }
+    // piped into a command
+    TT::String(_) | TT::InterpolatedString(_) | TT::RawString(_)
+      if ph.peek(1) == Some(&TT::Pipe) =>
+    {
+      function_call::parse(ph)
+    }
+    _ => return Err(Error::unexpected(ph, "statement")),
+  };
 
-  Ok((tree, ph.exports))
+  Ok(vec![node?])
 }
@@ -14,22 +14,19 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Vec<Value>> {
 
   let mut values = Vec::new();
 
-  if ph.peek(0) == Some(&TT::RBracket) {
-    ph.advance();
-    return Ok(values);
-  }
-
-  loop {
+  // a trailing comma is allowed
+  while ph.peek(0) != Some(&TT::RBracket) {
     values.push(value::parse_inner(ph)?);
 
     match ph.peek(0) {
       Some(TT::Comma) => ph.advance(),
       Some(TT::RBracket) => break,
-      Some(_) => return Err(Error::unexpected(ph)),
-      _ => return Err(Error::end(ph)),
-    };
+      Some(_) => return Err(Error::unexpected(ph, "`,` or `]`")),
+      _ => return Err(Error::end(ph, "`,` or `]`")),
+    }
   }
 
+  check_token!(ph, TT::RBracket);
   ph.advance();
 
   Ok(values)
@@ -2,7 +2,7 @@ use std::collections::HashSet;
 
 use super::{
   error::{Error, ParserResult},
-  node::Node,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
 };
 use crate::{check_token, types::TT};
@@ -25,25 +25,34 @@ pub fn parse_inner(ph: &mut ParseHelper, variables: HashSet<String>) -> ParserRe
     }
 
     if braces_level == 0 {
-      let (body, exports) = super::inner(&tmp, variables)?;
+      ph.advance();
 
-      ph.variables.extend(exports.iter().cloned());
-      ph.exports.extend(exports.iter().cloned());
+      // a block with errors is left empty so parsing can carry on after it
+      return match super::inner(ph.child(&tmp, variables)) {
+        Ok((body, exports)) => {
+          ph.variables.extend(exports.iter().cloned());
+          ph.exports.extend(exports.iter().cloned());
 
-      ph.advance();
+          Ok(body)
+        }
+        Err(errors) => {
+          ph.errors.extend(errors);
 
-      return Ok(body);
+          Ok(Vec::new())
+        }
+      };
     }
 
     tmp.push(token.clone());
     ph.advance();
   }
 
-  Err(Error::end(ph))
+  Err(Error::end(ph, "`}`"))
 }
 
 pub fn parse(ph: &mut ParseHelper, variables: HashSet<String>) -> ParserResult<Node> {
+  let start = ph.start();
   let body = parse_inner(ph, variables)?;
-  let block = Node::Block(body);
+  let block = Node::new(NodeKind::Block(body), ph.span_from(start));
   Ok(block)
 }
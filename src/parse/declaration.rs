@@ -1,8 +1,8 @@
 use super::{
   error::{Error, ParserResult},
-  node::Node,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
-  value,
+  value::{self, Literal, Value, ValueKind},
 };
 use crate::{check_token, types::TT};
 
@@ -32,19 +32,21 @@ impl Declaration {
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
   check_token!(ph, TT::Let | TT::Export);
 
+  let start = ph.start();
+
   let r#type = match ph.peek(0) {
     Some(TT::Let) => Type::Let,
     Some(TT::Export) => Type::Export,
-    Some(_) => return Err(Error::unexpected(ph)),
-    None => return Err(Error::end(ph)),
+    Some(_) => return Err(Error::unexpected(ph, "`let` or `export`")),
+    None => return Err(Error::end(ph, "`let` or `export`")),
   };
 
   ph.advance();
 
   let name = match ph.peek(0) {
     Some(TT::Identifier(name)) => name.clone(),
-    Some(_) => return Err(Error::unexpected(ph)),
-    None => return Err(Error::end(ph)),
+    Some(_) => return Err(Error::unexpected(ph, "identifier")),
+    None => return Err(Error::end(ph, "identifier")),
   };
 
   // if ph.variables.get(&name).is_some() {
@@ -64,11 +66,28 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
 
   let value = value::parse(ph)?;
 
+  let array = match &value.kind {
+    NodeKind::Value(Value { kind, .. }) => match kind {
+      ValueKind::Literal(Literal::Array(_)) => true,
+      ValueKind::Identifier(other) => other == "@" || ph.arrays.contains(other),
+      _ => false,
+    },
+    _ => false,
+  };
+  if array {
+    ph.arrays.insert(name.clone());
+  } else {
+    ph.arrays.remove(&name);
+  }
+
   check_token!(ph, TT::Semicolon);
 
   ph.advance();
 
-  let declaration = Node::Declaration(Declaration::new(r#type, name, Box::new(value)));
+  let declaration = Node::new(
+    NodeKind::Declaration(Declaration::new(r#type, name, Box::new(value))),
+    ph.span_from(start),
+  );
 
   Ok(declaration)
 }
@@ -0,0 +1,30 @@
+use super::{
+  block,
+  error::{Error, ParserResult},
+  function_call,
+  node::{Node, NodeKind},
+  parse_helper::ParseHelper,
+};
+use crate::{check_token, types::TT};
+
+/// Parses `defer { ... }`, or `defer cmd();` for a single call
+pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
+  check_token!(ph, TT::Defer);
+
+  let start = ph.start();
+
+  ph.advance();
+
+  // the block only runs on the way out, once every function is declared
+  let runs_later = std::mem::replace(&mut ph.runs_later, true);
+  let block = if ph.peek(0) == Some(&TT::LBrace) {
+    block::parse_inner(ph, ph.variables.clone())
+  } else {
+    check_token!(ph, TT::LBrace | TT::Identifier(..));
+    function_call::parse(ph).map(|call| vec![call])
+  };
+  ph.runs_later = runs_later;
+  let block = block?;
+
+  Ok(Node::new(NodeKind::Defer(block), ph.span_from(start)))
+}
@@ -1,37 +1,48 @@
 use std::fmt;
 
 use super::parse_helper::ParseHelper;
-use crate::types::{Position, Token};
+use crate::{
+  diagnostic::Diagnostic,
+  types::{Span, Token},
+};
 
 pub type ParserResult<T> = Result<T, Error>;
 
 #[derive(Debug, Clone)]
 pub struct Error {
   pub msg: String,
-  pub token: Option<Token>,
+  pub span: Option<Span>,
+  /// What the parser was looking for instead
+  pub expected: Option<Box<str>>,
+  pub help: Option<Box<str>>,
+  /// Imported file the error comes from
+  pub file: Option<String>,
 }
 
 impl Error {
   pub fn new(msg: &str, token: Option<&Token>) -> Self {
     Error {
       msg: msg.to_string(),
-      token: token.cloned(),
+      span: token.map(|token| token.span),
+      expected: None,
+      help: None,
+      file: None,
     }
   }
 
-  pub fn unexpected(ph: &ParseHelper) -> Self {
-    let token = ph.get(0).unwrap();
-    #[cfg(debug_assertions)]
-    panic!(
-      "{}\ncurrent index: {}\nUnexpected token {:?}",
-      ph.pretty_print_tokens(),
-      ph.get_index(),
-      token
-    );
-
-    #[cfg(not(debug_assertions))]
-    {
-      Self::new(&format!("Unexpected token {token}"), Some(token))
+  pub fn at(msg: &str, span: Span) -> Self {
+    Error {
+      span: Some(span),
+      ..Self::new(msg, None)
+    }
+  }
+
+  pub fn unexpected(ph: &ParseHelper, expected: &str) -> Self {
+    match ph.get(0) {
+      Some(token) => {
+        Self::new(&format!("Unexpected token `{token}`"), Some(token)).expected(expected)
+      }
+      None => Self::end(ph, expected),
     }
   }
 
@@ -50,40 +61,104 @@ impl Error {
   pub fn undefined_variable(ph: &ParseHelper) -> Self {
     let token = ph.get(0).unwrap();
 
-    #[cfg(debug_assertions)]
-    println!(
-      "{}\ncurrent index: {}",
-      ph.pretty_print_tokens(),
-      ph.get_index()
-    );
-    Self::new(&format!("Undefined variable {token}"), Some(token))
+    Self::undefined(&token.to_string(), token.span)
+  }
+
+  pub fn undefined(name: &str, span: Span) -> Self {
+    Self::at(&format!("Undefined variable {name}"), span).help(&format!(
+      "declare it first with `let {name} = ...;` or mark it with `external {name};`"
+    ))
+  }
+
+  pub fn undeclared_assignment(ph: &ParseHelper) -> Self {
+    let token = ph.get(0).unwrap();
+
+    Self::new(
+      &format!("Assignment to undeclared variable {token}"),
+      Some(token),
+    )
+    .help(&format!("declare it with `let {token} = ...;`"))
   }
 
-  pub fn end(ph: &ParseHelper) -> Error {
-    #[cfg(debug_assertions)]
-    println!(
-      "{}\ncurrent index: {}",
-      ph.pretty_print_tokens(),
-      ph.get_index()
-    );
+  pub fn end(ph: &ParseHelper, expected: &str) -> Error {
+    let error = match ph.get_tokens().last() {
+      Some(last) => Self::new(
+        &format!("Unexpected end of input after `{last}`"),
+        Some(last),
+      ),
+      None => Self::new("Unexpected end of input", None),
+    };
 
-    let last = ph.get_tokens().last().unwrap();
+    error.expected(expected)
+  }
+
+  pub fn expected(mut self, expected: &str) -> Self {
+    self.expected = Some(expected.into());
+    self
+  }
 
-    Self::new(&format!("Unexpected end of input after {last}"), Some(last))
+  pub fn help(mut self, help: &str) -> Self {
+    self.help = Some(help.into());
+    self
+  }
+
+  /// Marks the error as coming from `file`, unless it's already coming from
+  /// a file imported by that one
+  pub fn in_file(mut self, file: &str) -> Self {
+    self.file.get_or_insert_with(|| file.to_owned());
+    self
+  }
+}
+
+impl From<Error> for Diagnostic {
+  fn from(error: Error) -> Self {
+    let Error {
+      msg,
+      span,
+      expected,
+      help,
+      file,
+    } = error;
+
+    let mut diagnostic = Diagnostic::error(&msg);
+
+    if let Some(span) = span {
+      diagnostic = diagnostic.with_span(span);
+    }
+    if let Some(expected) = expected {
+      diagnostic = diagnostic.with_label(&format!("expected {expected}"));
+    }
+    if let Some(help) = help {
+      diagnostic = diagnostic.with_help(&help);
+    }
+    if let Some(file) = file {
+      diagnostic = diagnostic.in_file(&file);
+    }
+
+    diagnostic
   }
 }
 
 impl fmt::Display for Error {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    let Error { msg, token } = self;
+    let Error {
+      msg,
+      span,
+      expected,
+      file,
+      ..
+    } = self;
 
-    if let Some(token) = token {
-      let Position(line, column) = token.position;
-      let (line, column) = (line + 1, column + 1); // account for zero indexing
+    write!(f, "ParserError: \"{msg}")?;
+    if let Some(expected) = expected {
+      write!(f, ", expected {expected}")?;
+    }
+    write!(f, "\"")?;
 
-      write!(f, "ParserError: \"{msg}\" at position {line}:{column}")
-    } else {
-      write!(f, "ParserError: \"{msg}\"")
+    match (file, span) {
+      (Some(file), Some(span)) => write!(f, " at position {file}:{span}"),
+      (None, Some(span)) => write!(f, " at position {span}"),
+      _ => Ok(()),
     }
   }
 }
@@ -1,16 +1,17 @@
 use super::{
   error::{Error, ParserResult},
-  node::Node,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
   value,
 };
 use crate::{check_token, types::TT};
 
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
+  let start = ph.start();
   let value = value::parse_inner(ph)?;
 
   check_token!(ph, TT::Semicolon);
   ph.advance();
 
-  Ok(Node::Expression(value))
+  Ok(Node::new(NodeKind::Expression(value), ph.span_from(start)))
 }
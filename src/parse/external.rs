@@ -2,18 +2,32 @@ use super::{
   error::{Error, ParserResult},
   parse_helper::ParseHelper,
 };
-use crate::{check_token, parse::node::Node, types::TT};
+use crate::{
+  check_token,
+  parse::node::{Node, NodeKind},
+  types::TT,
+};
 
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
   check_token!(ph, TT::External);
 
+  let start = ph.start();
+
   ph.advance();
 
+  // `external fn` declares commands instead of variables
+  let commands = ph.peek(0) == Some(&TT::Function);
+  if commands {
+    ph.advance();
+  }
+
+  let mut names = Vec::new();
+
   match ph.peek(0) {
-    Some(TT::Identifier(string) | TT::String(string)) => ph.variables.insert(string.clone()),
-    Some(_) => return Err(Error::unexpected(ph)),
-    None => return Err(Error::end(ph)),
-  };
+    Some(TT::Identifier(string) | TT::String(string)) => names.push(string.clone()),
+    Some(_) => return Err(Error::unexpected(ph, "identifier or string")),
+    None => return Err(Error::end(ph, "identifier or string")),
+  }
 
   loop {
     ph.advance();
@@ -21,18 +35,29 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
     match ph.peek(0) {
       Some(TT::Comma) => ph.advance(),
       Some(TT::Semicolon) => break,
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
-    };
+      Some(_) => return Err(Error::unexpected(ph, "`,` or `;`")),
+      None => return Err(Error::end(ph, "`,` or `;`")),
+    }
 
     match ph.peek(0) {
-      Some(TT::Identifier(string) | TT::String(string)) => ph.variables.insert(string.clone()),
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
-    };
+      Some(TT::Identifier(string) | TT::String(string)) => names.push(string.clone()),
+      Some(_) => return Err(Error::unexpected(ph, "identifier or string")),
+      None => return Err(Error::end(ph, "identifier or string")),
+    }
   }
 
   ph.advance();
 
-  Ok(Node::Empty)
+  if commands {
+    ph.commands.extend(names.iter().cloned());
+
+    return Ok(Node::new(
+      NodeKind::ExternalFunction(names),
+      ph.span_from(start),
+    ));
+  }
+
+  ph.variables.extend(names.iter().cloned());
+
+  Ok(Node::new(NodeKind::External(names), ph.span_from(start)))
 }
@@ -1,11 +1,15 @@
 use super::{
   block::{self, Block},
   error::{Error, ParserResult},
-  node::Node,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
   value::{self, Value},
 };
-use crate::{check_token, parse::value::Literal, types::TT};
+use crate::{
+  check_token,
+  parse::value::{Literal, ValueKind},
+  types::{Position, TT},
+};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct For {
@@ -45,7 +49,7 @@ impl Foreach {
   }
 }
 
-fn parse_for(ph: &mut ParseHelper, variable: String) -> ParserResult<Node> {
+fn parse_for(ph: &mut ParseHelper, variable: String, node_start: Position) -> ParserResult<Node> {
   let start = value::parse_inner(ph)?;
 
   check_token!(ph, TT::Range);
@@ -57,7 +61,7 @@ fn parse_for(ph: &mut ParseHelper, variable: String) -> ParserResult<Node> {
     ph.advance();
     value::parse_inner(ph)?
   } else {
-    Value::Literal(Literal::Int(1))
+    Value::new(ValueKind::Literal(Literal::Int(1)), end.span)
   };
 
   let mut variables = ph.variables.clone();
@@ -65,12 +69,19 @@ fn parse_for(ph: &mut ParseHelper, variable: String) -> ParserResult<Node> {
 
   let block = Box::new(block::parse(ph, variables)?);
 
-  let node = Node::For(For::new(start, end, step, variable, block));
+  let node = Node::new(
+    NodeKind::For(Box::new(For::new(start, end, step, variable, block))),
+    ph.span_from(node_start),
+  );
 
   Ok(node)
 }
 
-fn parse_foreach(ph: &mut ParseHelper, variable: String) -> ParserResult<Node> {
+fn parse_foreach(
+  ph: &mut ParseHelper,
+  variable: String,
+  node_start: Position,
+) -> ParserResult<Node> {
   let iterable = value::parse_inner(ph)?;
 
   let mut variables = ph.variables.clone();
@@ -78,7 +89,10 @@ fn parse_foreach(ph: &mut ParseHelper, variable: String) -> ParserResult<Node> {
 
   let block = block::parse_inner(ph, variables)?;
 
-  let node = Node::Foreach(Foreach::new(iterable, variable, block));
+  let node = Node::new(
+    NodeKind::Foreach(Foreach::new(iterable, variable, block)),
+    ph.span_from(node_start),
+  );
 
   Ok(node)
 }
@@ -86,12 +100,14 @@ fn parse_foreach(ph: &mut ParseHelper, variable: String) -> ParserResult<Node> {
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
   check_token!(ph, TT::For);
 
+  let start = ph.start();
+
   ph.advance();
 
   let variable = match ph.peek(0) {
     Some(TT::Identifier(variable)) => variable.clone(),
-    Some(_) => return Err(Error::unexpected(ph)),
-    None => return Err(Error::end(ph)),
+    Some(_) => return Err(Error::unexpected(ph, "identifier")),
+    None => return Err(Error::end(ph, "identifier")),
   };
 
   ph.advance();
@@ -101,12 +117,12 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
   ph.advance();
 
   let node = match ph.peek(0) {
-    Some(TT::Integer(_)) => parse_for(ph, variable)?,
-    Some(TT::At | TT::LBracket | TT::Identifier(..) | TT::String(..)) => {
-      parse_foreach(ph, variable)?
-    }
-    Some(_) => return Err(Error::unexpected(ph)),
-    None => return Err(Error::end(ph)),
+    Some(TT::Integer(_)) => parse_for(ph, variable, start)?,
+    Some(
+      TT::At | TT::LBracket | TT::Identifier(..) | TT::String(..) | TT::InterpolatedString(..),
+    ) => parse_foreach(ph, variable, start)?,
+    Some(_) => return Err(Error::unexpected(ph, "range start or iterable")),
+    None => return Err(Error::end(ph, "range start or iterable")),
   };
 
   Ok(node)
@@ -1,22 +1,81 @@
-use std::collections::HashSet;
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+};
 
 use super::{
   block,
   error::{Error, ParserResult},
-  node::Node,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
+  value::{self, Literal, Value, ValueKind},
+};
+use crate::{
+  check_token,
+  types::{Position, Span, Token, TT},
 };
-use crate::{check_token, types::TT};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParamType {
+  String,
+  Int,
+  Bool,
+}
+
+impl fmt::Display for ParamType {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ParamType::String => write!(f, "string"),
+      ParamType::Int => write!(f, "int"),
+      ParamType::Bool => write!(f, "bool"),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Param {
+  /// Name as written, without the dashes of a named parameter
+  pub name: String,
+  /// Passed as `--name=value`, or as `--name` for a bool, instead of by
+  /// position
+  pub named: bool,
+  pub r#type: Option<ParamType>,
+  pub default: Option<Value>,
+  pub span: Span,
+}
+
+impl Param {
+  /// Name of the variable holding the argument in the function body
+  pub fn variable(&self) -> String {
+    self.name.replace('-', "_")
+  }
+
+  /// Named bool parameter, set by passing `--name`
+  pub fn is_flag(&self) -> bool {
+    self.named && self.r#type == Some(ParamType::Bool)
+  }
+}
+
+/// What calls to a function are checked against
+#[derive(Debug, PartialEq, Clone)]
+pub struct Signature {
+  pub params: Vec<Param>,
+  /// Reads `@`, so it takes any number of extra arguments
+  pub variadic: bool,
+  /// Where the declaration starts, which calls at the top level of the same
+  /// file have to come after. `None` for functions of other files
+  pub declared: Option<Position>,
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Function {
   pub name: String,
-  pub params: Vec<String>,
+  pub params: Vec<Param>,
   pub block: Box<Node>,
 }
 
 impl Function {
-  pub fn new(name: String, params: Vec<String>, block: Box<Node>) -> Self {
+  pub fn new(name: String, params: Vec<Param>, block: Box<Node>) -> Self {
     Self {
       name,
       params,
@@ -25,54 +84,436 @@ impl Function {
   }
 }
 
-pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
+fn parse_type(ph: &mut ParseHelper) -> ParserResult<ParamType> {
+  let r#type = match ph.peek(0) {
+    Some(TT::Identifier(name)) => match name.as_str() {
+      "string" => ParamType::String,
+      "int" => ParamType::Int,
+      "bool" => ParamType::Bool,
+      _ => return Err(Error::unexpected(ph, "string, int or bool")),
+    },
+    Some(_) => return Err(Error::unexpected(ph, "string, int or bool")),
+    None => return Err(Error::end(ph, "string, int or bool")),
+  };
+
   ph.advance();
 
-  let name = match ph.peek(0) {
-    Some(TT::Identifier(name)) => name.clone(),
-    Some(_) => return Err(Error::unexpected(ph)),
-    None => return Err(Error::end(ph)),
+  Ok(r#type)
+}
+
+/// `name`, `--name` or either of them followed by `: type` and `= default`
+fn parse_param(ph: &mut ParseHelper) -> ParserResult<Param> {
+  let start = ph.start();
+
+  let (name, named, mut has_default) = match ph.peek(0) {
+    Some(TT::Identifier(name)) => (name.clone(), false, false),
+    Some(TT::Flag(flag)) => {
+      let Some(name) = flag
+        .strip_prefix("--")
+        .filter(|name| !name.starts_with('-'))
+      else {
+        return Err(
+          Error::new("Named parameters start with '--'", ph.get(0))
+            .help(&format!("write it as --{}", flag.trim_start_matches('-'))),
+        );
+      };
+
+      // `--name="default"` is a single flag token followed by the value
+      match name.strip_suffix('=') {
+        Some(name) => (name.to_owned(), true, true),
+        None => (name.to_owned(), true, false),
+      }
+    }
+    Some(_) => return Err(Error::unexpected(ph, "identifier, flag or `)`")),
+    None => return Err(Error::end(ph, "identifier, flag or `)`")),
   };
 
   ph.advance();
 
+  let r#type = if !has_default && ph.peek(0) == Some(&TT::Colon) {
+    ph.advance();
+    Some(parse_type(ph)?)
+  } else {
+    None
+  };
+
+  if !has_default && ph.peek(0) == Some(&TT::Assignment) {
+    ph.advance();
+    has_default = true;
+  }
+
+  let default = if has_default {
+    Some(value::parse_inner(ph)?)
+  } else {
+    None
+  };
+
+  let param = Param {
+    name,
+    named,
+    r#type,
+    default,
+    span: ph.span_from(start),
+  };
+
+  if param.r#type == Some(ParamType::Bool) && !param.named {
+    return Err(
+      Error::at("Only named parameters can be bool", param.span)
+        .help(&format!("make it a flag with `--{}: bool`", param.name)),
+    );
+  }
+
+  if param.is_flag() && param.default.is_some() {
+    return Err(Error::at(
+      "Flags can't have a default value, they are off unless passed",
+      param.span,
+    ));
+  }
+
+  if let Some(default) = &param.default {
+    check_type(&param, default)?;
+  }
+
+  Ok(param)
+}
+
+fn parse_params(ph: &mut ParseHelper) -> ParserResult<Vec<Param>> {
   check_token!(ph, TT::LParen);
 
   ph.advance();
 
-  let mut params = vec![];
+  let mut params: Vec<Param> = vec![];
 
-  loop {
-    match ph.peek(0) {
-      Some(TT::Identifier(name)) => params.push(name.clone()),
-      Some(TT::RParen) => break,
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
-    };
+  while ph.peek(0) != Some(&TT::RParen) {
+    let param = parse_param(ph)?;
 
-    ph.advance();
+    if params
+      .iter()
+      .any(|other| other.variable() == param.variable())
+    {
+      return Err(Error::at(
+        &format!("Duplicate parameter {}", param.name),
+        param.span,
+      ));
+    }
+
+    let after_default = params
+      .iter()
+      .any(|other| !other.named && other.default.is_some());
+    if !param.named && param.default.is_none() && after_default {
+      return Err(Error::at(
+        &format!(
+          "Parameter {} needs a default value, it follows one that has one",
+          param.name
+        ),
+        param.span,
+      ));
+    }
+
+    params.push(param);
 
     match ph.peek(0) {
       Some(TT::Comma) => ph.advance(),
       Some(TT::RParen) => break,
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
-    };
+      Some(_) => return Err(Error::unexpected(ph, "`,` or `)`")),
+      None => return Err(Error::end(ph, "`,` or `)`")),
+    }
   }
 
   check_token!(ph, TT::RParen);
 
   ph.advance();
 
+  Ok(params)
+}
+
+/// Rejects literals that can't be converted to the type of `param`
+fn check_type(param: &Param, value: &Value) -> ParserResult<()> {
+  let ValueKind::Literal(literal) = &value.kind else {
+    return Ok(());
+  };
+
+  let valid = match (param.r#type, literal) {
+    (_, Literal::Array(_) | Literal::Map(_)) => false,
+    (None | Some(ParamType::String), _) | (Some(ParamType::Int), Literal::Int(_)) => true,
+    (Some(ParamType::Int), Literal::String(string) | Literal::RawString(string)) => {
+      string.parse::<i32>().is_ok()
+    }
+    (Some(ParamType::Int | ParamType::Bool), _) => false,
+  };
+
+  if valid {
+    Ok(())
+  } else {
+    let r#type = param.r#type.unwrap_or(ParamType::String);
+
+    Err(Error::at(
+      &format!("Expected {type} for parameter {}", param.name),
+      value.span,
+    ))
+  }
+}
+
+/// Value given to a named argument
+enum NamedValue<'a> {
+  None,
+  /// Part of the flag, like in `--name=value` without quotes
+  Raw,
+  Value(&'a Value),
+}
+
+/// Argument passed as `--name` or `--name=value`
+fn named_argument(arg: &Value) -> Option<(&str, NamedValue<'_>)> {
+  match &arg.kind {
+    ValueKind::NamedArgument(name, value) => Some((name.as_str(), NamedValue::Value(value))),
+    ValueKind::Literal(Literal::RawString(flag)) => {
+      let flag = flag.strip_prefix("--")?;
+
+      Some(
+        flag
+          .split_once('=')
+          .map_or((flag, NamedValue::None), |(name, _)| {
+            (name, NamedValue::Raw)
+          }),
+      )
+    }
+    _ => None,
+  }
+}
+
+fn arguments(count: usize) -> String {
+  match count {
+    1 => "1 argument".to_owned(),
+    _ => format!("{count} arguments"),
+  }
+}
+
+/// `@` or an array variable, which expand to any number of arguments
+fn is_spread(ph: &ParseHelper, arg: &Value) -> bool {
+  matches!(&arg.kind, ValueKind::Identifier(name) if name == "@" || ph.arrays.contains(name))
+}
+
+/// Error for a call of `name` with `count` positional arguments, of which
+/// the function takes `required` to `positional`
+fn arity_error(name: &str, required: usize, positional: usize, count: usize, span: Span) -> Error {
+  let takes = if required == positional {
+    arguments(positional)
+  } else if count < required {
+    format!("at least {}", arguments(required))
+  } else {
+    format!("at most {}", arguments(positional))
+  };
+
+  Error::at(
+    &format!(
+      "Function {name} takes {takes} but {count} {} given",
+      if count == 1 { "was" } else { "were" }
+    ),
+    span,
+  )
+}
+
+/// Checks the arguments of a call against the signature of the function
+pub fn check_call(
+  ph: &ParseHelper,
+  name: &str,
+  signature: &Signature,
+  args: &[Value],
+  span: Span,
+) -> ParserResult<()> {
+  let params = &signature.params;
+  let positional: Vec<&Param> = params.iter().filter(|param| !param.named).collect();
+  let required = positional
+    .iter()
+    .filter(|param| param.default.is_none())
+    .count();
+
+  // which parameters the arguments after a spread go to isn't known
+  let spread = args.iter().any(|arg| is_spread(ph, arg));
+  let count = args
+    .iter()
+    .filter(|arg| named_argument(arg).is_none())
+    .count();
+  let mut index = 0;
+
+  for arg in args {
+    let Some((flag, value)) = named_argument(arg) else {
+      if spread || (signature.variadic && index >= positional.len()) {
+        continue;
+      }
+
+      let Some(param) = positional.get(index) else {
+        return Err(arity_error(
+          name,
+          required,
+          positional.len(),
+          count,
+          arg.span,
+        ));
+      };
+
+      check_type(param, arg)?;
+      index += 1;
+      continue;
+    };
+
+    let Some(param) = params
+      .iter()
+      .find(|param| param.named && param.name == flag)
+    else {
+      // extra flags end up in `@`
+      if signature.variadic {
+        continue;
+      }
+
+      return Err(Error::at(
+        &format!("Function {name} has no parameter --{flag}"),
+        arg.span,
+      ));
+    };
+
+    match value {
+      NamedValue::Raw | NamedValue::Value(_) if param.is_flag() => {
+        return Err(Error::at(
+          &format!("--{flag} is a flag and takes no value"),
+          arg.span,
+        ))
+      }
+      NamedValue::Value(value) => check_type(param, value)?,
+      NamedValue::Raw => {}
+      NamedValue::None if param.is_flag() => {}
+      NamedValue::None => {
+        return Err(
+          Error::at(&format!("--{flag} needs a value"), arg.span)
+            .help(&format!("pass it as --{flag}=...")),
+        )
+      }
+    }
+  }
+
+  if !spread && count < required {
+    return Err(arity_error(name, required, positional.len(), count, span));
+  }
+
+  Ok(())
+}
+
+/// Whether the block starting at the current token reads `@`
+fn reads_arguments(ph: &ParseHelper) -> bool {
+  let mut braces_level = 0usize;
+  let mut offset = 0;
+
+  while let Some(token) = ph.peek(offset) {
+    match token {
+      TT::LBrace => braces_level += 1,
+      TT::RBrace if braces_level <= 1 => return false,
+      TT::RBrace => braces_level -= 1,
+      TT::At => return true,
+      _ => {}
+    }
+
+    offset += 1;
+  }
+
+  false
+}
+
+/// Index of the token after the end of the declaration starting at the
+/// parameters in `tokens`, that is after its body or the end of `tokens`
+fn declaration_end(tokens: &[Token]) -> usize {
+  let mut parens_level = 0usize;
+  let mut braces_level = 0usize;
+
+  for (i, token) in tokens.iter().enumerate() {
+    match token.r#type {
+      TT::LParen => parens_level += 1,
+      TT::RParen => parens_level = parens_level.saturating_sub(1),
+      TT::LBrace => braces_level += 1,
+      TT::RBrace if braces_level <= 1 && parens_level == 0 => return i + 1,
+      TT::RBrace => braces_level -= 1,
+      _ => {}
+    }
+  }
+
+  tokens.len()
+}
+
+/// Signatures of every function declared in `tokens`, nested ones included,
+/// so calls can be checked before the declaration is parsed
+pub fn scan(tokens: &[Token]) -> HashMap<String, Signature> {
+  let mut functions = HashMap::new();
+
+  for (i, window) in tokens.windows(2).enumerate() {
+    let [Token {
+      r#type: TT::Function,
+      span,
+    }, Token {
+      r#type: TT::Identifier(name),
+      ..
+    }] = window
+    else {
+      continue;
+    };
+
+    // only the declaration itself is copied, the rest of the file can be long
+    let declaration = &tokens[i + 2..];
+    let declaration = &declaration[..declaration_end(declaration)];
+
+    let variables = (0..10).map(|x| x.to_string()).collect();
+    let mut ph = ParseHelper::new(declaration.to_vec(), variables, HashMap::new());
+
+    // defaults that don't parse on their own are left to the parser, any
+    // call is accepted until then
+    let signature = match parse_params(&mut ph) {
+      Ok(params) => Signature {
+        params,
+        variadic: reads_arguments(&ph),
+        declared: Some(span.start),
+      },
+      Err(_) => Signature {
+        params: Vec::new(),
+        variadic: true,
+        declared: Some(span.start),
+      },
+    };
+
+    functions.insert(name.clone(), signature);
+  }
+
+  functions
+}
+
+pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
+  let start = ph.start();
+
+  ph.advance();
+
+  let name = match ph.peek(0) {
+    Some(TT::Identifier(name)) => name.clone(),
+    Some(_) => return Err(Error::unexpected(ph, "identifier")),
+    None => return Err(Error::end(ph, "identifier")),
+  };
+
+  ph.advance();
+
+  let params = parse_params(ph)?;
+
   let variables = ph
     .variables
     .iter()
-    .chain(params.iter())
     .cloned()
+    .chain(params.iter().map(Param::variable))
     .collect::<HashSet<_>>();
 
-  let block = block::parse(ph, variables)?;
-  let node = Node::Function(Function::new(name, params, Box::new(block)));
+  let runs_later = std::mem::replace(&mut ph.runs_later, true);
+  let block = block::parse(ph, variables);
+  ph.runs_later = runs_later;
+  let block = block?;
+
+  let node = Node::new(
+    NodeKind::Function(Function::new(name, params, Box::new(block))),
+    ph.span_from(start),
+  );
 
   Ok(node)
 }
@@ -1,15 +1,48 @@
 use super::{
   error::{Error, ParserResult},
-  node::Node,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
-  value::{Literal, Value},
+  value::{Literal, Value, ValueKind},
+};
+use crate::{
+  check_token,
+  parse::{function, interpolation, utils, value},
+  types::{Span, StringToken, TT},
 };
-use crate::{check_token, parse::value, types::TT};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Next {
   Call(Box<FunctionCall>),
-  File(String),
+}
+
+/// Stream a redirection to or from a file applies to
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RedirectKind {
+  /// `< file`
+  Input,
+  /// `> file`, or just `"file"`
+  Output,
+  /// `>> file`
+  Append,
+  /// `2> file`
+  Error,
+  /// `2>> file`
+  AppendError,
+  /// `&> file`, both stdout and stderr
+  All,
+  /// `&>> file`
+  AppendAll,
+}
+
+/// Redirection written after a `|` like a pipe, e.g. `cmd() | 2>&1 | > log`
+#[derive(Debug, PartialEq, Clone)]
+pub enum Redirect {
+  /// The file is a string or a variable
+  File(RedirectKind, Value),
+  /// `2>&1`
+  ErrorToOutput,
+  /// `>&2`
+  OutputToError,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -17,6 +50,7 @@ pub struct FunctionCall {
   pub name: String,
   pub args: Vec<Value>,
   pub is_daemon: bool,
+  pub redirects: Vec<Redirect>,
   pub next: Option<Next>,
 }
 
@@ -26,21 +60,55 @@ impl FunctionCall {
       name,
       args,
       is_daemon,
+      redirects: Vec::new(),
       next,
     }
   }
 }
 
+/// Builtins of every target shell, which strict mode accepts without a
+/// declaration
+const BUILTINS: &[&str] = &[
+  "alias", "bg", "cd", "command", "echo", "eval", "exec", "exit", "export", "false", "fg",
+  "getopts", "hash", "jobs", "kill", "local", "printf", "pwd", "read", "readonly", "set", "shift",
+  "source", "test", "times", "trap", "true", "type", "ulimit", "umask", "unalias", "unset", "wait",
+];
+
+fn is_command(ph: &ParseHelper, name: &str) -> bool {
+  ph.commands.contains(name) || BUILTINS.contains(&name)
+}
+
+fn unknown_command(ph: &ParseHelper, name: &str, span: Span) -> Error {
+  let similar = ph
+    .functions
+    .keys()
+    .chain(&ph.commands)
+    .map(|other| (utils::edit_distance(name, other), other))
+    .filter(|(distance, _)| *distance <= (name.len() / 3).max(1))
+    .min();
+
+  let help = match similar {
+    Some((_, other)) => format!("did you mean `{other}`?"),
+    None => format!("declare it with `external fn {name};`"),
+  };
+
+  Error::at(&format!("Unknown command {name}"), span).help(&help)
+}
+
 #[derive(Debug, PartialEq, Clone)]
 enum FType {
   Call,
   Aritmetics,
   String(String),
+  Interpolated(Vec<StringToken>),
   RawString(String),
   Variable(String),
 }
 
 pub fn parse_inner(ph: &mut ParseHelper) -> ParserResult<FunctionCall> {
+  let start = ph.start();
+  let first_span = ph.get(0).map(|token| token.span).unwrap_or_default();
+
   let (name, type_) = if let Some(token) = ph.peek(0) {
     match token {
       TT::Identifier(name) => {
@@ -51,31 +119,66 @@ pub fn parse_inner(ph: &mut ParseHelper) -> ParserResult<FunctionCall> {
         }
       }
       TT::String(string) => ("printf".to_owned(), FType::String(string.clone())),
+      TT::InterpolatedString(parts) => ("printf".to_owned(), FType::Interpolated(parts.clone())),
       TT::RawString(string) => ("printf".to_owned(), FType::RawString(string.clone())),
       TT::Dollar => ("$".to_owned(), FType::Aritmetics),
-      _ => return Err(Error::unexpected(ph)),
+      _ => {
+        return Err(Error::unexpected(
+          ph,
+          "identifier, string, raw string or `$`",
+        ))
+      }
     }
   } else {
-    return Err(Error::end(ph));
+    return Err(Error::end(ph, "identifier, string, raw string or `$`"));
   };
 
   ph.advance();
 
   let args = match &type_ {
-    FType::String(string) => vec![Value::Literal(Literal::String(string.clone()))],
-    FType::RawString(string) => vec![Value::Literal(Literal::RawString(string.clone()))],
-    FType::Variable(name) => vec![Value::Identifier(name.clone())],
+    FType::String(string) => vec![Value::new(
+      ValueKind::Literal(Literal::String(string.clone())),
+      first_span,
+    )],
+    FType::Interpolated(parts) => vec![Value::new(
+      ValueKind::Literal(Literal::Interpolated(interpolation::parse(ph, parts)?)),
+      first_span,
+    )],
+    FType::RawString(string) => vec![Value::new(
+      ValueKind::Literal(Literal::RawString(string.clone())),
+      first_span,
+    )],
+    FType::Variable(name) => vec![Value::new(ValueKind::Identifier(name.clone()), first_span)],
     _ => parse_args(ph)?,
   };
 
+  let call_span = ph.span_from(start);
+
+  if let Some(signature) = ph.functions.get(&name) {
+    if !ph.syntax_only {
+      // the shell only knows the function once its declaration has run
+      if !ph.runs_later && signature.declared.is_some_and(|declared| declared > start) {
+        return Err(
+          Error::at(
+            &format!("Function {name} is called before it's declared"),
+            first_span,
+          )
+          .help("move the declaration above the call"),
+        );
+      }
+
+      function::check_call(ph, &name, signature, &args, call_span)?;
+    }
+  } else if type_ == FType::Call && ph.strict && !is_command(ph, &name) {
+    return Err(unknown_command(ph, &name, first_span));
+  }
+
+  let redirects = parse_redirects(ph)?;
+
   let next = if let Some(TT::Pipe) = ph.peek(0) {
     ph.advance();
 
-    if let Some(TT::String(string)) = ph.peek(0) {
-      Some(Next::File(string.clone()))
-    } else {
-      Some(Next::Call(Box::new(parse_inner(ph)?)))
-    }
+    Some(Next::Call(Box::new(parse_inner(ph)?)))
   } else {
     None
   };
@@ -87,20 +190,104 @@ pub fn parse_inner(ph: &mut ParseHelper) -> ParserResult<FunctionCall> {
     false
   };
 
-  if type_ == FType::Aritmetics && next.is_some() {
+  // a multi-line string piped into a command is given to it as a heredoc
+  let name = match type_ {
+    FType::String(_) | FType::Interpolated(_) | FType::RawString(_)
+      if first_span.start.0 != first_span.end.0 && (next.is_some() || !redirects.is_empty()) =>
+    {
+      "<<".to_owned()
+    }
+    _ => name,
+  };
+
+  if type_ == FType::Aritmetics && (next.is_some() || !redirects.is_empty()) {
     let new = FunctionCall {
       name: "echo".to_owned(),
-      args: vec![Value::FunctionCall(FunctionCall::new(
-        name, args, None, is_daemon,
-      ))],
+      args: vec![Value::new(
+        ValueKind::FunctionCall(FunctionCall::new(name, args, None, is_daemon)),
+        call_span,
+      )],
       is_daemon,
+      redirects,
       next,
     };
 
     return Ok(new);
   }
 
-  Ok(FunctionCall::new(name, args, next, is_daemon))
+  Ok(FunctionCall {
+    redirects,
+    ..FunctionCall::new(name, args, next, is_daemon)
+  })
+}
+
+/// Parses the redirections after a call, up to the `|` of a pipe if any
+fn parse_redirects(ph: &mut ParseHelper) -> ParserResult<Vec<Redirect>> {
+  let mut redirects = Vec::new();
+
+  while ph.peek(0) == Some(&TT::Pipe) {
+    // number of tokens of the operator, and the stream of redirections to
+    // a file, which is parsed next
+    let (length, kind) = match (ph.peek(1), ph.peek(2), ph.peek(3), ph.peek(4)) {
+      (Some(TT::String(_) | TT::InterpolatedString(_) | TT::RawString(_)), ..) => {
+        (0, Some(RedirectKind::Output))
+      }
+      (Some(TT::Less), ..) => (1, Some(RedirectKind::Input)),
+      (Some(TT::Greater), Some(TT::Greater), ..) => (2, Some(RedirectKind::Append)),
+      (Some(TT::Greater), Some(TT::Daemon), Some(TT::Integer(2)), _) => {
+        redirects.push(Redirect::OutputToError);
+        (3, None)
+      }
+      (Some(TT::Greater), ..) => (1, Some(RedirectKind::Output)),
+      (Some(TT::Integer(2)), Some(TT::Greater), Some(TT::Greater), _) => {
+        (3, Some(RedirectKind::AppendError))
+      }
+      (Some(TT::Integer(2)), Some(TT::Greater), Some(TT::Daemon), Some(TT::Integer(1))) => {
+        redirects.push(Redirect::ErrorToOutput);
+        (4, None)
+      }
+      (Some(TT::Integer(2)), Some(TT::Greater), ..) => (2, Some(RedirectKind::Error)),
+      (Some(TT::Daemon), Some(TT::Greater), Some(TT::Greater), _) => {
+        (3, Some(RedirectKind::AppendAll))
+      }
+      (Some(TT::Daemon), Some(TT::Greater), ..) => (2, Some(RedirectKind::All)),
+      _ => break,
+    };
+
+    // the `|` and the operator
+    for _ in 0..=length {
+      ph.advance();
+    }
+
+    if let Some(kind) = kind {
+      redirects.push(Redirect::File(kind, parse_redirect_target(ph)?));
+    }
+  }
+
+  Ok(redirects)
+}
+
+/// File of a redirection, a string or a variable holding one
+fn parse_redirect_target(ph: &mut ParseHelper) -> ParserResult<Value> {
+  let span = ph.get(0).map(|token| token.span).unwrap_or_default();
+
+  let kind = match ph.peek(0) {
+    Some(TT::String(string)) => ValueKind::Literal(Literal::String(string.clone())),
+    Some(TT::RawString(string)) => ValueKind::Literal(Literal::RawString(string.clone())),
+    Some(TT::InterpolatedString(parts)) => {
+      ValueKind::Literal(Literal::Interpolated(interpolation::parse(ph, parts)?))
+    }
+    Some(TT::Identifier(name)) if ph.syntax_only || ph.variables.contains(name) => {
+      ValueKind::Identifier(name.clone())
+    }
+    Some(TT::Identifier(_)) => return Err(Error::undefined_variable(ph)),
+    Some(_) => return Err(Error::unexpected(ph, "string, raw string or identifier")),
+    None => return Err(Error::end(ph, "string, raw string or identifier")),
+  };
+
+  ph.advance();
+
+  Ok(Value::new(kind, span))
 }
 
 fn parse_args(ph: &mut ParseHelper) -> Result<Vec<Value>, Error> {
@@ -115,8 +302,30 @@ fn parse_args(ph: &mut ParseHelper) -> Result<Vec<Value>, Error> {
     }
 
     let arg = if let Some(TT::Flag(str)) = ph.peek(0).cloned() {
+      let start = ph.start();
+      let span = ph.get(0).unwrap().span;
       ph.advance();
-      Value::Literal(Literal::RawString(str))
+
+      // `--name="value"` and `--name = value`, the value being an expression
+      let name = match (str.strip_suffix('='), ph.peek(0)) {
+        (Some(name), _) => Some(name.to_owned()),
+        (None, Some(TT::Assignment)) => {
+          ph.advance();
+          Some(str.clone())
+        }
+        _ => None,
+      };
+
+      match name.as_deref().and_then(|name| name.strip_prefix("--")) {
+        Some(name) => {
+          let value = value::parse_inner(ph)?;
+          Value::new(
+            ValueKind::NamedArgument(name.to_owned(), Box::new(value)),
+            ph.span_from(start),
+          )
+        }
+        None => Value::new(ValueKind::Literal(Literal::RawString(str)), span),
+      }
     } else {
       value::parse_inner(ph)?
     };
@@ -126,9 +335,9 @@ fn parse_args(ph: &mut ParseHelper) -> Result<Vec<Value>, Error> {
     match ph.peek(0) {
       Some(TT::Comma) => ph.advance(),
       Some(TT::RParen) => break,
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
-    };
+      Some(_) => return Err(Error::unexpected(ph, "`,` or `)`")),
+      None => return Err(Error::end(ph, "`,` or `)`")),
+    }
   }
 
   check_token!(ph, TT::RParen);
@@ -139,12 +348,19 @@ fn parse_args(ph: &mut ParseHelper) -> Result<Vec<Value>, Error> {
 }
 
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
-  check_token!(ph, TT::Identifier(..) | TT::Dollar);
+  check_token!(
+    ph,
+    TT::Identifier(..) | TT::Dollar | TT::String(_) | TT::InterpolatedString(_) | TT::RawString(_)
+  );
 
+  let start = ph.start();
   let fn_call = parse_inner(ph)?;
 
   check_token!(ph, TT::Semicolon);
   ph.advance();
 
-  Ok(Node::FunctionCall(fn_call))
+  Ok(Node::new(
+    NodeKind::FunctionCall(fn_call),
+    ph.span_from(start),
+  ))
 }
@@ -2,7 +2,7 @@ use super::{
   block::{self, Block},
   error::{Error, ParserResult},
   function_call::FunctionCall,
-  node::Node,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
   value::Value,
 };
@@ -102,11 +102,15 @@ fn parse_with_let(ph: &mut ParseHelper) -> ParserResult<IfLet> {
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
   check_token!(ph, TT::If);
 
+  let start = ph.start();
+
   ph.advance();
 
-  match ph.peek(0) {
-    Some(TT::Let) => Ok(Node::IfLet(parse_with_let(ph)?)),
-    Some(_) => Ok(Node::If(parse_inner(ph)?)),
-    None => Err(Error::end(ph)),
-  }
+  let kind = match ph.peek(0) {
+    Some(TT::Let) => NodeKind::IfLet(parse_with_let(ph)?),
+    Some(_) => NodeKind::If(parse_inner(ph)?),
+    None => return Err(Error::end(ph, "condition")),
+  };
+
+  Ok(Node::new(kind, ph.span_from(start)))
 }
@@ -1,21 +1,31 @@
-use std::{fs::File, io::Read, path::Path};
+use std::{fs::File, io::Read, path::Path, rc::Rc, slice};
 
 use super::{
   error::{Error, ParserResult},
-  node::Node,
+  function,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
+  r#if::{Else, If},
+  signal::Signal,
 };
 use crate::{
   check_token,
-  types::{Token, TT},
+  types::{Span, Token, TT},
 };
 
+/// File inlined at transpile time
+#[derive(Debug, PartialEq, Clone)]
+pub struct Import {
+  pub path: String,
+  pub tree: Vec<Node>,
+}
+
 fn read_file(path: &Path, token: &Token) -> Result<String, Error> {
   let mut file = match File::open(path) {
     Ok(path) => path,
     Err(e) => {
       return Err(Error::new(
-        &format!("Couldn't open file '{path:?}', error: '{e}'"),
+        &format!("Couldn't open file '{}', error: '{e}'", path.display()),
         Some(token),
       ))
     }
@@ -26,7 +36,7 @@ fn read_file(path: &Path, token: &Token) -> Result<String, Error> {
   match file.read_to_string(&mut contents) {
     Ok(_) => Ok(contents),
     Err(e) => Err(Error::new(
-      &format!("Error reading file '{path:?}', error: '{e}'"),
+      &format!("Error reading file '{}', error: '{e}'", path.display()),
       Some(token),
     )),
   }
@@ -36,6 +46,7 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Vec<Node>> {
   check_token!(ph, TT::Import | TT::Source);
 
   let token = ph.get(0).cloned().unwrap();
+  let start = ph.start();
 
   ph.advance();
 
@@ -43,9 +54,9 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Vec<Node>> {
 
   match ph.peek(0) {
     Some(TT::String(string)) => files.push(string.clone()),
-    Some(_) => return Err(Error::unexpected(ph)),
-    None => return Err(Error::end(ph)),
-  };
+    Some(_) => return Err(Error::unexpected(ph, "string")),
+    None => return Err(Error::end(ph, "string")),
+  }
 
   ph.advance();
 
@@ -53,55 +64,136 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Vec<Node>> {
     match ph.peek(0) {
       Some(TT::Comma) => {}
       Some(TT::Semicolon) => break,
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
-    };
+      Some(_) => return Err(Error::unexpected(ph, "`,` or `;`")),
+      None => return Err(Error::end(ph, "`,` or `;`")),
+    }
 
     ph.advance();
 
     match ph.peek(0) {
       Some(TT::String(string)) => files.push(string.clone()),
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
-    };
+      Some(_) => return Err(Error::unexpected(ph, "string")),
+      None => return Err(Error::end(ph, "string")),
+    }
 
     ph.advance();
   }
 
   ph.advance();
 
-  if token.r#type == TT::Import {
-    macro_rules! unwrap_or_error {
-      ($input:expr, $file:ident) => {
-        match $input {
-          Ok(a) => a,
-          Err(e) => {
-            return Err(Error::new(
-              &format!("Error while importing {}:\n{e}", $file),
-              Some(&token),
-            ))
-          }
+  let span = ph.span_from(start);
+
+  if token.r#type == TT::Import && ph.syntax_only {
+    Ok(
+      files
+        .into_iter()
+        .map(|path| Node::new(NodeKind::Import(Import { path, tree: vec![] }), span))
+        .collect(),
+    )
+  } else if token.r#type == TT::Import {
+    let mut nodes = Vec::new();
+
+    for file in files {
+      let contents = read_file(Path::new(&file), &token)?;
+
+      let tokens = crate::tokenize::tokenize(&contents)
+        .map_err(|e| Error::at(&e.msg, Span::new(e.pos, e.pos)).in_file(&file))?;
+
+      // functions of the imported file can be called from this one too.
+      // Where they're declared only counts within the same file
+      let functions = function::scan(&tokens);
+
+      let mut child = ph.child(&tokens, ph.variables.clone());
+      let inherited = Rc::make_mut(&mut child.functions);
+      for signature in inherited.values_mut() {
+        signature.declared = None;
+      }
+      inherited.extend(functions.clone());
+
+      Rc::make_mut(&mut ph.functions).extend(functions.into_iter().map(|(name, mut signature)| {
+        signature.declared = None;
+        (name, signature)
+      }));
+
+      // errors in the imported file are reported along with the ones in this
+      // file, the import itself is skipped
+      match crate::parse::inner(child) {
+        Ok((tree, exports)) => {
+          ph.variables.extend(exports.iter().cloned());
+          ph.exports.extend(exports.iter().cloned());
+
+          nodes.push(Node::new(
+            NodeKind::Import(Import { path: file, tree }),
+            span,
+          ));
+        }
+        Err(errors) => {
+          let errors = errors.into_iter().map(|e| e.in_file(&file));
+          ph.errors.extend(errors);
         }
-      };
+      }
     }
 
-    files
-      .into_iter()
-      .map(|file| {
-        let contents = read_file(Path::new(&file), &token)?;
+    Ok(nodes)
+  } else {
+    Ok(
+      files
+        .into_iter()
+        .map(|file| Node::new(NodeKind::Source(file), span))
+        .collect(),
+    )
+  }
+}
+
+/// Paths of the files imported by the tree, directly or through other
+/// imports
+pub fn files(tree: &[Node]) -> Vec<String> {
+  let mut files = Vec::new();
+  collect_files(tree, &mut files);
+  files
+}
 
-        let tokens = unwrap_or_error!(crate::tokenize(&contents), file);
-        let (tree, exports) =
-          unwrap_or_error!(crate::parse::inner(&tokens, ph.variables.clone()), file);
+fn collect_files(nodes: &[Node], files: &mut Vec<String>) {
+  for node in nodes {
+    match &node.kind {
+      NodeKind::Import(Import { path, tree }) => {
+        files.push(path.clone());
+        collect_files(tree, files);
+      }
+      NodeKind::Block(block) | NodeKind::Defer(block) | NodeKind::Signal(Signal { block, .. }) => {
+        collect_files(block, files);
+      }
+      NodeKind::Function(function) => collect_files(slice::from_ref(&function.block), files),
+      NodeKind::If(r#if) => collect_if_files(r#if, files),
+      NodeKind::IfLet(if_let) => {
+        collect_files(&if_let.block, files);
+        collect_files(if_let.r#else.as_deref().unwrap_or_default(), files);
+      }
+      NodeKind::For(r#for) => collect_files(slice::from_ref(&r#for.block), files),
+      NodeKind::Foreach(foreach) => collect_files(&foreach.block, files),
+      NodeKind::While(r#while) => collect_files(&r#while.block, files),
+      NodeKind::WhileLet(while_let) => collect_files(&while_let.block, files),
+      NodeKind::Switch(switch) => {
+        for case in &switch.cases {
+          collect_files(slice::from_ref(&case.block), files);
+        }
+      }
+      NodeKind::Try(r#try) => {
+        collect_files(&r#try.block, files);
+        collect_files(&r#try.catch, files);
+        collect_files(r#try.finally.as_deref().unwrap_or_default(), files);
+      }
+      _ => {}
+    }
+  }
+}
 
-        ph.variables.extend(exports.iter().cloned());
-        ph.exports.extend(exports.iter().cloned());
+fn collect_if_files(r#if: &If, files: &mut Vec<String>) {
+  collect_files(&r#if.block, files);
 
-        Ok(tree)
-      })
-      .collect::<ParserResult<Vec<Vec<Node>>>>()
-      .map(|trees| trees.into_iter().flatten().collect())
-  } else {
-    Ok(files.into_iter().map(Node::Source).collect())
+  match &r#if.r#else {
+    Some(Else::Else(block)) => collect_files(block, files),
+    Some(Else::Elif(r#if)) => collect_if_files(r#if, files),
+    None => {}
   }
 }
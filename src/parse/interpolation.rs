@@ -0,0 +1,44 @@
+use super::{
+  error::{Error, ParserResult},
+  parse_helper::ParseHelper,
+  value::{self, Value},
+};
+use crate::types::{Span, StringToken};
+
+/// Piece of a string with interpolation
+#[derive(Debug, PartialEq, Clone)]
+pub enum StringPart {
+  Text(String),
+  /// `$name` or `${name}`
+  Variable(String, Span),
+  /// `${...}` around an expression
+  Expression(Value),
+}
+
+pub fn parse(ph: &ParseHelper, parts: &[StringToken]) -> ParserResult<Vec<StringPart>> {
+  parts
+    .iter()
+    .map(|part| match part {
+      StringToken::Text(text) => Ok(StringPart::Text(text.clone())),
+      StringToken::Variable(name, span) => {
+        let positional = name == "@" || name.chars().all(|c| c.is_ascii_digit());
+
+        if positional || ph.syntax_only || ph.variables.contains(name) {
+          Ok(StringPart::Variable(name.clone(), *span))
+        } else {
+          Err(Error::undefined(name, *span))
+        }
+      }
+      StringToken::Expression(tokens) => {
+        let mut child = ph.child(tokens, ph.variables.clone());
+        let value = value::parse_inner(&mut child)?;
+
+        if child.peek(0).is_some() {
+          return Err(Error::unexpected(&child, "`}`"));
+        }
+
+        Ok(StringPart::Expression(value))
+      }
+    })
+    .collect()
+}
@@ -14,8 +14,13 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Vec<(String, Value)>> {
     let key = match ph.peek(0) {
       Some(TT::Identifier(key) | TT::String(key) | TT::RawString(key)) => key.clone(),
       Some(TT::RBrace) => break,
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
+      Some(_) => {
+        return Err(Error::unexpected(
+          ph,
+          "identifier, string, raw string or `}`",
+        ))
+      }
+      None => return Err(Error::end(ph, "identifier, string, raw string or `}`")),
     };
 
     ph.advance();
@@ -31,9 +36,9 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Vec<(String, Value)>> {
     match ph.peek(0) {
       Some(TT::Comma) => ph.advance(),
       Some(TT::RBrace) => break,
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
-    };
+      Some(_) => return Err(Error::unexpected(ph, "`,` or `}`")),
+      None => return Err(Error::end(ph, "`,` or `}`")),
+    }
   }
 
   ph.advance();
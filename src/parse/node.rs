@@ -1,19 +1,25 @@
 use super::{
+  block::Block,
   declaration::Declaration,
   function::Function,
   function_call::FunctionCall,
+  import::Import,
   r#for::{For, Foreach},
   r#if::{If, IfLet},
+  r#try::Try,
   r#while::{While, WhileLet},
+  signal::Signal,
   switch::Switch,
   value::Value,
 };
+use crate::types::Span;
 
 #[derive(Debug, PartialEq, Clone)]
-pub enum Node {
+pub enum NodeKind {
   Value(Value),
 
   Source(String),
+  Import(Import),
   Block(Vec<Node>),
   Function(Function),
   FunctionCall(FunctionCall),
@@ -21,7 +27,7 @@ pub enum Node {
   Expression(Value),
   If(If),
   IfLet(IfLet),
-  For(For),
+  For(Box<For>),
   Foreach(Foreach),
   While(While),
   WhileLet(WhileLet),
@@ -29,8 +35,27 @@ pub enum Node {
   Continue,
   Break,
   Switch(Switch),
+  Try(Try),
+  /// Block run when the function returns or the script exits
+  Defer(Block),
+  /// Handler of signals, declared with `on signal(...)`
+  Signal(Signal),
+  /// Names declared with `external`, which produce no code
+  External(Vec<String>),
+  /// Commands declared with `external fn`, which produce no code either
+  ExternalFunction(Vec<String>),
+}
 
-  Empty,
+#[derive(Debug, PartialEq, Clone)]
+pub struct Node {
+  pub kind: NodeKind,
+  pub span: Span,
+}
+
+impl Node {
+  pub fn new(kind: NodeKind, span: Span) -> Self {
+    Self { kind, span }
+  }
 }
 
 impl std::fmt::Display for Node {
@@ -1,22 +1,68 @@
-use std::collections::HashSet;
+use std::{
+  collections::{HashMap, HashSet},
+  rc::Rc,
+};
 
-use crate::types::{Token, TokenType};
+use super::{error::Error, function::Signature};
+use crate::types::{Position, Span, Token, TokenType, TT};
 
 #[derive(Debug)]
 pub struct ParseHelper {
   tokens: Vec<Token>,
   index: usize,
   pub variables: HashSet<String>,
+  /// variables declared with an array, which a call spreads into arguments
+  pub arrays: HashSet<String>,
   pub exports: HashSet<String>,
+  /// functions declared in the file, to check calls against, shared with
+  /// the helpers of nested blocks
+  pub functions: Rc<HashMap<String, Signature>>,
+  /// system commands declared with `external fn`
+  pub commands: HashSet<String>,
+  /// reject calls to commands that weren't declared
+  pub strict: bool,
+  /// in a function body, deferred block or signal handler, which can call
+  /// functions declared after them as they only run later
+  pub runs_later: bool,
+  /// only check the syntax, without resolving variables, checking calls
+  /// against functions or reading imported files
+  pub syntax_only: bool,
+  /// errors recovered from so far
+  pub errors: Vec<Error>,
 }
 
 impl ParseHelper {
-  pub fn new(tokens: Vec<Token>, variables: HashSet<String>) -> ParseHelper {
+  pub fn new(
+    tokens: Vec<Token>,
+    variables: HashSet<String>,
+    functions: HashMap<String, Signature>,
+  ) -> ParseHelper {
     ParseHelper {
       tokens,
       index: 0,
       variables,
+      arrays: HashSet::new(),
       exports: HashSet::new(),
+      functions: Rc::new(functions),
+      commands: HashSet::new(),
+      strict: false,
+      runs_later: false,
+      syntax_only: false,
+      errors: Vec::new(),
+    }
+  }
+
+  /// Helper for a nested block or an imported file, knowing the same
+  /// functions and commands
+  pub fn child(&self, tokens: &[Token], variables: HashSet<String>) -> ParseHelper {
+    ParseHelper {
+      arrays: self.arrays.clone(),
+      commands: self.commands.clone(),
+      strict: self.strict,
+      runs_later: self.runs_later,
+      syntax_only: self.syntax_only,
+      functions: Rc::clone(&self.functions),
+      ..ParseHelper::new(tokens.to_vec(), variables, HashMap::new())
     }
   }
 
@@ -36,23 +82,45 @@ impl ParseHelper {
     &self.tokens
   }
 
-  #[cfg(debug_assertions)]
-  pub fn get_index(&self) -> usize {
-    self.index
+  /// Position where the current token starts
+  pub fn start(&self) -> Position {
+    self
+      .get(0)
+      .or_else(|| self.tokens.last())
+      .map_or_else(Position::default, |token| token.span.start)
   }
 
-  #[cfg(debug_assertions)]
-  pub fn pretty_print_tokens(&self) -> String {
-    self
-      .tokens
-      .iter()
-      .enumerate()
-      .map(|(i, token)| format!("{i}: {token}"))
-      .collect::<Vec<_>>()
-      .join(",\n")
+  /// Span from `start` to the end of the last consumed token
+  pub fn span_from(&self, start: Position) -> Span {
+    let end = self
+      .index
+      .checked_sub(1)
+      .and_then(|i| self.tokens.get(i))
+      .map_or(start, |token| token.span.end);
+
+    Span::new(start, end)
   }
 
   pub fn advance(&mut self) {
     self.index += 1;
   }
+
+  /// Skips the rest of a statement that failed to parse, that is everything
+  /// up to and including the next `;`, or the `}` closing a block the
+  /// statement opened
+  pub fn synchronize(&mut self) {
+    let mut braces_level = 0usize;
+
+    while let Some(token) = self.peek(0).cloned() {
+      self.advance();
+
+      match token {
+        TT::LBrace => braces_level += 1,
+        TT::RBrace if braces_level <= 1 => break,
+        TT::RBrace => braces_level -= 1,
+        TT::Semicolon if braces_level == 0 => break,
+        _ => {}
+      }
+    }
+  }
 }
@@ -1,4 +1,8 @@
-use super::{error::ParserResult, node::Node, parse_helper::ParseHelper};
+use super::{
+  error::ParserResult,
+  node::{Node, NodeKind},
+  parse_helper::ParseHelper,
+};
 use crate::{
   check_token,
   parse::{error::Error, value},
@@ -7,6 +11,9 @@ use crate::{
 
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
   check_token!(ph, TT::Return);
+
+  let start = ph.start();
+
   ph.advance();
 
   let value = if let Some(TT::Integer(int)) = ph.peek(0) {
@@ -18,18 +25,18 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
     }
 
     let int = *int;
+    let span = ph.get(0).unwrap().span;
 
     ph.advance();
 
-    Node::Return(value::Value::Literal(value::Literal::Int(int)))
+    value::Value::new(value::ValueKind::Literal(value::Literal::Int(int)), span)
   } else {
-    let value = value::parse_inner(ph)?;
-    Node::Return(value)
+    value::parse_inner(ph)?
   };
 
   check_token!(ph, TT::Semicolon);
 
   ph.advance();
 
-  Ok(value)
+  Ok(Node::new(NodeKind::Return(value), ph.span_from(start)))
 }
@@ -0,0 +1,95 @@
+use super::{
+  block::{self, Block},
+  error::{Error, ParserResult},
+  node::{Node, NodeKind},
+  parse_helper::ParseHelper,
+};
+use crate::{check_token, types::TT};
+
+/// Signals `trap` takes, by the name it takes them
+const SIGNALS: &[&str] = &[
+  "HUP", "INT", "QUIT", "ILL", "TRAP", "ABRT", "BUS", "FPE", "USR1", "SEGV", "USR2", "PIPE",
+  "ALRM", "TERM", "CHLD", "CONT", "TSTP", "TTIN", "TTOU", "URG", "XCPU", "XFSZ", "VTALRM", "PROF",
+  "WINCH", "IO", "SYS",
+];
+
+/// `on signal("INT", "TERM") { ... }`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Signal {
+  pub signals: Vec<String>,
+  pub block: Block,
+}
+
+impl Signal {
+  pub fn new(signals: Vec<String>, block: Block) -> Self {
+    Self { signals, block }
+  }
+}
+
+/// Whether the statement is a signal handler, `on` not being a keyword
+pub fn is_handler(ph: &ParseHelper) -> bool {
+  matches!(
+    (ph.peek(0), ph.peek(1), ph.peek(2)),
+    (Some(TT::Identifier(on)), Some(TT::Identifier(signal)), Some(TT::LParen))
+      if on == "on" && signal == "signal"
+  )
+}
+
+fn parse_signal(ph: &mut ParseHelper) -> ParserResult<String> {
+  let name = match ph.peek(0) {
+    Some(TT::String(name)) => name.clone(),
+    Some(_) => return Err(Error::unexpected(ph, "string")),
+    None => return Err(Error::end(ph, "string")),
+  };
+
+  if matches!(name.as_str(), "KILL" | "STOP") {
+    return Err(Error::new(
+      &format!("{name} can't be caught, it always ends or stops the process"),
+      ph.get(0),
+    ));
+  }
+
+  if !SIGNALS.contains(&name.as_str()) {
+    let error = Error::new(&format!("Unknown signal '{name}'"), ph.get(0));
+
+    return Err(match name.strip_prefix("SIG") {
+      Some(short) if SIGNALS.contains(&short) => error.help(&format!(
+        "signals are named without the SIG prefix, like \"{short}\""
+      )),
+      _ => error.help(&format!("the signals are {}", SIGNALS.join(", "))),
+    });
+  }
+
+  ph.advance();
+
+  Ok(name)
+}
+
+pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
+  let start = ph.start();
+
+  // `on signal(`
+  ph.advance();
+  ph.advance();
+  ph.advance();
+
+  let mut signals = vec![parse_signal(ph)?];
+
+  while ph.peek(0) == Some(&TT::Comma) {
+    ph.advance();
+    signals.push(parse_signal(ph)?);
+  }
+
+  check_token!(ph, TT::RParen);
+
+  ph.advance();
+
+  let runs_later = std::mem::replace(&mut ph.runs_later, true);
+  let block = block::parse_inner(ph, ph.variables.clone());
+  ph.runs_later = runs_later;
+  let block = block?;
+
+  let signal = Signal::new(signals, block);
+
+  Ok(Node::new(NodeKind::Signal(signal), ph.span_from(start)))
+}
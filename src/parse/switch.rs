@@ -1,4 +1,9 @@
-use super::{error::ParserResult, node::Node, parse_helper::ParseHelper, value::Value};
+use super::{
+  error::ParserResult,
+  node::{Node, NodeKind},
+  parse_helper::ParseHelper,
+  value::Value,
+};
 use crate::{
   check_token,
   parse::{block, error::Error, value},
@@ -24,6 +29,8 @@ impl Switch {
 }
 
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
+  let start = ph.start();
+
   ph.advance();
 
   let arg = value::parse_inner(ph)?;
@@ -38,16 +45,16 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
     match ph.peek(0) {
       Some(TT::Case) => (),
       Some(TT::RBrace) => break,
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
-    };
+      Some(_) => return Err(Error::unexpected(ph, "`case` or `}`")),
+      None => return Err(Error::end(ph, "`case` or `}`")),
+    }
 
     ph.advance();
 
     let value = match ph.peek(0) {
       Some(TT::String(value) | TT::RawString(value)) => value.clone(),
-      Some(_) => return Err(Error::unexpected(ph)),
-      None => return Err(Error::end(ph)),
+      Some(_) => return Err(Error::unexpected(ph, "string")),
+      None => return Err(Error::end(ph, "string")),
     };
 
     ph.advance();
@@ -63,5 +70,8 @@ pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
 
   ph.advance();
 
-  Ok(Node::Switch(Switch::new(arg, cases)))
+  Ok(Node::new(
+    NodeKind::Switch(Switch::new(arg, cases)),
+    ph.span_from(start),
+  ))
 }
@@ -0,0 +1,67 @@
+use super::{
+  block::{self, Block},
+  error::{Error, ParserResult},
+  node::{Node, NodeKind},
+  parse_helper::ParseHelper,
+};
+use crate::{check_token, types::TT};
+
+/// `try { ... } catch error { ... } finally { ... }`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Try {
+  pub block: Block,
+  /// name of the exit code in the catch block
+  pub error: Option<String>,
+  pub catch: Block,
+  pub finally: Option<Block>,
+}
+
+impl Try {
+  pub fn new(block: Block, error: Option<String>, catch: Block, finally: Option<Block>) -> Self {
+    Self {
+      block,
+      error,
+      catch,
+      finally,
+    }
+  }
+}
+
+pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
+  check_token!(ph, TT::Try);
+
+  let start = ph.start();
+
+  ph.advance();
+
+  let block = block::parse_inner(ph, ph.variables.clone())?;
+
+  check_token!(ph, TT::Catch);
+
+  ph.advance();
+
+  let mut variables = ph.variables.clone();
+
+  let error = if let Some(TT::Identifier(name)) = ph.peek(0) {
+    let name = name.clone();
+    ph.advance();
+
+    variables.insert(name.clone());
+    Some(name)
+  } else {
+    None
+  };
+
+  let catch = block::parse_inner(ph, variables)?;
+
+  let finally = if ph.peek(0) == Some(&TT::Finally) {
+    ph.advance();
+    Some(block::parse_inner(ph, ph.variables.clone())?)
+  } else {
+    None
+  };
+
+  let r#try = Try::new(block, error, catch, finally);
+
+  Ok(Node::new(NodeKind::Try(r#try), ph.span_from(start)))
+}
@@ -3,8 +3,33 @@ macro_rules! check_token {
   ($ph:expr, $(|)? $( $pattern:pat_param )|+ ) => {
     match $ph.peek(0) {
       Some($( $pattern )|+)  => {},
-      Some(_) => return Err(Error::unexpected($ph)),
-      _ => return Err(Error::end($ph))
+      Some(_) => {
+        let expected = $crate::types::TokenType::describe_matching(|token| matches!(token, $( $pattern )|+));
+        return Err(Error::unexpected($ph, &expected));
+      }
+      _ => {
+        let expected = $crate::types::TokenType::describe_matching(|token| matches!(token, $( $pattern )|+));
+        return Err(Error::end($ph, &expected));
+      }
     }
   };
 }
+
+/// Number of single character edits turning `a` into `b`
+pub fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+  for (i, a) in a.chars().enumerate() {
+    let mut current = vec![i + 1];
+
+    for (j, b) in b.iter().enumerate() {
+      let substitution = previous[j] + usize::from(a != *b);
+      current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+    }
+
+    previous = current;
+  }
+
+  previous[b.len()]
+}
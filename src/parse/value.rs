@@ -2,13 +2,14 @@ use super::{
   array,
   error::{Error, ParserResult},
   function_call::{self, FunctionCall},
+  interpolation::{self, StringPart},
   map,
-  node::Node,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
 };
 use crate::{
   check_token,
-  types::{TokenType, TT},
+  types::{Position, Span, TokenType, TT},
 };
 
 #[derive(Debug, PartialEq, Clone)]
@@ -17,6 +18,7 @@ pub enum Literal {
   Float(f32),
   Bool(bool),
   String(String),
+  Interpolated(Vec<StringPart>),
   RawString(String),
   Array(Vec<Value>),
   Map(Vec<(String, Value)>),
@@ -68,6 +70,13 @@ impl BinaryOperator {
       _ => None,
     }
   }
+
+  pub fn is_arithmetic(&self) -> bool {
+    matches!(
+      self,
+      Self::Add | Self::Sub | Self::Multiply | Self::Divide | Self::Modulo | Self::Power
+    )
+  }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -97,7 +106,7 @@ impl AssignmentOperator {
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub enum Value {
+pub enum ValueKind {
   Literal(Literal),
   Identifier(String),
   UnaryExpression(UnaryOperator, Box<Value>),
@@ -107,171 +116,284 @@ pub enum Value {
   Assignment(Box<Value>, AssignmentOperator, Box<Value>),
   FunctionCall(FunctionCall),
   Parenthesized(Box<Value>),
+  /// `--name=value` passed to a function, the name without dashes
+  NamedArgument(String, Box<Value>),
+  /// `run(cmd())`, giving the output, errors and exit code of the command
+  Run(FunctionCall),
+  /// `result.code`, a field of the result of [`ValueKind::Run`]
+  Field(Box<Value>, String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Value {
+  pub kind: ValueKind,
+  pub span: Span,
+}
+
+impl Value {
+  pub fn new(kind: ValueKind, span: Span) -> Self {
+    Self { kind, span }
+  }
 }
 
 fn parse_single(ph: &mut ParseHelper) -> ParserResult<Value> {
+  let start = ph.start();
   let token = ph.peek(0);
 
-  let mut value = match token {
+  let kind = match token {
     Some(TT::LParen) => {
       ph.advance();
       let value = parse_inner(ph)?;
       check_token!(ph, TT::RParen);
       ph.advance();
-      Ok(Value::Parenthesized(Box::new(value)))
+      ValueKind::Parenthesized(Box::new(value))
+    }
+
+    // unless a function takes the name
+    Some(TT::Identifier(name))
+      if name == "run" && ph.peek(1) == Some(&TT::LParen) && !ph.functions.contains_key(name) =>
+    {
+      parse_run(ph)?
     }
 
     Some(TT::Identifier(..) | TT::Dollar) if ph.peek(1) == Some(&TT::LParen) => {
-      Ok(Value::FunctionCall(function_call::parse_inner(ph)?))
+      ValueKind::FunctionCall(function_call::parse_inner(ph)?)
     }
 
     Some(TT::Identifier(name)) => {
-      if !ph.variables.contains(name) {
-        return Err(Error::undefined_variable(ph));
+      if !ph.syntax_only && !ph.variables.contains(name) {
+        let assigned = ph
+          .peek(1)
+          .and_then(AssignmentOperator::try_from_token)
+          .is_some();
+
+        return Err(if assigned {
+          Error::undeclared_assignment(ph)
+        } else {
+          Error::undefined_variable(ph)
+        });
       }
 
       if ph.peek(1) == Some(&TT::Pipe) {
-        return Ok(Value::FunctionCall(function_call::parse_inner(ph)?));
+        ValueKind::FunctionCall(function_call::parse_inner(ph)?)
+      } else {
+        let name = name.clone();
+        ph.advance();
+        ValueKind::Identifier(name)
       }
-
-      let name = name.clone();
-      ph.advance();
-      Ok(Value::Identifier(name))
     }
 
     Some(TT::At) => {
       ph.advance();
-      Ok(Value::Identifier("@".into()))
+      ValueKind::Identifier("@".into())
     }
 
-    Some(TT::String(string)) => {
-      if ph.peek(1) == Some(&TT::Pipe) {
-        return Ok(Value::FunctionCall(function_call::parse_inner(ph)?));
-      }
+    // piped into a command
+    Some(TT::String(_) | TT::InterpolatedString(_) | TT::RawString(_))
+      if ph.peek(1) == Some(&TT::Pipe) =>
+    {
+      ValueKind::FunctionCall(function_call::parse_inner(ph)?)
+    }
 
+    Some(TT::String(string)) => {
       let string = string.clone();
       ph.advance();
-      Ok(Value::Literal(Literal::String(string)))
+      ValueKind::Literal(Literal::String(string))
     }
 
-    Some(TT::RawString(string)) => {
-      if ph.peek(1) == Some(&TT::Pipe) {
-        return Ok(Value::FunctionCall(function_call::parse_inner(ph)?));
-      }
+    Some(TT::InterpolatedString(parts)) => {
+      let parts = interpolation::parse(ph, parts)?;
+      ph.advance();
+      ValueKind::Literal(Literal::Interpolated(parts))
+    }
 
+    Some(TT::RawString(string)) => {
       let string = string.clone();
       ph.advance();
-      Ok(Value::Literal(Literal::RawString(string)))
+      ValueKind::Literal(Literal::RawString(string))
     }
 
     Some(TT::Integer(num)) => {
       let num = *num;
       ph.advance();
-      Ok(Value::Literal(Literal::Int(num)))
+      ValueKind::Literal(Literal::Int(num))
     }
 
     Some(TT::Float(num)) => {
       let num = *num;
       ph.advance();
-      Ok(Value::Literal(Literal::Float(num)))
+      ValueKind::Literal(Literal::Float(num))
     }
 
     Some(TT::Boolean(boolean)) => {
       let boolean = *boolean;
       ph.advance();
-      Ok(Value::Literal(Literal::Bool(boolean)))
+      ValueKind::Literal(Literal::Bool(boolean))
     }
 
-    Some(TT::LBracket) => Ok(Value::Literal(Literal::Array(array::parse(ph)?))),
-    Some(TT::LBrace) => Ok(Value::Literal(Literal::Map(map::parse(ph)?))),
+    Some(TT::LBracket) => ValueKind::Literal(Literal::Array(array::parse(ph)?)),
+    Some(TT::LBrace) => ValueKind::Literal(Literal::Map(map::parse(ph)?)),
 
     Some(TT::Not) => {
       ph.advance();
       let value = parse_single(ph)?;
-      Ok(Value::UnaryExpression(UnaryOperator::Not, Box::new(value)))
+      ValueKind::UnaryExpression(UnaryOperator::Not, Box::new(value))
     }
 
     Some(TT::Sub) => {
       ph.advance();
       let value = parse_single(ph)?;
-      Ok(Value::UnaryExpression(
-        UnaryOperator::Minus,
-        Box::new(value),
-      ))
+      ValueKind::UnaryExpression(UnaryOperator::Minus, Box::new(value))
     }
 
-    Some(_) => Err(Error::unexpected(ph)),
-    None => Err(Error::end(ph)),
+    Some(_) => return Err(Error::unexpected(ph, "value")),
+    None => return Err(Error::end(ph, "value")),
   };
 
-  while let Ok(val) = &value {
-    if ph.peek(0) != Some(&TT::LBracket) {
-      break;
-    }
+  parse_postfix(ph, Value::new(kind, ph.span_from(start)), start)
+}
 
-    ph.advance();
+/// Parses the `[member]` and `.field` accesses following `value`
+fn parse_postfix(ph: &mut ParseHelper, mut value: Value, start: Position) -> ParserResult<Value> {
+  loop {
+    let kind = match ph.peek(0) {
+      Some(TT::LBracket) => {
+        ph.advance();
 
-    let member = parse_single(ph)?;
+        let member = parse_single(ph)?;
 
-    value = Ok(Value::MemberExpression(
-      Box::new(val.clone()),
-      Box::new(member),
-    ));
+        check_token!(ph, TT::RBracket);
 
-    check_token!(ph, TT::RBracket);
+        ph.advance();
 
-    ph.advance();
+        ValueKind::MemberExpression(Box::new(value), Box::new(member))
+      }
+      Some(TT::Dot) => {
+        ph.advance();
+
+        let Some(TT::Identifier(field)) = ph.peek(0) else {
+          return Err(Error::unexpected(ph, "identifier"));
+        };
+        let field = field.clone();
+
+        ph.advance();
+
+        ValueKind::Field(Box::new(value), field)
+      }
+      _ => break,
+    };
+
+    value = Value::new(kind, ph.span_from(start));
   }
 
-  value
+  Ok(value)
+}
+
+/// Parses `run(cmd())`
+fn parse_run(ph: &mut ParseHelper) -> ParserResult<ValueKind> {
+  // `run` and `(`
+  ph.advance();
+  ph.advance();
+
+  let call = function_call::parse_inner(ph)?;
+
+  check_token!(ph, TT::RParen);
+  ph.advance();
+
+  Ok(ValueKind::Run(call))
+}
+
+/// Binding power of a binary operator, higher binds tighter. Relational
+/// comparisons bind tighter than (in)equality, like they do in the shell's
+/// arithmetic.
+fn binary_precedence(operator: &BinaryOperator) -> u8 {
+  match operator {
+    BinaryOperator::Or => 3,
+    BinaryOperator::And => 4,
+    BinaryOperator::RegexMatch => 5,
+    BinaryOperator::Equal | BinaryOperator::NotEqual => 6,
+    BinaryOperator::Greater
+    | BinaryOperator::GreaterEqual
+    | BinaryOperator::Less
+    | BinaryOperator::LessEqual => 7,
+    BinaryOperator::Add | BinaryOperator::Sub => 8,
+    BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 9,
+    BinaryOperator::Power => 10,
+  }
 }
 
-fn parse_stage_1(ph: &mut ParseHelper) -> ParserResult<Value> {
+const ASSIGNMENT_PRECEDENCE: u8 = 1;
+const TERNARY_PRECEDENCE: u8 = 2;
+
+/// Parses operators binding at least as tightly as `min_precedence`, using
+/// precedence climbing
+fn parse_expression(ph: &mut ParseHelper, min_precedence: u8) -> ParserResult<Value> {
   let mut left = parse_single(ph)?;
 
   while let Some(token) = ph.peek(0) {
-    if let Some(operator) = BinaryOperator::try_from_token(token) {
+    let start = left.span.start;
+
+    let kind = if let Some(operator) = BinaryOperator::try_from_token(token) {
+      let precedence = binary_precedence(&operator);
+      if precedence < min_precedence {
+        break;
+      }
+
+      // `**` is right associative, the rest is left associative
+      let right_precedence = if operator == BinaryOperator::Power {
+        precedence
+      } else {
+        precedence + 1
+      };
+
+      ph.advance();
+      let right = parse_expression(ph, right_precedence)?;
+      ValueKind::BinaryExpression(Box::new(left), operator, Box::new(right))
+    } else if *token == TT::Question {
+      if TERNARY_PRECEDENCE < min_precedence {
+        break;
+      }
+
       ph.advance();
-      let right = parse_single(ph)?;
-      left = Value::BinaryExpression(Box::new(left), operator, Box::new(right));
+      let true_value = parse_expression(ph, ASSIGNMENT_PRECEDENCE)?;
+
+      check_token!(ph, TT::Colon);
+      ph.advance();
+
+      let false_value = parse_expression(ph, TERNARY_PRECEDENCE)?;
+      ValueKind::TernaryExpression(Box::new(left), Box::new(true_value), Box::new(false_value))
     } else if let Some(operator) = AssignmentOperator::try_from_token(token) {
-      match &left {
-        Value::Identifier(_) | Value::MemberExpression(_, _) => {}
-        _ => return Err(Error::new("Can't assign to this expression", ph.get(-1))),
+      if ASSIGNMENT_PRECEDENCE < min_precedence {
+        break;
+      }
+
+      match &left.kind {
+        ValueKind::Identifier(_) | ValueKind::MemberExpression(_, _) => {}
+        _ => return Err(Error::at("Can't assign to this expression", left.span)),
       }
 
       ph.advance();
-      let right = parse_single(ph)?;
-      left = Value::Assignment(Box::new(left), operator, Box::new(right));
+      let right = parse_expression(ph, ASSIGNMENT_PRECEDENCE)?;
+      ValueKind::Assignment(Box::new(left), operator, Box::new(right))
     } else {
-      return Ok(left);
-    }
+      break;
+    };
+
+    left = Value::new(kind, ph.span_from(start));
   }
 
   Ok(left)
 }
 
 pub fn parse_inner(ph: &mut ParseHelper) -> ParserResult<Value> {
-  let mut value = parse_stage_1(ph)?;
-
-  if let Some(TT::Question) = ph.peek(0) {
-    ph.advance();
-
-    let true_value = parse_stage_1(ph)?;
-
-    check_token!(ph, TT::Colon);
-    ph.advance();
-
-    let false_value = parse_stage_1(ph)?;
-
-    value = Value::TernaryExpression(Box::new(value), Box::new(true_value), Box::new(false_value));
-  }
-
-  Ok(value)
+  parse_expression(ph, ASSIGNMENT_PRECEDENCE)
 }
 
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
-  Ok(Node::Value(parse_inner(ph)?))
+  let start = ph.start();
+  let value = parse_inner(ph)?;
+
+  Ok(Node::new(NodeKind::Value(value), ph.span_from(start)))
 }
 
 pub fn parse_inline_let(ph: &mut ParseHelper) -> ParserResult<(String, FunctionCall)> {
@@ -280,8 +402,8 @@ pub fn parse_inline_let(ph: &mut ParseHelper) -> ParserResult<(String, FunctionC
 
   let name = match ph.peek(0) {
     Some(TT::Identifier(name)) => name.clone(),
-    Some(_) => return Err(Error::unexpected(ph)),
-    None => return Err(Error::end(ph)),
+    Some(_) => return Err(Error::unexpected(ph, "identifier")),
+    None => return Err(Error::end(ph, "identifier")),
   };
 
   ph.advance();
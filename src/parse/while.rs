@@ -2,7 +2,7 @@ use super::{
   block::Block,
   error::{Error, ParserResult},
   function_call::FunctionCall,
-  node::Node,
+  node::{Node, NodeKind},
   parse_helper::ParseHelper,
   value::Value,
 };
@@ -64,11 +64,15 @@ fn parse_with_let(ph: &mut ParseHelper) -> ParserResult<WhileLet> {
 pub fn parse(ph: &mut ParseHelper) -> ParserResult<Node> {
   check_token!(ph, TT::While);
 
+  let start = ph.start();
+
   ph.advance();
 
-  match ph.peek(0) {
-    Some(TT::Let) => Ok(Node::WhileLet(parse_with_let(ph)?)),
-    Some(_) => Ok(Node::While(parse_inner(ph)?)),
-    None => Err(Error::end(ph)),
-  }
+  let kind = match ph.peek(0) {
+    Some(TT::Let) => NodeKind::WhileLet(parse_with_let(ph)?),
+    Some(_) => NodeKind::While(parse_inner(ph)?),
+    None => return Err(Error::end(ph, "condition")),
+  };
+
+  Ok(Node::new(kind, ph.span_from(start)))
 }
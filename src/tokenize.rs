@@ -1,8 +1,8 @@
-mod error;
+pub mod error;
 
 use error::{Error, Result};
 
-use super::types::{Position, Token, TokenType, TT};
+use super::types::{Comment, Position, Span, StringToken, Token, TokenType, TT};
 
 static KEYWORDS: phf::Map<&'static str, TokenType> = phf::phf_map! {
   "let" => TT::Let,
@@ -22,12 +22,19 @@ static KEYWORDS: phf::Map<&'static str, TokenType> = phf::phf_map! {
   "external" => TT::External,
   "switch" => TT::Switch,
   "case" => TT::Case,
+  "try" => TT::Try,
+  "catch" => TT::Catch,
+  "finally" => TT::Finally,
+  "defer" => TT::Defer,
 };
 
 pub struct State {
   lines: Vec<Vec<char>>,
   tokens: Vec<Token>,
+  comments: Vec<Comment>,
   comment_block: usize,
+  /// Where the outermost block comment being skipped starts
+  comment_start: Position,
   line: usize,
   column: usize,
 }
@@ -37,7 +44,9 @@ impl State {
     State {
       lines,
       tokens: Vec::new(),
+      comments: Vec::new(),
       comment_block: 0,
+      comment_start: Position::default(),
       line: 0,
       column: 0,
     }
@@ -63,10 +72,6 @@ impl State {
     }
   }
 
-  fn prev(&self) -> char {
-    self.char_at(self.line, self.column - 1)
-  }
-
   fn valid_char(&self) -> bool {
     self.column < self.line().len()
   }
@@ -87,6 +92,36 @@ impl State {
   fn to_pos(&self) -> Position {
     Position(self.line, self.column)
   }
+
+  /// Pushes a comment from `start` to the current position
+  fn push_comment(&mut self, start: Position) {
+    let Position(line, column) = start;
+
+    let text = if line == self.line {
+      self.lines[line][column..self.column].iter().collect()
+    } else {
+      let first = self.lines[line][column..].iter().collect::<String>();
+      let middle = self.lines[line + 1..self.line]
+        .iter()
+        .map(|line| line.iter().collect::<String>());
+      let last = self.lines[self.line][..self.column].iter().collect();
+
+      std::iter::once(first)
+        .chain(middle)
+        .chain(std::iter::once(last))
+        .collect::<Vec<String>>()
+        .join("\n")
+    };
+
+    let span = Span::new(start, self.to_pos());
+    self.comments.push(Comment { text, span });
+  }
+
+  /// Pushes a token that ends at the current position
+  fn push(&mut self, mut token: Token) {
+    token.span.end = self.to_pos();
+    self.tokens.push(token);
+  }
 }
 
 fn is_whitespace(ch: char) -> bool {
@@ -179,7 +214,7 @@ fn load_number(state: &mut State) -> Result<Token> {
         return Ok(Token::new(TT::Integer(num), Position(state.line, start)));
       }
     }
-  };
+  }
 
   Err(Error::new("Invalid number", state))
 }
@@ -233,27 +268,236 @@ fn load_flag(state: &mut State) -> Result<Token> {
   Ok(Token::new(TT::Flag(buf), Position(state.line, tmp_i)))
 }
 
-fn load_string(state: &mut State) -> Result<Token> {
-  let mut buf = String::new();
+/// Column of the `}` closing the `${` at the current position, skipping the
+/// braces and quotes nested in it
+fn interpolation_end(state: &State) -> Option<usize> {
+  let line = state.line();
+  let mut depth = 0;
+  let mut quote = None;
+  let mut column = state.column + 2;
+
+  while column < line.len() {
+    match (line[column], quote) {
+      ('\\', Some(_)) => column += 1,
+      (char, Some(open)) if char == open => quote = None,
+      (char @ ('"' | '\''), None) => quote = Some(char),
+      ('{', None) => depth += 1,
+      ('}', None) if depth == 0 => return Some(column),
+      ('}', None) => depth -= 1,
+      _ => {}
+    }
 
-  let start = state.column;
+    column += 1;
+  }
 
-  let quote_type = state.char();
+  None
+}
 
-  // omit beginning quote
-  state.advance();
+/// Loads the `$name` or `${...}` at the current position, if it is one
+fn load_interpolation(state: &mut State) -> Result<Option<StringToken>> {
+  let start = state.to_pos();
+
+  match state.next() {
+    Some('{') => {
+      let Some(end) = interpolation_end(state) else {
+        return Err(Error::new("No matching '}' for '${' found", state));
+      };
+
+      let inner: String = state.line()[state.column + 2..end].iter().collect();
+      let is_name = inner == "@"
+        || inner.chars().all(|c| c.is_ascii_digit())
+        || inner.starts_with(|c: char| is_alpha(c) || c == '_')
+          && inner.chars().all(|c| is_alpha_num(c) || c == '_');
+
+      let token = if inner.is_empty() {
+        return Err(Error::new("Empty '${}' in string", state));
+      } else if is_name {
+        state.column = end + 1;
+        StringToken::Variable(inner, Span::new(start, state.to_pos()))
+      } else {
+        // the expression is tokenized in place, keeping its positions
+        let mut lines = vec![Vec::new(); state.line];
+        lines.push(state.line()[..end].to_vec());
+
+        let mut expression = State::new(lines);
+        expression.line = state.line;
+        expression.column = state.column + 2;
+        tokenize_line(&mut expression)?;
+
+        state.column = end + 1;
+        StringToken::Expression(expression.tokens)
+      };
 
-  while state.valid_char() && !(state.char() == quote_type && state.prev() != '\\') {
-    if state.char() == '\\' && state.prev() != '\\' {
+      Ok(Some(token))
+    }
+    Some(char) if is_alpha(char) || char == '_' => {
       state.advance();
-      continue;
+      let name = load_name(state);
+
+      Ok(Some(StringToken::Variable(
+        name,
+        Span::new(start, state.to_pos()),
+      )))
     }
+    Some(char @ ('@' | '0'..='9')) => {
+      state.advance_by(2);
 
-    buf.push(state.char());
+      Ok(Some(StringToken::Variable(
+        char.to_string(),
+        Span::new(start, state.to_pos()),
+      )))
+    }
+    _ => Ok(None),
+  }
+}
+
+/// Character the escape sequence starting at the current backslash stands
+/// for, or `None` when the backslash is kept as text.
+///
+/// Double quoted strings know `\n`, `\t`, `\r`, `\e`, `\u{263A}` and
+/// the escaped `\\`, `\"`, `\'` and `\$`. Raw strings only escape `\\` and
+/// `\'`, keeping any other backslash.
+fn load_escape(state: &mut State, quote_type: char) -> Result<Option<char>> {
+  let Some(next) = state.next() else {
+    return Err(Error::new("Unfinished escape sequence", state));
+  };
+
+  let char = match (quote_type, next) {
+    (_, '\\' | '\'') => next,
+    ('\'', _) => return Ok(None),
+    (_, '"' | '$') => next,
+    (_, 'n') => '\n',
+    (_, 't') => '\t',
+    (_, 'r') => '\r',
+    (_, 'e') => '\x1b',
+    (_, 'u') => return load_unicode_escape(state).map(Some),
+    _ => {
+      state.advance();
+      return Err(Error::new(
+        &format!("Unknown escape sequence '\\{next}'"),
+        state,
+      ));
+    }
+  };
+
+  state.advance_by(2);
+
+  Ok(Some(char))
+}
+
+/// Loads a `\u{...}` escape of one to six hex digits
+fn load_unicode_escape(state: &mut State) -> Result<char> {
+  // skip `\u`
+  state.advance_by(2);
+
+  let line = state.line();
+  let end = line[state.column..]
+    .iter()
+    .position(|&c| c == '}')
+    .map(|end| state.column + end);
+
+  let code = match end {
+    Some(end) if line[state.column] == '{' && (2..=7).contains(&(end - state.column)) => {
+      let digits: String = line[state.column + 1..end].iter().collect();
+      u32::from_str_radix(&digits, 16)
+        .ok()
+        .map(|code| (code, end))
+    }
+    _ => None,
+  };
+
+  let Some((code, end)) = code else {
+    return Err(Error::new(
+      "Invalid unicode escape, expected one to six hex digits like '\\u{263A}'",
+      state,
+    ));
+  };
+
+  let Some(char) = char::from_u32(code) else {
+    return Err(Error::new(
+      &format!("'{code:X}' isn't a unicode character"),
+      state,
+    ));
+  };
+
+  state.column = end + 1;
+
+  Ok(char)
+}
+
+/// Text and interpolated parts of a string being loaded
+#[derive(Default)]
+struct StringContents {
+  buf: String,
+  parts: Vec<StringToken>,
+}
+
+impl StringContents {
+  fn into_token_type(mut self, quote_type: char) -> TT {
+    match quote_type {
+      '"' if self.parts.is_empty() => TT::String(self.buf),
+      '"' => {
+        if !self.buf.is_empty() {
+          self.parts.push(StringToken::Text(self.buf));
+        }
+
+        TT::InterpolatedString(self.parts)
+      }
+      _ => TT::RawString(self.buf),
+    }
+  }
+}
+
+/// Loads the contents of a string up to the `end` quote, or up to the end of
+/// the line without one
+fn load_string_contents(
+  state: &mut State,
+  quote_type: char,
+  end: Option<char>,
+  contents: &mut StringContents,
+) -> Result<()> {
+  while state.valid_char() && Some(state.char()) != end {
+    if state.char() == '\\' {
+      if let Some(char) = load_escape(state, quote_type)? {
+        contents.buf.push(char);
+        continue;
+      }
+    } else if state.char() == '$' && quote_type == '"' {
+      if let Some(part) = load_interpolation(state)? {
+        if !contents.buf.is_empty() {
+          contents
+            .parts
+            .push(StringToken::Text(std::mem::take(&mut contents.buf)));
+        }
+
+        contents.parts.push(part);
+        continue;
+      }
+    }
+
+    contents.buf.push(state.char());
     state.advance();
   }
 
-  if state.char() != quote_type {
+  Ok(())
+}
+
+fn load_string(state: &mut State) -> Result<Token> {
+  let start = state.column;
+
+  let quote_type = state.char();
+
+  if state.line()[start..].starts_with(&[quote_type; 3]) {
+    return load_multiline_string(state);
+  }
+
+  // omit beginning quote
+  state.advance();
+
+  let mut contents = StringContents::default();
+  load_string_contents(state, quote_type, Some(quote_type), &mut contents)?;
+
+  if !state.valid_char() || state.char() != quote_type {
     return Err(Error::new(
       &format!("No matching quote for '{quote_type}' found"),
       state,
@@ -263,13 +507,72 @@ fn load_string(state: &mut State) -> Result<Token> {
   // omit ending quote
   state.advance();
 
-  let token_type = match quote_type {
-    '"' => TT::String(buf),
-    '\'' => TT::RawString(buf),
-    _ => return Err(Error::new("Invalid string quote type", state)),
+  Ok(Token::new(
+    contents.into_token_type(quote_type),
+    Position(state.line, start),
+  ))
+}
+
+/// Loads a `"""` or `'''` string, whose text is on the lines between the
+/// quotes, stripped of the indentation they have in common
+fn load_multiline_string(state: &mut State) -> Result<Token> {
+  let start = state.to_pos();
+  let quote_type = state.char();
+  let quotes = [quote_type; 3];
+  let delimiter: String = quotes.iter().collect();
+
+  state.advance_by(3);
+
+  if !state.line()[state.column..]
+    .iter()
+    .all(|&c| is_whitespace(c))
+  {
+    return Err(Error::new(
+      &format!("A multi-line string starts on the line after its '{delimiter}'"),
+      state,
+    ));
+  }
+
+  let first = state.line + 1;
+  let closing = (first..state.lines.len()).find_map(|line| {
+    let text = &state.lines[line];
+    let column = text.iter().position(|&c| !is_whitespace(c))?;
+
+    text[column..]
+      .starts_with(&quotes)
+      .then_some((line, column))
+  });
+
+  let Some((last, column)) = closing else {
+    return Err(Error::new(
+      &format!("No matching '{delimiter}' found"),
+      state,
+    ));
   };
 
-  Ok(Token::new(token_type, Position(state.line, start)))
+  let indent = state.lines[first..last]
+    .iter()
+    .filter(|line| !line.iter().all(|&c| is_whitespace(c)))
+    .map(|line| line.iter().take_while(|&&c| is_whitespace(c)).count())
+    .min()
+    .unwrap_or(0);
+
+  let mut contents = StringContents::default();
+
+  for line in first..last {
+    if line > first {
+      contents.buf.push('\n');
+    }
+
+    state.line = line;
+    state.column = indent.min(state.line().len());
+    load_string_contents(state, quote_type, None, &mut contents)?;
+  }
+
+  state.line = last;
+  state.column = column + 3;
+
+  Ok(Token::new(contents.into_token_type(quote_type), start))
 }
 
 fn load_operator(state: &mut State) -> Result<Token> {
@@ -375,16 +678,21 @@ fn classify_str(str: &str) -> TokenType {
 }
 
 pub fn tokenize(s: &str) -> Result<Vec<Token>> {
+  tokenize_with_comments(s).map(|(tokens, _)| tokens)
+}
+
+/// Like [`tokenize`], but also returns the comments, in order
+pub fn tokenize_with_comments(s: &str) -> Result<(Vec<Token>, Vec<Comment>)> {
   let lines: Vec<Vec<char>> = s.lines().map(|line| line.chars().collect()).collect();
-  let lines_length = lines.len();
   let mut state = State::new(lines);
 
-  for _ in 0..lines_length {
+  // multi-line strings move the state past the lines they span
+  while state.line < state.lines.len() {
     tokenize_line(&mut state)?;
     state.advance_line();
   }
 
-  Ok(state.tokens)
+  Ok((state.tokens, state.comments))
 }
 
 fn tokenize_line(state: &mut State) -> Result<()> {
@@ -406,10 +714,19 @@ fn tokenize_line(state: &mut State) -> Result<()> {
 
       state.advance_by(2);
       state.comment_block -= 1;
+
+      if state.comment_block == 0 {
+        state.push_comment(state.comment_start);
+      }
+
       continue;
     }
 
     if char == '/' && state.next() == Some('*') {
+      if state.comment_block == 0 {
+        state.comment_start = state.to_pos();
+      }
+
       state.advance_by(2);
       state.comment_block += 1;
       continue;
@@ -421,8 +738,11 @@ fn tokenize_line(state: &mut State) -> Result<()> {
     }
 
     if char == '/' && state.next() == Some('/') {
+      let start = state.to_pos();
+      state.column = state.line().len();
+      state.push_comment(start);
       break;
-    };
+    }
 
     // names
     if is_alpha(char) || char == '_' {
@@ -435,7 +755,7 @@ fn tokenize_line(state: &mut State) -> Result<()> {
 
       let token = Token::new(r#type, Position(state.line, tmp_i));
 
-      state.tokens.push(token);
+      state.push(token);
 
       continue;
     }
@@ -451,8 +771,8 @@ fn tokenize_line(state: &mut State) -> Result<()> {
         Position(state.line, state.column),
       );
 
-      state.tokens.push(token);
       state.advance_by(2);
+      state.push(token);
 
       continue;
     }
@@ -462,7 +782,7 @@ fn tokenize_line(state: &mut State) -> Result<()> {
       if let Some(char) = state.next() {
         if is_alpha(char) || char == '-' {
           let token = load_flag(state)?;
-          state.tokens.push(token);
+          state.push(token);
           continue;
         }
       }
@@ -471,20 +791,20 @@ fn tokenize_line(state: &mut State) -> Result<()> {
     // numbers
     if is_digit(char) {
       let token = load_number(state)?;
-      state.tokens.push(token);
+      state.push(token);
       continue;
     }
 
     // strings
     if state.char() == '"' || state.char() == '\'' {
       let token = load_string(state)?;
-      state.tokens.push(token);
+      state.push(token);
       continue;
     }
 
     // operators
     let operator = load_operator(state)?;
-    state.tokens.push(operator);
+    state.push(operator);
   }
 
   Ok(())
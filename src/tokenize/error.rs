@@ -1,6 +1,7 @@
 use std::fmt;
 
 use super::{Position, State};
+use crate::{diagnostic::Diagnostic, types::Span};
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Clone)]
@@ -27,3 +28,11 @@ impl fmt::Display for Error {
     write!(f, "LexerError: \"{msg}\" at position {line}:{column}")
   }
 }
+
+impl From<Error> for Diagnostic {
+  fn from(error: Error) -> Self {
+    let Error { msg, pos } = error;
+
+    Diagnostic::error(&msg).with_span(Span::new(pos, pos))
+  }
+}
@@ -1,42 +1,73 @@
-mod error;
+pub mod error;
+mod target;
 mod transpiler;
 // mod utils;
 
 use error::TranspileResult;
+pub use target::{FloatBackend, Target};
 
 use self::transpiler::Transpiler;
-use crate::parse::node::Node::{
-  self, Block, Break, Continue, Declaration, Empty, Expression, For, Foreach, Function,
-  FunctionCall, If, IfLet, Return, Source, Switch, Value, While, WhileLet,
+use crate::{
+  compile::Options,
+  diagnostic::Diagnostic,
+  infer::infer,
+  parse::node::{
+    Node,
+    NodeKind::{
+      Block, Break, Continue, Declaration, Defer, Expression, External, ExternalFunction, For,
+      Foreach, Function, FunctionCall, If, IfLet, Import, Return, Signal, Source, Switch, Try,
+      Value, While, WhileLet,
+    },
+  },
 };
 
 mod block;
 mod declaration;
+mod defer;
 mod expression;
+mod float;
 mod r#for;
 mod foreach;
 mod function;
 mod function_call;
 mod r#if;
+mod import;
+mod quote;
 mod r#return;
+mod run;
+mod signal;
 mod source;
 mod switch;
+mod r#try;
 mod value;
 mod r#while;
 
 mod utils;
 
-pub fn transpile(tree: &[Node]) -> TranspileResult<String> {
-  let mut t = Transpiler::new("  ");
+/// Returns the generated code along with any warnings
+#[cfg(feature = "cli")]
+pub fn transpile(tree: &[Node], target: Target) -> TranspileResult<(String, Vec<Diagnostic>)> {
+  transpile_with_options(tree, &Options::new(target))
+}
+
+/// Like [`transpile`], with the target, indentation and float backend of
+/// `options`
+pub fn transpile_with_options(
+  tree: &[Node],
+  options: &Options,
+) -> TranspileResult<(String, Vec<Diagnostic>)> {
+  let mut t = Transpiler::new(&options.indent, options.target, infer(tree), options.float);
 
-  inner(tree, &mut t)
+  let code = inner(tree, &mut t)?;
+
+  Ok((code, t.take_warnings()))
 }
 
 pub fn inner(tree: &[Node], t: &mut Transpiler) -> TranspileResult<String> {
   let mut output = Vec::new();
 
-  for node in tree {
-    let code = match node {
+  for (i, node) in tree.iter().enumerate() {
+    let code = match node.kind {
       Block(_) => block::transpile(t, node),
       Declaration(_) => declaration::transpile(t, node),
       Expression(_) => expression::transpile(t, node),
@@ -46,6 +77,7 @@ pub fn inner(tree: &[Node], t: &mut Transpiler) -> TranspileResult<String> {
       FunctionCall(_) => function_call::transpile(t, node),
       If(_) => r#if::transpile(t, node),
       IfLet(_) => r#if::transpile_let(t, node),
+      Import(_) => import::transpile(t, node),
       Source(_) => source::transpile(t, node),
       Value(_) => value::transpile(t, node),
       While(_) => r#while::transpile(t, node),
@@ -56,8 +88,23 @@ pub fn inner(tree: &[Node], t: &mut Transpiler) -> TranspileResult<String> {
       Continue => Ok(t.use_indent("continue")),
 
       Switch(_) => switch::transpile(t, node),
+      Signal(_) => signal::transpile(t, node),
+      Try(_) => r#try::transpile(t, node),
+
+      // the statements after it may end up inside the code it makes
+      Defer(_) => {
+        output.push(defer::transpile(t, node, &tree[i + 1..])?);
+        break;
+      }
 
-      Empty => Ok(String::new()),
+      // so blocks don't overwrite them
+      External(ref names) => {
+        for name in names {
+          t.declare(name, node.span);
+        }
+        continue;
+      }
+      ExternalFunction(_) => continue,
     }?;
 
     output.push(code);
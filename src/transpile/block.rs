@@ -2,18 +2,35 @@ use super::{
   error::{Error, TranspileResult},
   transpiler::{BlockType, Transpiler},
 };
-use crate::parse::node::Node;
+use crate::parse::node::{Node, NodeKind};
 
+/// Transpiles the nodes of a block, in a scope of their own
 pub fn transpile_inner(t: &mut Transpiler, block: &[Node]) -> TranspileResult<String> {
+  t.push_scope();
   t.push_block(BlockType::Generic);
   let block = super::inner(block, t)?;
   t.pop_block();
+  t.pop_scope();
 
   Ok(block)
 }
 
+/// Transpiles the body of a function, which shares the scope of the
+/// parameters
+pub fn transpile_body(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
+  if let NodeKind::Block(block) = &node.kind {
+    t.push_block(BlockType::Generic);
+    let block = super::inner(block, t)?;
+    t.pop_block();
+
+    Ok(block)
+  } else {
+    Err(Error::invalid(node))
+  }
+}
+
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::Block(block) = node {
+  if let NodeKind::Block(block) = &node.kind {
     transpile_inner(t, block)
   } else {
     Err(Error::invalid(node))
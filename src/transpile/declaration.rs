@@ -1,34 +1,172 @@
 use super::{
   error::{Error, TranspileResult},
+  r#try, run,
+  target::Target,
   transpiler::Transpiler,
   value,
 };
-use crate::parse::{
-  declaration::{Declaration, Type},
-  node::Node,
-  value::{Literal, Value},
+use crate::{
+  infer::ValueType,
+  parse::{
+    declaration::{Declaration, Type},
+    function_call::FunctionCall,
+    node::{Node, NodeKind},
+    value::{Literal, Value, ValueKind},
+  },
 };
 
-pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::Declaration(Declaration {
+fn literal(node: &Node) -> Option<&Literal> {
+  match &node.kind {
+    NodeKind::Value(Value {
+      kind: ValueKind::Literal(literal),
+      ..
+    }) => Some(literal),
+    _ => None,
+  }
+}
+
+fn check_posix(t: &Transpiler, declaration: &Declaration, node: &Node) -> TranspileResult<()> {
+  let Declaration {
     r#type,
     name,
     value,
-  }) = node
-  {
-    let type_string = match r#type {
-      Type::Export => "export",
-      Type::Let => "local",
+  } = declaration;
+
+  match literal(value) {
+    Some(Literal::Array(_)) => {
+      return Err(Error::new(
+        &format!("Array variable '{name}' has no POSIX sh equivalent"),
+        node.span,
+      ))
+    }
+    Some(Literal::Map(_)) => {
+      return Err(Error::new(
+        &format!("Map variable '{name}' has no POSIX sh equivalent"),
+        node.span,
+      ))
+    }
+    _ => {}
+  }
+
+  if *r#type == Type::Export && t.in_function() {
+    return Err(Error::new(
+      &format!("Can't export '{name}' from a function body, it runs in a subshell in POSIX sh"),
+      node.span,
+    ));
+  }
+
+  Ok(())
+}
+
+fn transpile_run(
+  t: &mut Transpiler,
+  Declaration { r#type, name, .. }: &Declaration,
+  call: &FunctionCall,
+  node: &Node,
+) -> TranspileResult<String> {
+  if *r#type == Type::Export {
+    return Err(
+      Error::new("Can't export the result of run()", node.span)
+        .help("export the fields you need instead, like `export code = result.code;`"),
+    );
+  }
+
+  let command = run::command(t, call, node)?;
+
+  t.add_local(name);
+  let name = t.declare(name, node.span);
+
+  Ok(run::transpile(t, &name, &command))
+}
+
+pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
+  if let NodeKind::Declaration(declaration) = &node.kind {
+    let Declaration {
+      r#type,
+      name,
+      value,
+    } = declaration;
+
+    if t.target() == Target::Sh {
+      check_posix(t, declaration, node)?;
+    }
+
+    if *r#type == Type::Export && t.in_try() {
+      r#try::check_assignment(t, "export", name, node.span)?;
+    }
+
+    if let NodeKind::Value(Value {
+      kind: ValueKind::Run(call),
+      ..
+    }) = &value.kind
+    {
+      return transpile_run(t, declaration, call, node);
+    }
+
+    let value_type = t.type_of(node.span);
+
+    // the value can still refer to the variable the declaration shadows
+    let value_string = match &value.kind {
+      // a copy of an array, item by item
+      NodeKind::Value(
+        value @ Value {
+          kind: ValueKind::Identifier(_),
+          ..
+        },
+      ) if value_type == Some(ValueType::Array) => {
+        format!("({})", value::transpile_argument(t, value, node)?)
+      }
+      _ => value::transpile(t, value)?,
+    };
+
+    let name = match r#type {
+      Type::Let => {
+        t.add_local(name);
+        t.declare(name, node.span)
+      }
+      Type::Export => name.clone(),
     };
 
-    if let Node::Value(Value::Literal(Literal::Map(_))) = **value {
-      let value = value::transpile(t, value)?;
-      return Ok(format!("{type_string} {name}\ntypeset -A {name}={value}"));
+    let value = value_string;
+
+    // floats stay plain variables, `typeset -F` would print ten decimals
+    let flag = match value_type {
+      Some(ValueType::Int) => "-i ",
+      Some(ValueType::Array) => "-a ",
+      _ => "",
+    };
+
+    let type_string = match (t.target(), r#type) {
+      (_, Type::Export) => "export ".to_owned(),
+      (Target::Zsh, Type::Let) => format!("local {flag}"),
+      (Target::Bash, Type::Let) => format!("declare {flag}"),
+      (Target::Sh, Type::Let) => String::new(),
+    };
+
+    if value_type == Some(ValueType::Map) {
+      return Ok(match (t.target(), r#type) {
+        (Target::Bash, Type::Let) if t.in_try() => {
+          t.use_indent(&format!("declare -A {name}"))
+            + "\n"
+            + &t.use_indent(&format!("{name}={value}"))
+        }
+        (Target::Bash, Type::Let) => t.use_indent(&format!("declare -A {name}={value}")),
+        (Target::Bash, Type::Export) => t.use_indent(&format!("declare -gxA {name}={value}")),
+        _ => format!("{type_string}{name}\ntypeset -A {name}={value}"),
+      });
     }
 
-    let value = value::transpile(t, value)?;
+    // `local` and the others exit with their own status rather than the one
+    // of a command in the value, which a try block has to stop at
+    if t.in_try() && !type_string.is_empty() {
+      return Ok(format!(
+        "{}\n{}",
+        t.use_indent(&format!("{type_string}{name}")),
+        t.use_indent(&format!("{name}={value}"))
+      ));
+    }
 
-    Ok(t.use_indent(&format!(r#"{type_string} {name}={value}"#)))
+    Ok(t.use_indent(&format!(r"{type_string}{name}={value}")))
   } else {
     Err(Error::invalid(node))
   }
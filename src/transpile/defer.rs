@@ -0,0 +1,160 @@
+//! `defer { ... }`, run once the function returns or the script exits, the
+//! last deferred block first
+
+use super::{
+  block,
+  error::{Error, TranspileResult},
+  r#try,
+  target::Target,
+  transpiler::{BlockType, Transpiler},
+};
+use crate::parse::node::{Node, NodeKind};
+
+/// Transpiles the deferred block at the same level as the statements around
+fn transpile_inline(t: &mut Transpiler, block: &[Node]) -> TranspileResult<String> {
+  t.push_scope();
+  let block = super::inner(block, t);
+  t.pop_scope();
+
+  block
+}
+
+/// Signals the shell exits on without running the EXIT trap, along with the
+/// exit code to exit with instead, leaving out the ones the script handles
+fn exiting_signals(t: &Transpiler) -> Vec<(&'static str, u8)> {
+  [("HUP", 129), ("INT", 130), ("TERM", 143)]
+    .into_iter()
+    .filter(|(signal, _)| !t.has_handler(signal))
+    .collect()
+}
+
+/// Function running the deferred block, called by an EXIT trap along with
+/// the ones deferred before, which the shell also runs when the script fails
+/// or, with the signal traps, gets interrupted
+fn transpile_trap(t: &mut Transpiler, block: &[Node], rest: &[Node]) -> TranspileResult<String> {
+  let name = format!("__defer_{}", t.next_function());
+
+  let head = match t.target() {
+    Target::Zsh | Target::Bash => format!("function {name}() {{"),
+    Target::Sh => format!("{name}() {{"),
+  };
+
+  // the cleanup sees and assigns the variables the way the code around does
+  let body = block::transpile_inner(t, block);
+
+  let mut lines = vec![t.use_indent(&head), body?, t.use_indent("}")];
+
+  let defers = t.add_defer(&name);
+  let first = defers.len() == 1;
+  let defers = defers.join("; ");
+
+  if first {
+    // the signal traps are put back once the function ends, the EXIT trap
+    // of a zsh function only ever runs when it ends
+    if t.target() == Target::Zsh && t.in_function() {
+      lines.push(t.use_indent("setopt local_options local_traps"));
+    }
+
+    for (signal, code) in exiting_signals(t) {
+      lines.push(t.use_indent(&format!("trap 'exit {code}' {signal}")));
+    }
+  }
+
+  lines.push(t.use_indent(&format!("trap '{defers}' EXIT")));
+
+  if !rest.is_empty() {
+    lines.push(super::inner(rest, t)?);
+  }
+
+  Ok(lines.join("\n"))
+}
+
+/// Function holding the rest of the function, running the deferred block
+/// and returning its exit code once it's done. For as long as it runs, the
+/// EXIT trap runs the deferred block before the trap it replaced, which is
+/// put back along with the signal traps afterwards
+fn transpile_wrapper(t: &mut Transpiler, block: &[Node], rest: &[Node]) -> TranspileResult<String> {
+  let number = t.next_function();
+  let defer = format!("__defer_{number}");
+  let body = format!("__defer_body_{number}");
+  let status = format!("__status_{number}");
+  let traps = format!("__traps_{number}");
+  let exit = format!("__exit_{number}");
+
+  let block = block::transpile_inner(t, block)?;
+
+  let signals = exiting_signals(t);
+  let names = signals
+    .iter()
+    .fold(String::new(), |names, (signal, _)| names + " " + signal);
+
+  let mut lines = vec![
+    t.use_indent(&format!("function {defer}() {{")),
+    block,
+    t.use_indent("}"),
+    t.use_indent(&format!(r#"declare {traps}="$(trap -p EXIT{names})""#)),
+    // the command of the EXIT trap, quoted the way `trap -p` quotes it
+    t.use_indent(&format!(r#"declare {exit}="$(trap -p EXIT)""#)),
+    t.use_indent(&format!(r#"{exit}="${{{exit}#trap -- }}""#)),
+    t.use_indent(&format!(r#"{exit}="${{{exit}% EXIT}}""#)),
+  ];
+
+  for (signal, code) in signals {
+    lines.push(t.use_indent(&format!("trap 'exit {code}' {signal}")));
+  }
+
+  lines.push(t.use_indent(&format!(r#"trap '{defer}; eval "eval ${{{exit}}}"' EXIT"#)));
+
+  t.push_block(BlockType::Generic);
+  let rest = super::inner(rest, t);
+  t.pop_block();
+
+  lines.extend([
+    t.use_indent(&format!("function {body}() {{")),
+    rest?,
+    t.use_indent("}"),
+    t.use_indent(&format!(r#"{body} "$@""#)),
+    t.use_indent(&format!("declare -i {status}=$?")),
+    t.use_indent(&format!("trap - EXIT{names}")),
+    t.use_indent(&format!(r#"eval "${{{traps}}}""#)),
+    t.use_indent(&defer),
+    t.use_indent(&format!(r#"return "${{{status}}}""#)),
+  ]);
+
+  Ok(lines.join("\n"))
+}
+
+/// Transpiles the `defer` statement along with the `rest` of the block it
+/// ends up wrapping
+pub fn transpile(t: &mut Transpiler, node: &Node, rest: &[Node]) -> TranspileResult<String> {
+  if let NodeKind::Defer(block) = &node.kind {
+    if !t.at_function_level() {
+      return Err(
+        Error::new(
+          "defer can only be used in a function body or at the top level",
+          node.span,
+        )
+        .help("the block runs once the function returns or the script exits"),
+      );
+    }
+
+    if let Some((keyword, node)) = r#try::leaving(block, false) {
+      return Err(Error::new(
+        &format!("`{keyword}` can't leave a defer block"),
+        node.span,
+      ));
+    }
+
+    // POSIX sh function bodies are subshells, which have traps of their own,
+    // and so do zsh functions
+    let trapped = !t.in_function() || t.target() != Target::Bash;
+
+    match t.target() {
+      _ if trapped => transpile_trap(t, block, rest),
+      _ if rest.is_empty() => transpile_inline(t, block),
+      _ => transpile_wrapper(t, block, rest),
+    }
+  } else {
+    Err(Error::invalid(node))
+  }
+}
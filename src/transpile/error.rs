@@ -1,31 +1,77 @@
 use std::fmt;
 
-use crate::parse::node::Node;
+use crate::{diagnostic::Diagnostic, parse::node::Node, types::Span};
 
 pub type TranspileResult<T> = Result<T, Error>;
 
 #[derive(Debug, Clone)]
 pub struct Error {
   pub msg: String,
-  pub node: Node,
+  pub span: Span,
+  pub help: Option<String>,
+  /// Imported file the error comes from
+  pub file: Option<String>,
 }
 
 impl Error {
-  pub fn new(msg: &str, node: &Node) -> Self {
+  pub fn new(msg: &str, span: Span) -> Self {
     Error {
       msg: msg.to_string(),
-      node: node.clone(),
+      span,
+      help: None,
+      file: None,
     }
   }
 
+  pub fn help(mut self, help: &str) -> Self {
+    self.help = Some(help.to_owned());
+    self
+  }
+
   pub fn invalid(node: &Node) -> Self {
-    Self::new("Invalid node type", node)
+    Self::new("Invalid node type", node.span)
+  }
+
+  /// Marks the error as coming from `file`, unless it's already coming from
+  /// a file imported by that one
+  pub fn in_file(mut self, file: &str) -> Self {
+    self.file.get_or_insert_with(|| file.to_owned());
+    self
+  }
+}
+
+impl From<Error> for Diagnostic {
+  fn from(error: Error) -> Self {
+    let Error {
+      msg,
+      span,
+      help,
+      file,
+    } = error;
+
+    let mut diagnostic = Diagnostic::error(&msg).with_span(span);
+
+    if let Some(help) = help {
+      diagnostic = diagnostic.with_help(&help);
+    }
+
+    match file {
+      Some(file) => diagnostic.in_file(&file),
+      None => diagnostic,
+    }
   }
 }
 
 impl fmt::Display for Error {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    let Error { msg, node } = self;
-    write!(f, "TranspileError: \"{msg}\" at node {node}")
+    let Error {
+      msg, span, file, ..
+    } = self;
+
+    if let Some(file) = file {
+      write!(f, "TranspileError: \"{msg}\" at position {file}:{span}")
+    } else {
+      write!(f, "TranspileError: \"{msg}\" at position {span}")
+    }
   }
 }
@@ -3,10 +3,10 @@ use super::{
   transpiler::Transpiler,
   value,
 };
-use crate::parse::node::Node;
+use crate::parse::node::{Node, NodeKind};
 
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::Expression(value) = node {
+  if let NodeKind::Expression(value) = &node.kind {
     value::transpile_inner(t, value, node)
   } else {
     Err(Error::invalid(node))
@@ -0,0 +1,264 @@
+//! Float arithmetic on bash and sh, whose arithmetic only handles integers,
+//! evaluated by the external command of a [`FloatBackend`]
+
+use std::fmt::Write;
+
+use super::{
+  error::{Error, TranspileResult},
+  target::{FloatBackend, Target},
+  transpiler::{BlockType, Transpiler},
+  value::{self, transpile_arithmetic_operator},
+};
+use crate::{
+  infer::ValueType,
+  parse::{
+    function_call::FunctionCall,
+    node::Node,
+    value::{BinaryOperator, Literal, UnaryOperator, Value, ValueKind},
+  },
+  types::Span,
+};
+
+/// Names awk or bc would read as something else than a variable
+const RESERVED: &[&str] = &[
+  "auto", "break", "continue", "cos", "define", "delete", "do", "else", "exit", "exp", "for",
+  "function", "getline", "halt", "ibase", "if", "in", "index", "int", "last", "length", "limits",
+  "log", "next", "obase", "print", "printf", "quit", "rand", "read", "return", "scale", "sin",
+  "split", "sqrt", "srand", "substr", "warranty", "while",
+];
+
+/// Whether evaluating `value` involves a float
+fn has_float(t: &Transpiler, value: &Value) -> bool {
+  match &value.kind {
+    ValueKind::Literal(Literal::Float(_)) => true,
+    ValueKind::Identifier(_) => t.type_of(value.span) == Some(ValueType::Float),
+    ValueKind::UnaryExpression(_, value) | ValueKind::Parenthesized(value) => has_float(t, value),
+    ValueKind::BinaryExpression(left, _, right) => has_float(t, left) || has_float(t, right),
+    ValueKind::FunctionCall(FunctionCall { name, args, .. }) if name == "$" => {
+      args.iter().any(|arg| has_float(t, arg))
+    }
+    _ => false,
+  }
+}
+
+/// Whether `value` evaluates to a float, rather than to the 1 or 0 of a
+/// comparison
+fn is_float(t: &Transpiler, value: &Value) -> bool {
+  match &value.kind {
+    ValueKind::UnaryExpression(UnaryOperator::Not, _) => false,
+    ValueKind::BinaryExpression(left, operator, right) => {
+      operator.is_arithmetic() && (is_float(t, left) || is_float(t, right))
+    }
+    ValueKind::Parenthesized(value) => is_float(t, value),
+    ValueKind::FunctionCall(FunctionCall { name, args, .. }) if name == "$" => {
+      args.iter().any(|arg| is_float(t, arg))
+    }
+    _ => has_float(t, value),
+  }
+}
+
+/// Whether the arithmetic `values` can't be left to the shell
+pub fn is_needed(t: &Transpiler, values: &[Value]) -> bool {
+  t.target() != Target::Zsh && values.iter().any(|value| has_float(t, value))
+}
+
+/// `expansion` of the arithmetic `values` as zsh prints it, which leaves the
+/// point of whole floats bare, like `3.`, where awk and bc print `3.0`
+pub fn format(t: &Transpiler, values: &[Value], expansion: String) -> String {
+  let float = values.iter().any(|value| is_float(t, value));
+  let printed = !t.search(&BlockType::Arithmetics) && !t.search(&BlockType::Condition);
+
+  if t.target() == Target::Zsh && float && printed {
+    format!("${{{expansion}/%./.0}}")
+  } else {
+    expansion
+  }
+}
+
+/// Arithmetic expression as written for awk and bc, with the operands that
+/// aren't numbers passed to the command as variables
+struct Expression<'a> {
+  t: &'a mut Transpiler,
+  node: &'a Node,
+  /// names in the expression and the shell words giving their values
+  variables: Vec<(String, String)>,
+}
+
+impl Expression<'_> {
+  fn render(&mut self, value: &Value) -> TranspileResult<String> {
+    match &value.kind {
+      ValueKind::Literal(Literal::Int(num)) => Ok(num.to_string()),
+      ValueKind::Literal(Literal::Float(num)) => Ok(format!("{num:?}")),
+      ValueKind::Literal(Literal::String(_) | Literal::RawString(_)) => Err(Error::new(
+        "Strings can't be used in float arithmetic",
+        value.span,
+      )),
+      ValueKind::Identifier(name) => {
+        let name = self.t.resolve(name).to_owned();
+        self.variable(Some(&name), value)
+      }
+      ValueKind::UnaryExpression(operator, right) => {
+        let operator = match operator {
+          UnaryOperator::Not => "!",
+          UnaryOperator::Minus => "-",
+        };
+
+        Ok(format!("{operator}{}", self.render(right)?))
+      }
+      ValueKind::BinaryExpression(left, operator, right) => {
+        let operator = match operator {
+          BinaryOperator::Power => "^",
+          operator => transpile_arithmetic_operator(operator).ok_or_else(|| {
+            Error::new(
+              "Regex match has no equivalent in arithmetic context",
+              value.span,
+            )
+          })?,
+        };
+
+        Ok(format!(
+          "{} {operator} {}",
+          self.render(left)?,
+          self.render(right)?
+        ))
+      }
+      ValueKind::Parenthesized(value) => Ok(format!("({})", self.render(value)?)),
+      ValueKind::FunctionCall(FunctionCall { name, args, .. }) if name == "$" => {
+        let args = args
+          .iter()
+          .map(|arg| self.render(arg))
+          .collect::<TranspileResult<Vec<_>>>()?;
+
+        Ok(format!("({})", args.join(" ")))
+      }
+      ValueKind::Assignment(..) => Err(
+        Error::new("Can't assign inside float arithmetic", value.span)
+          .help("assign the result instead, like `x = $(x + 1.5);`"),
+      ),
+      _ => self.variable(None, value),
+    }
+  }
+
+  /// Passes `value` to the command, as `name` if the command accepts it
+  fn variable(&mut self, name: Option<&str>, value: &Value) -> TranspileResult<String> {
+    self.t.push_block(BlockType::Expression);
+    let word = value::transpile_inner(self.t, value, self.node);
+    self.t.pop_block();
+    let word = word?;
+
+    if let Some((name, _)) = self.variables.iter().find(|(_, other)| *other == word) {
+      return Ok(name.clone());
+    }
+
+    let taken = |name: &str| self.variables.iter().any(|(other, _)| other == name);
+
+    let name = match name {
+      Some(name)
+        if name.starts_with(|c: char| c.is_ascii_lowercase())
+          && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+          && !RESERVED.contains(&name)
+          && !taken(name) =>
+      {
+        name.to_owned()
+      }
+      // one of them is always free
+      _ => (1..=self.variables.len() + 1)
+        .map(|n| format!("v{n}"))
+        .find(|name| !taken(name))
+        .expect("every name is taken"),
+    };
+
+    self.variables.push((name.clone(), word));
+
+    Ok(name)
+  }
+}
+
+/// Command substitution printing the result of the arithmetic `values`
+pub fn transpile(
+  t: &mut Transpiler,
+  values: &[Value],
+  span: Span,
+  node: &Node,
+) -> TranspileResult<String> {
+  let target = match t.target() {
+    Target::Bash => "bash",
+    _ => "POSIX sh",
+  };
+
+  let Some(backend) = t.float_backend() else {
+    return Err(
+      Error::new(
+        &format!("Float arithmetic has no {target} equivalent"),
+        span,
+      )
+      .help("evaluate it with an external command by passing `--float awk` or `--float bc`"),
+    );
+  };
+
+  if t.search(&BlockType::Condition) {
+    return Err(Error::new(
+      &format!("Float arithmetic in a condition has no {target} equivalent"),
+      span,
+    ));
+  }
+
+  let mut expression = Expression {
+    t,
+    node,
+    variables: Vec::new(),
+  };
+
+  let rendered = values
+    .iter()
+    .map(|value| expression.render(value))
+    .collect::<TranspileResult<Vec<_>>>()?
+    .join(" ");
+  let variables = expression.variables;
+
+  let float = values.iter().any(|value| is_float(t, value));
+
+  let command = match backend {
+    FloatBackend::Awk => {
+      let mut options = String::new();
+      for (name, word) in &variables {
+        write!(options, " -v {name}={word}").unwrap();
+      }
+
+      // awk prints whole numbers without a decimal point
+      let program = if float {
+        format!(r#"BEGIN {{ x = {rendered}; print (x == int(x) ? x ".0" : x) }}"#)
+      } else {
+        format!("BEGIN {{ print ({rendered}) }}")
+      };
+
+      format!("awk{options} '{program}'")
+    }
+    FloatBackend::Bc => {
+      let mut assignments = String::new();
+      for (name, word) in &variables {
+        write!(assignments, " {name}={word}").unwrap();
+      }
+
+      let bc = if variables.is_empty() {
+        format!("echo '{rendered}' | bc -l")
+      } else {
+        format!(r"printf '%s\n'{assignments} '{rendered}' | bc -l")
+      };
+
+      // bc prints every decimal of its scale and drops the 0 before the
+      // point, `2.00000000000000000000` and `.5` read `2.0` and `0.5`
+      if float {
+        format!(
+          r"{bc} | sed 's/^\(-\{{0,1\}}\)\./\10./; s/\(\.[0-9]*[1-9]\)0*$/\1/; s/\.0*$/.0/; s/^-\{{0,1\}}[0-9]*$/&.0/'"
+        )
+      } else {
+        bc
+      }
+    }
+  };
+
+  Ok(format!("$({command})"))
+}
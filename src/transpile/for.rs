@@ -1,26 +1,134 @@
 use super::{
   block,
   error::{Error, TranspileResult},
-  transpiler::Transpiler,
+  target::Target,
+  transpiler::{BlockType, Transpiler},
   value,
 };
-use crate::parse::{node::Node, r#for::For};
+use crate::parse::{
+  node::{Node, NodeKind},
+  r#for::For,
+  value::{Literal, Value, ValueKind},
+};
+
+fn int(value: &Value) -> Option<i32> {
+  match value.kind {
+    ValueKind::Literal(Literal::Int(int)) => Some(int),
+    _ => None,
+  }
+}
+
+/// Bound of a zsh brace range, unquoted as quotes would keep the braces
+/// from being a range
+fn transpile_brace_bound(
+  t: &mut Transpiler,
+  value: &Value,
+  node: &Node,
+) -> TranspileResult<String> {
+  if let Some(int) = int(value) {
+    return Ok(int.to_string());
+  }
+
+  t.push_block(BlockType::Arithmetics);
+  let value = value::transpile_inner(t, value, node);
+  t.pop_block();
+
+  Ok(format!("$(( {} ))", value?))
+}
+
+/// Transpiles the loop bounds in arithmetic context, declares the variable
+/// and builds the condition checking whether the loop should continue and
+/// the signed step added to the variable after each iteration
+fn transpile_bounds(
+  t: &mut Transpiler,
+  r#for: &For,
+  node: &Node,
+) -> TranspileResult<(String, String, String, String)> {
+  let For {
+    start, end, step, ..
+  } = r#for;
+
+  t.push_block(BlockType::Arithmetics);
+  let start_string = value::transpile_inner(t, start, node)?;
+  let end_string = value::transpile_inner(t, end, node)?;
+  let step_string = value::transpile_inner(t, step, node)?;
+  t.pop_block();
+
+  let variable = t.declare(&r#for.variable, node.span);
+
+  // like zsh ranges, the loop counts down when the end is below the start,
+  // by the size of the step whatever its sign
+  let step_string = match int(step) {
+    Some(step) => step.abs().to_string(),
+    None => format!("({step_string} < 0 ? -({step_string}) : {step_string})"),
+  };
+  let up = match (int(start), int(end)) {
+    (Some(start), Some(end)) => Some(start <= end),
+    _ => None,
+  };
+
+  let (condition, step) = match up {
+    Some(true) => (format!("{variable} <= {end_string}"), step_string),
+    Some(false) => (
+      format!("{variable} >= {end_string}"),
+      format!("-{step_string}"),
+    ),
+    None => (
+      format!(
+        "{start_string} <= {end_string} ? {variable} <= {end_string} : {variable} >= {end_string}"
+      ),
+      format!("({start_string} <= {end_string} ? {step_string} : -{step_string})"),
+    ),
+  };
+
+  Ok((variable, start_string, step, condition))
+}
 
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::For(For {
-    start,
-    end,
-    step,
-    variable,
-    block,
-  }) = node
-  {
-    let start = value::transpile_inner(t, start, node)?;
-    let end = value::transpile_inner(t, end, node)?;
-    let step = value::transpile_inner(t, step, node)?;
-    let head = t.use_indent(&format!("for {variable} ({{{start}..{end}..{step}}}); do"));
-    let block = block::transpile(t, block)?;
+  if let NodeKind::For(r#for) = &node.kind {
+    let r#for = &**r#for;
+
+    // the variable only exists in the loop
+    t.push_scope();
+
+    let head = match t.target() {
+      Target::Zsh => {
+        let For {
+          start, end, step, ..
+        } = r#for;
+
+        let start = transpile_brace_bound(t, start, node)?;
+        let end = transpile_brace_bound(t, end, node)?;
+        let step = transpile_brace_bound(t, step, node)?;
+        let variable = t.declare(&r#for.variable, node.span);
+
+        t.use_indent(&format!("for {variable} ({{{start}..{end}..{step}}}); do"))
+      }
+      Target::Bash => {
+        let (variable, start, step, condition) = transpile_bounds(t, r#for, node)?;
+
+        t.use_indent(&format!(
+          "for (( {variable} = {start}; {condition}; {variable} += {step} )); do"
+        ))
+      }
+      Target::Sh => {
+        // the increment is part of the condition, so `continue` can't skip it
+        let (variable, start, step, condition) = transpile_bounds(t, r#for, node)?;
+
+        let init = t.use_indent(&format!("{variable}=$(( {start} - {step} ))"));
+        let head = t.use_indent(&format!(
+          "while {variable}=$(( {variable} + {step} )); [ $(( {condition} )) -ne 0 ]; do"
+        ));
+
+        format!("{init}\n{head}")
+      }
+    };
+
+    t.add_local(&r#for.variable);
+
+    let block = block::transpile(t, &r#for.block)?;
     let end = t.use_indent("done");
+    t.pop_scope();
 
     let output = format!("{head}\n{block}\n{end}");
 
@@ -1,25 +1,40 @@
 use super::{
   block,
   error::{Error, TranspileResult},
+  target::Target,
   transpiler::{BlockType, Transpiler},
   value,
 };
-use crate::parse::{node::Node, r#for::Foreach};
+use crate::parse::{
+  node::{Node, NodeKind},
+  r#for::Foreach,
+};
 
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::Foreach(Foreach {
+  if let NodeKind::Foreach(Foreach {
     variable,
     iterable,
     block,
-  }) = node
+  }) = &node.kind
   {
     t.push_block(BlockType::Foreach);
     let iterable = value::transpile_inner(t, iterable, node)?;
     t.pop_block();
 
-    let head = t.use_indent(&format!("for {variable} ({iterable}); do"));
+    // the variable only exists in the loop
+    t.push_scope();
+    t.add_local(variable);
+    let variable = t.declare(variable, node.span);
+
+    let head = match t.target() {
+      Target::Zsh => format!("for {variable} ({iterable}); do"),
+      Target::Bash | Target::Sh => format!("for {variable} in {iterable}; do"),
+    };
+
+    let head = t.use_indent(&head);
     let block = block::transpile_inner(t, block)?;
     let end = t.use_indent("done");
+    t.pop_scope();
 
     let output = format!("{head}\n{block}\n{end}");
 
@@ -1,29 +1,237 @@
 use super::{
   block,
   error::{Error, TranspileResult},
+  target::Target,
   transpiler::{BlockType, Transpiler},
+  value,
 };
-use crate::parse::{function::Function, node::Node};
+use crate::{
+  parse::{
+    function::{Function, Param},
+    node::{Node, NodeKind},
+    value::Value,
+  },
+  types::Span,
+};
+
+/// Commands whose effect on the shell a POSIX sh function body or a try
+/// block would lose
+const SHELL_STATE: [&str; 3] = ["exit", "cd", "trap"];
+
+/// Errors on `what` in a POSIX sh function body or a bash or sh try block, as
+/// it would only change the subshell they run in
+pub fn check_subshell(t: &Transpiler, what: &str, span: Span) -> TranspileResult<()> {
+  if t.target() == Target::Sh && t.in_function() {
+    return Err(Error::new(
+      &format!("Can't use {what} in a function body, it runs in a subshell in POSIX sh"),
+      span,
+    ));
+  }
+
+  let target = match t.target() {
+    Target::Bash => "bash",
+    Target::Sh => "POSIX sh",
+    Target::Zsh => return Ok(()),
+  };
+
+  if t.in_try() {
+    return Err(Error::new(
+      &format!("Can't use {what} in a try block, it runs in a subshell on {target}"),
+      span,
+    ));
+  }
+
+  Ok(())
+}
+
+/// Same as [`check_subshell`] for a call of `name`
+pub fn check_call(t: &Transpiler, name: &str, span: Span) -> TranspileResult<()> {
+  if SHELL_STATE.contains(&name) {
+    check_subshell(t, &format!("`{name}`"), span)?;
+  }
+
+  Ok(())
+}
+
+fn transpile_default(t: &mut Transpiler, default: &Value, node: &Node) -> TranspileResult<String> {
+  t.push_block(BlockType::Expression);
+  let default = value::transpile_inner(t, default, node);
+  t.pop_block();
+
+  default
+}
+
+/// Indented lines setting the variables of named parameters and removing
+/// their arguments from `$@`, `zparseopts` doing the work in zsh
+fn transpile_zsh_options(
+  t: &mut Transpiler,
+  named: &[&Param],
+  node: &Node,
+) -> TranspileResult<Vec<String>> {
+  let arrays = named
+    .iter()
+    .map(|param| format!("_{}", param.variable()))
+    .collect::<Vec<_>>();
+
+  let specs = named
+    .iter()
+    .zip(&arrays)
+    .map(|(param, array)| {
+      let argument = if param.is_flag() { "" } else { ":" };
+      format!("-{}{argument}={array}", param.name)
+    })
+    .collect::<Vec<_>>();
+
+  let mut lines = vec![
+    t.use_indent(&format!("local -a {}", arrays.join(" "))),
+    t.use_indent(&format!("zparseopts -D -E -- {}", specs.join(" "))),
+  ];
+
+  for (param, array) in named.iter().zip(&arrays) {
+    let variable = param.variable();
+
+    // `--name=value` may leave the `=` in front of the value
+    let line = match &param.default {
+      _ if param.is_flag() => format!("local {variable}=${{{array}:+1}}"),
+      Some(default) => format!(
+        "local {variable}=${{${{{array}[-1]#=}}:-{}}}",
+        transpile_default(t, default, node)?
+      ),
+      None => format!("local {variable}=${{{array}[-1]#=}}"),
+    };
+
+    lines.push(t.use_indent(&line));
+  }
+
+  Ok(lines)
+}
+
+/// Same as [`transpile_zsh_options`] with a loop over the arguments, as
+/// `getopts` doesn't know long options and stops at the first positional
+/// argument
+fn transpile_posix_options(
+  t: &mut Transpiler,
+  named: &[&Param],
+  local: &str,
+  node: &Node,
+) -> TranspileResult<Vec<String>> {
+  let mut lines = Vec::new();
+
+  for param in named {
+    let variable = param.variable();
+
+    let line = match &param.default {
+      Some(default) => format!("{local}{variable}={}", transpile_default(t, default, node)?),
+      None => format!("{local}{variable}="),
+    };
+
+    lines.push(t.use_indent(&line));
+  }
+
+  if !local.is_empty() {
+    lines.push(t.use_indent(&format!("{local}_arg")));
+  }
+  lines.push(t.use_indent("for _arg; do"));
+
+  t.push_block(BlockType::Generic);
+  lines.push(t.use_indent("shift"));
+  lines.push(t.use_indent("case $_arg in"));
+
+  t.push_block(BlockType::Generic);
+  for param in named {
+    let (pattern, value) = if param.is_flag() {
+      (format!("--{}", param.name), "1")
+    } else {
+      (format!("--{}=*", param.name), "${_arg#*=}")
+    };
+
+    lines.push(t.use_indent(&format!("{pattern}) {}={value} ;;", param.variable())));
+  }
+  lines.push(t.use_indent(r#"*) set -- "$@" "$_arg" ;;"#));
+  t.pop_block();
+
+  lines.push(t.use_indent("esac"));
+  t.pop_block();
+
+  lines.push(t.use_indent("done"));
+
+  Ok(lines)
+}
+
+fn transpile_params(
+  t: &mut Transpiler,
+  params: &[Param],
+  node: &Node,
+) -> TranspileResult<Vec<String>> {
+  let local = match t.target() {
+    Target::Zsh | Target::Bash => "local ",
+    Target::Sh => "",
+  };
+
+  let named = params
+    .iter()
+    .filter(|param| param.named)
+    .collect::<Vec<_>>();
+
+  // options are removed from `$@` first so the positions are right
+  let mut lines = match t.target() {
+    _ if named.is_empty() => Vec::new(),
+    Target::Zsh => transpile_zsh_options(t, &named, node)?,
+    Target::Bash | Target::Sh => transpile_posix_options(t, &named, local, node)?,
+  };
+
+  let positional = params.iter().filter(|param| !param.named);
+
+  for (i, param) in positional.enumerate() {
+    let variable = param.variable();
+    let position = i + 1;
+
+    let line = match &param.default {
+      Some(default) => format!(
+        "{local}{variable}=${{{position}:-{}}}",
+        transpile_default(t, default, node)?
+      ),
+      None => format!("{local}{variable}=${position}"),
+    };
+
+    lines.push(t.use_indent(&line));
+  }
+
+  Ok(lines)
+}
 
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::Function(Function {
+  if let NodeKind::Function(Function {
     name,
     params,
     block,
-  }) = node
+  }) = &node.kind
   {
-    let head = t.use_indent(&format!("function {name}() {{"));
+    // POSIX sh has no `local`, so the body runs in a subshell instead
+    let (head, end) = match t.target() {
+      Target::Zsh | Target::Bash => (format!("function {name}() {{"), "}"),
+      Target::Sh => (format!("{name}() ("), ")"),
+    };
+
+    let head = t.use_indent(&head);
+
+    let variables = params.iter().map(Param::variable).collect::<Vec<_>>();
+    t.push_function_scope(&variables);
 
     t.push_block(BlockType::Generic);
-    let params = params
-      .iter()
-      .enumerate()
-      .map(|(i, param)| t.use_indent(&format!("local {param}=${}\n", i + 1)))
-      .collect::<String>();
+    let params = transpile_params(t, params, node).map(|lines| {
+      lines
+        .into_iter()
+        .map(|line| line + "\n")
+        .collect::<String>()
+    });
     t.pop_block();
 
-    let block = block::transpile(t, block)?;
-    let end = t.use_indent("}");
+    let block = params.and_then(|params| Ok((params, block::transpile_body(t, block)?)));
+    t.pop_function_scope();
+
+    let (params, block) = block?;
+    let end = t.use_indent(end);
 
     let output = format!("{head}\n{params}{block}\n{end}");
 
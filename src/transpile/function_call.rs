@@ -1,20 +1,142 @@
 use super::{
   error::{Error, TranspileResult},
+  float, function, quote,
+  target::Target,
   transpiler::{BlockType, Transpiler},
   value,
 };
 use crate::parse::{
-  function_call::{FunctionCall, Next},
-  node::Node,
+  function_call::{FunctionCall, Next, Redirect, RedirectKind},
+  node::{Node, NodeKind},
+  value::{Literal, Value, ValueKind},
 };
 
 fn transpile_next(t: &mut Transpiler, next: &Next, node: &Node) -> TranspileResult<String> {
   match next {
     Next::Call(call) => transpile_inner(t, call, node),
-    Next::File(file) => Ok(format!(">{file}")),
   }
 }
 
+/// Redirections of a call, each after a space
+fn transpile_redirects(
+  t: &mut Transpiler,
+  redirects: &[Redirect],
+  node: &Node,
+) -> TranspileResult<String> {
+  let mut transpiled = String::new();
+
+  for redirect in redirects {
+    let (kind, file) = match redirect {
+      Redirect::File(kind, file) => (kind, file),
+      Redirect::ErrorToOutput => {
+        transpiled += " 2>&1";
+        continue;
+      }
+      Redirect::OutputToError => {
+        transpiled += " >&2";
+        continue;
+      }
+    };
+
+    let file = match &file.kind {
+      ValueKind::Literal(Literal::String(file) | Literal::RawString(file)) => quote::word(file),
+      _ => value::transpile_inner(t, file, node)?,
+    };
+
+    let redirect = match (kind, t.target()) {
+      (RedirectKind::Input, _) => format!("<{file}"),
+      (RedirectKind::Output, _) => format!(">{file}"),
+      (RedirectKind::Append, _) => format!(">>{file}"),
+      (RedirectKind::Error, _) => format!("2>{file}"),
+      (RedirectKind::AppendError, _) => format!("2>>{file}"),
+      (RedirectKind::All, Target::Zsh | Target::Bash) => format!("&>{file}"),
+      (RedirectKind::AppendAll, Target::Zsh | Target::Bash) => format!("&>>{file}"),
+      // POSIX sh reads `&>` as running the command in the background
+      (RedirectKind::All, Target::Sh) => format!(">{file} 2>&1"),
+      (RedirectKind::AppendAll, Target::Sh) => format!(">>{file} 2>&1"),
+    };
+
+    transpiled.push(' ');
+    transpiled += &redirect;
+  }
+
+  Ok(transpiled)
+}
+
+/// `"""..."""` piped into a command, which gets the text through a heredoc
+fn transpile_heredoc(
+  t: &mut Transpiler,
+  call: &FunctionCall,
+  node: &Node,
+) -> TranspileResult<String> {
+  let FunctionCall { args, next, .. } = call;
+
+  let [text] = &args[..] else {
+    return Err(Error::invalid(node));
+  };
+
+  // a heredoc ends the line, so it can't be followed by more of a condition
+  if !matches!(
+    t.get_block(),
+    None | Some(BlockType::Generic | BlockType::Expression)
+  ) {
+    let format = Value::new(
+      ValueKind::Literal(Literal::RawString(r"%s\n".to_owned())),
+      text.span,
+    );
+    let printf = FunctionCall {
+      name: "printf".to_owned(),
+      args: vec![format, text.clone()],
+      ..call.clone()
+    };
+
+    return transpile_inner(t, &printf, node);
+  }
+
+  let (body, expands) = value::transpile_heredoc(t, text, node)?;
+  let delimiter = quote::delimiter(&body);
+
+  t.push_block(BlockType::FunctionCall);
+  let command = match next {
+    Some(Next::Call(command)) => {
+      let head = FunctionCall {
+        next: None,
+        is_daemon: false,
+        ..(**command).clone()
+      };
+      let head = transpile_inner(t, &head, node);
+
+      let tail = match &command.next {
+        Some(next) => transpile_next(t, next, node).map(|next| format!(" | {next}")),
+        None if command.is_daemon => Ok(" &".to_owned()),
+        None => Ok(String::new()),
+      };
+
+      head.and_then(|head| Ok((head, tail?)))
+    }
+    // only redirected to a file
+    None => transpile_redirects(t, &call.redirects, node).map(|redirects| {
+      let daemon = if call.is_daemon { " &" } else { "" };
+      (format!("cat{redirects}"), daemon.to_owned())
+    }),
+  };
+  t.pop_block();
+  let (head, tail) = command?;
+
+  let redirect = if expands {
+    delimiter.clone()
+  } else {
+    format!("'{delimiter}'")
+  };
+
+  let call = format!("{head} <<{redirect}{tail}\n{body}\n{delimiter}");
+
+  Ok(match t.get_block() {
+    Some(BlockType::Expression) => format!("\"$({call}\n)\""),
+    _ => t.use_indent(&call),
+  })
+}
+
 pub fn transpile_inner(
   t: &mut Transpiler,
   call: &FunctionCall,
@@ -24,9 +146,26 @@ pub fn transpile_inner(
     name,
     args,
     is_daemon,
+    redirects,
     next,
   } = call;
 
+  if name == "<<" {
+    return transpile_heredoc(t, call, node);
+  }
+
+  function::check_call(t, name, node.span)?;
+
+  if name == "$" && float::is_needed(t, args) {
+    let command = float::transpile(t, args, node.span, node)?;
+
+    if matches!(t.get_block(), Some(BlockType::Generic)) {
+      return Ok(t.use_indent(&format!(": {command}")));
+    }
+
+    return Ok(command);
+  }
+
   if name == "$" {
     t.push_block(BlockType::Arithmetics);
 
@@ -41,10 +180,15 @@ pub fn transpile_inner(
     let args = transpiled_args.join(" ");
 
     if matches!(t.get_block(), Some(BlockType::Generic)) {
-      return Ok(t.use_indent(&format!("(( {args} ))")));
+      let statement = match t.target() {
+        Target::Zsh | Target::Bash => format!("(( {args} ))"),
+        Target::Sh => format!(": $(( {args} ))"),
+      };
+
+      return Ok(t.use_indent(&statement));
     }
 
-    return Ok(format!("$(( {args} ))"));
+    return Ok(float::format(t, &call.args, format!("$(( {args} ))")));
   }
 
   let basic_call = if args.is_empty() {
@@ -52,12 +196,13 @@ pub fn transpile_inner(
   } else {
     let transpiled_args = args
       .iter()
-      .map(|arg| value::transpile_inner(t, arg, node))
+      .map(|arg| value::transpile_argument(t, arg, node))
       .collect::<TranspileResult<Vec<_>>>()?
       .join(" ");
 
     format!("{name} {transpiled_args}")
   };
+  let basic_call = basic_call + &transpile_redirects(t, redirects, node)?;
 
   let call = if let Some(next) = next {
     t.push_block(BlockType::FunctionCall);
@@ -80,7 +225,7 @@ pub fn transpile_inner(
 }
 
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::FunctionCall(call) = node {
+  if let NodeKind::FunctionCall(call) = &node.kind {
     transpile_inner(t, call, node)
   } else {
     Err(Error::invalid(node))
@@ -4,22 +4,23 @@ use super::{
   block,
   error::{Error, TranspileResult},
   function_call,
+  target::Target,
   transpiler::{BlockType, Transpiler},
   utils::random_string,
   value::{self, transpile_binary_operator},
 };
 use crate::parse::{
   function_call::FunctionCall,
-  node::Node,
+  node::{Node, NodeKind},
   r#if::{Else, If, IfLet},
-  value::Value,
+  value::{BinaryOperator, UnaryOperator, Value, ValueKind},
 };
 
 fn is_function_call(condition: &Value) -> bool {
-  if let Value::BinaryExpression(right, _, left) = condition {
+  if let ValueKind::BinaryExpression(right, _, left) = &condition.kind {
     is_function_call(right) || is_function_call(left)
   } else {
-    if let Value::FunctionCall(FunctionCall { name, .. }) = condition {
+    if let ValueKind::FunctionCall(FunctionCall { name, .. }) = &condition.kind {
       if name != "$" {
         return true;
       }
@@ -29,18 +30,134 @@ fn is_function_call(condition: &Value) -> bool {
   }
 }
 
+fn transpile_test_operand(
+  t: &mut Transpiler,
+  value: &Value,
+  node: &Node,
+) -> TranspileResult<String> {
+  t.push_block(BlockType::Condition);
+  let value = value::transpile_inner(t, value, node);
+  t.pop_block();
+
+  value
+}
+
+/// Transpiles an operand of `&&` or `||`, grouping it if it's a chain of the
+/// other operator, because the shell gives both the same precedence
+fn transpile_logic_operand(
+  t: &mut Transpiler,
+  value: &Value,
+  operator: &BinaryOperator,
+  node: &Node,
+) -> TranspileResult<String> {
+  let condition = transpile_posix_condition(t, value, node)?;
+
+  match &value.kind {
+    ValueKind::BinaryExpression(_, op @ (BinaryOperator::And | BinaryOperator::Or), _)
+      if op != operator =>
+    {
+      Ok(format!("{{ {condition}; }}"))
+    }
+    _ => Ok(condition),
+  }
+}
+
+/// Transpiles a condition using only `[ ]` tests, which POSIX sh supports
+pub fn transpile_posix_condition(
+  t: &mut Transpiler,
+  condition: &Value,
+  node: &Node,
+) -> TranspileResult<String> {
+  match &condition.kind {
+    ValueKind::BinaryExpression(
+      left,
+      operator @ (BinaryOperator::And | BinaryOperator::Or),
+      right,
+    ) => {
+      let left = transpile_logic_operand(t, left, operator, node)?;
+      let right = transpile_logic_operand(t, right, operator, node)?;
+
+      Ok(format!(
+        "{left} {} {right}",
+        transpile_binary_operator(operator)
+      ))
+    }
+    ValueKind::BinaryExpression(_, operator, _) if operator.is_arithmetic() => Ok(format!(
+      "[ {} -ne 0 ]",
+      transpile_test_operand(t, condition, node)?
+    )),
+    ValueKind::BinaryExpression(left, BinaryOperator::RegexMatch, right) => {
+      value::check_comparison(left, right, condition.span)?;
+
+      let left = transpile_test_operand(t, left, node)?;
+      let right = transpile_test_operand(t, right, node)?;
+
+      Ok(format!("printf '%s\\n' {left} | grep -Eq -- {right}"))
+    }
+    ValueKind::BinaryExpression(left, operator, right) => {
+      let operator = match operator {
+        BinaryOperator::Equal => "=",
+        BinaryOperator::NotEqual => "!=",
+        BinaryOperator::Greater
+        | BinaryOperator::GreaterEqual
+        | BinaryOperator::Less
+        | BinaryOperator::LessEqual => transpile_binary_operator(operator),
+        op => {
+          return Err(Error::new(
+            &format!("Operator {op:?} can't be used as a condition"),
+            condition.span,
+          ))
+        }
+      };
+
+      value::check_comparison(left, right, condition.span)?;
+
+      let left = transpile_test_operand(t, left, node)?;
+      let right = transpile_test_operand(t, right, node)?;
+
+      Ok(format!("[ {left} {operator} {right} ]"))
+    }
+    ValueKind::Parenthesized(value) => Ok(format!(
+      "{{ {}; }}",
+      transpile_posix_condition(t, value, node)?
+    )),
+    ValueKind::UnaryExpression(UnaryOperator::Not, value) => {
+      Ok(format!("! {}", transpile_posix_condition(t, value, node)?))
+    }
+    ValueKind::FunctionCall(FunctionCall { name, .. }) if name != "$" => {
+      transpile_test_operand(t, condition, node)
+    }
+    _ => Ok(format!(
+      "[ -n {} ]",
+      transpile_test_operand(t, condition, node)?
+    )),
+  }
+}
+
 pub fn transpile_condition(
   t: &mut Transpiler,
   condition: &Value,
   node: &Node,
 ) -> TranspileResult<String> {
+  if t.target() == Target::Sh {
+    return transpile_posix_condition(t, condition, node);
+  }
+
+  if matches!(&condition.kind, ValueKind::BinaryExpression(_, op, _) if op.is_arithmetic()) {
+    t.push_block(BlockType::Arithmetics);
+    let string = value::transpile_inner(t, condition, node);
+    t.pop_block();
+
+    return Ok(format!("(( {} ))", string?));
+  }
+
   t.push_block(BlockType::Condition);
   let string = value::transpile_inner(t, condition, node)?;
   t.pop_block();
 
-  if matches!(condition, Value::FunctionCall(_)) {
+  if matches!(condition.kind, ValueKind::FunctionCall(_)) {
     Ok(string)
-  } else if let Value::BinaryExpression(left, op, right) = condition {
+  } else if let ValueKind::BinaryExpression(left, op, right) = &condition.kind {
     if is_function_call(right) || is_function_call(left) {
       Ok(format!(
         "{} {} {}",
@@ -57,11 +174,11 @@ pub fn transpile_condition(
 }
 
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::If(If {
+  if let NodeKind::If(If {
     condition,
     r#else,
     block,
-  }) = node
+  }) = &node.kind
   {
     let condition = transpile_condition(t, condition, node)?;
     let head = t.use_indent(&format!("if {condition}; then"));
@@ -109,17 +226,41 @@ pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
 }
 
 pub fn transpile_let(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::IfLet(IfLet {
+  if let NodeKind::IfLet(IfLet {
     name,
     call,
     block,
     r#else,
-  }) = node
+  }) = &node.kind
   {
     t.push_block(BlockType::FunctionCall);
     let call = function_call::transpile_inner(t, call, node)?;
     t.pop_block();
 
+    // the variable only exists in the blocks
+    t.push_scope();
+    t.add_local(name);
+    let name = t.declare(name, node.span);
+
+    // without `local`, there is nothing to gain from the wrapper function
+    if t.target() == Target::Sh {
+      let head = t.use_indent(&format!("if {name}=$({call}); then"));
+      let block = block::transpile_inner(t, block)?;
+
+      let string_else = if let Some(else_block) = r#else {
+        let head = t.use_indent("else");
+        let else_block = block::transpile_inner(t, else_block)?;
+        format!("{head}\n{else_block}\n")
+      } else {
+        String::new()
+      };
+
+      let end = t.use_indent("fi");
+      t.pop_scope();
+
+      return Ok(format!("{head}\n{block}\n{string_else}{end}"));
+    }
+
     let temp_name = format!("__tmp_{}", random_string(6));
 
     t.push_block(BlockType::Generic);
@@ -153,6 +294,8 @@ pub fn transpile_let(t: &mut Transpiler, node: &Node) -> TranspileResult<String>
       )
     };
 
+    t.pop_scope();
+
     let output = output
       .trim()
       .lines()
@@ -0,0 +1,20 @@
+use super::{
+  error::{Error, TranspileResult},
+  transpiler::Transpiler,
+};
+use crate::parse::{
+  import::Import,
+  node::{Node, NodeKind},
+};
+
+pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
+  if let NodeKind::Import(Import { path, tree }) = &node.kind {
+    t.push_file(path);
+    let code = super::inner(tree, t).map_err(|e| e.in_file(path));
+    t.pop_file();
+
+    code
+  } else {
+    Err(Error::invalid(node))
+  }
+}
@@ -0,0 +1,168 @@
+//! Quoting of text for the shell, so a string reaches the command it's
+//! passed to as exactly the text it holds
+
+use std::fmt::Write;
+
+use super::target::Target;
+
+/// Escapes the characters that are live inside double quotes, the only ones
+/// zsh, bash and POSIX sh don't take literally there
+pub fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+
+  for char in text.chars() {
+    if matches!(char, '\\' | '"' | '$' | '`') {
+      escaped.push('\\');
+    }
+
+    escaped.push(char);
+  }
+
+  escaped
+}
+
+/// Escapes the characters that are live in the body of a heredoc whose
+/// delimiter isn't quoted, where quotes are taken literally
+pub fn escape_heredoc(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+
+  for char in text.chars() {
+    if matches!(char, '\\' | '$' | '`') {
+      escaped.push('\\');
+    }
+
+    escaped.push(char);
+  }
+
+  escaped
+}
+
+fn double_quote(text: &str) -> String {
+  format!("\"{}\"", escape(text))
+}
+
+/// `$'...'` word, which zsh and bash have for text with line breaks and other
+/// control characters
+fn ansi_c(text: &str) -> String {
+  let mut quoted = String::from("$'");
+
+  for char in text.chars() {
+    match char {
+      '\\' | '\'' => {
+        quoted.push('\\');
+        quoted.push(char);
+      }
+      '\n' => quoted += r"\n",
+      '\t' => quoted += r"\t",
+      '\r' => quoted += r"\r",
+      '\x1b' => quoted += r"\e",
+      char if char.is_control() => write!(quoted, r"\x{:02x}", u32::from(char)).unwrap(),
+      char => quoted.push(char),
+    }
+  }
+
+  quoted.push('\'');
+  quoted
+}
+
+fn needs_ansi_c(text: &str, target: Target) -> bool {
+  target != Target::Sh && text.chars().any(char::is_control)
+}
+
+/// Double quoted word holding `text`, or a `$'...'` one if it has control
+/// characters and the target has those
+pub fn quote(text: &str, target: Target) -> String {
+  if needs_ansi_c(text, target) {
+    ansi_c(text)
+  } else {
+    double_quote(text)
+  }
+}
+
+/// Single quoted word holding `text`, like [`quote`] otherwise if `text` has
+/// a single quote, which single quotes can't escape, or control characters
+pub fn single_quote(text: &str, target: Target) -> String {
+  if needs_ansi_c(text, target) {
+    ansi_c(text)
+  } else if text.contains('\'') {
+    double_quote(text)
+  } else {
+    format!("'{text}'")
+  }
+}
+
+/// `text` as a bare word if the shell would take it literally, quoted
+/// otherwise
+pub fn word(text: &str) -> String {
+  let bare = !text.is_empty()
+    && text.chars().all(|c| {
+      c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '+' | ',' | ':' | '@' | '%')
+    });
+
+  if bare {
+    text.to_owned()
+  } else {
+    double_quote(text)
+  }
+}
+
+/// Key of a map between the brackets of a subscript. Inside of a double
+/// quoted expansion, bash misreads double quotes there and looks for command
+/// substitutions even in single quotes. zsh reads the brackets as if they
+/// were double quotes already, and takes a lone `@` or `*` for every item
+/// unless the `(e)` flag makes it a plain key
+pub fn subscript(text: &str, target: Target) -> String {
+  match target {
+    Target::Zsh if matches!(text, "@" | "*") => format!("(e){text}"),
+    Target::Zsh => {
+      let mut escaped = String::with_capacity(text.len());
+
+      for char in text.chars() {
+        if matches!(char, '\\' | '"' | '$' | '`' | '[' | ']') {
+          escaped.push('\\');
+        }
+
+        escaped.push(char);
+      }
+
+      escaped
+    }
+    Target::Bash | Target::Sh => {
+      let mut quoted = String::from("'");
+
+      for char in text.chars() {
+        match char {
+          '\'' | '$' | '`' => write!(quoted, r"'\{char}'").unwrap(),
+          char => quoted.push(char),
+        }
+      }
+
+      quoted.push('\'');
+
+      if text.is_empty() {
+        return quoted;
+      }
+
+      // no empty quotes around a character escaped at either end
+      let quoted = quoted.strip_prefix("''").unwrap_or(&quoted);
+      let quoted = quoted.strip_suffix("''").unwrap_or(quoted);
+
+      quoted.to_owned()
+    }
+  }
+}
+
+/// Delimiter of a heredoc that isn't a line of its `body`
+pub fn delimiter(body: &str) -> String {
+  let taken = |delimiter: &str| body.lines().any(|line| line == delimiter);
+
+  if !taken("EOF") {
+    return "EOF".to_owned();
+  }
+
+  // one of them is always free
+  (1..=body.lines().count())
+    .map(|n| format!("EOF_{n}"))
+    .find(|delimiter| !taken(delimiter))
+    .expect("every delimiter is taken")
+}
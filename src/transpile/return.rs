@@ -3,10 +3,10 @@ use super::{
   transpiler::Transpiler,
   value,
 };
-use crate::parse::node::Node;
+use crate::parse::node::{Node, NodeKind};
 
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::Return(value) = node {
+  if let NodeKind::Return(value) = &node.kind {
     let output = format!("return {}", value::transpile_inner(t, value, node)?);
 
     Ok(t.use_indent(&output))
@@ -0,0 +1,83 @@
+//! `let result = run(cmd());`, keeping the output, errors and exit code of
+//! the command in a variable each
+
+use super::{
+  error::{Error, TranspileResult},
+  function_call,
+  target::Target,
+  transpiler::{BlockType, Transpiler},
+};
+use crate::{
+  infer::ValueType,
+  parse::{function_call::FunctionCall, node::Node},
+  types::Span,
+};
+
+/// Fields of a result, the variables it's made of being named after them
+pub const FIELDS: &[&str] = &["code", "stdout", "stderr"];
+
+/// Variable holding the `field` of the result `name`
+pub fn variable(name: &str, field: &str) -> String {
+  format!("{name}__{field}")
+}
+
+/// Rejects the variable `name` at `span` if it holds a result, which has no
+/// value of its own besides its fields
+pub fn check_value(t: &Transpiler, name: &str, span: Span) -> TranspileResult<()> {
+  if t.type_of(span) == Some(ValueType::Result) {
+    return Err(
+      Error::new(
+        &format!("'{name}' holds the result of run(), only its fields have a value"),
+        span,
+      )
+      .help(&format!("use one of them instead, like `{name}.stdout`")),
+    );
+  }
+
+  Ok(())
+}
+
+/// The command `call` runs, to be transpiled before the result is declared,
+/// as it can still refer to a variable the declaration shadows
+pub fn command(t: &mut Transpiler, call: &FunctionCall, node: &Node) -> TranspileResult<String> {
+  t.push_block(BlockType::FunctionCall);
+  let command = function_call::transpile_inner(t, call, node);
+  t.pop_block();
+
+  command
+}
+
+/// Runs `command`, keeping its result in the variables of `name`
+pub fn transpile(t: &Transpiler, name: &str, command: &str) -> String {
+  let code = variable(name, "code");
+  let stdout = variable(name, "stdout");
+  let stderr = variable(name, "stderr");
+
+  // declared apart from the assignments, which would otherwise give the
+  // exit code of `local` instead of the command's
+  let mut lines = match t.target() {
+    Target::Zsh => vec![
+      format!("local {stdout} {stderr}"),
+      format!("local -i {code}"),
+    ],
+    Target::Bash => vec![
+      format!("declare {stdout} {stderr}"),
+      format!("declare -i {code}"),
+    ],
+    Target::Sh => Vec::new(),
+  };
+
+  // errors go to a temporary file, whose path the variable holds until then
+  lines.extend([
+    format!(r#"{stderr}="$(mktemp)""#),
+    format!(r#"{stdout}="$({{ {command}; }} 2>"${{{stderr}}}")""#),
+    format!("{code}=$?"),
+    format!(r#"{stderr}="$(cat "${{{stderr}}}"; rm -f "${{{stderr}}}")""#),
+  ]);
+
+  lines
+    .iter()
+    .map(|line| t.use_indent(line))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
@@ -0,0 +1,43 @@
+use super::{
+  block,
+  error::{Error, TranspileResult},
+  function,
+  target::Target,
+  transpiler::Transpiler,
+};
+use crate::parse::{
+  node::{Node, NodeKind},
+  signal::Signal,
+};
+
+/// Function running the handler, which `trap` calls on any of the signals
+pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
+  if let NodeKind::Signal(Signal { signals, block }) = &node.kind {
+    function::check_subshell(t, "`on signal(...)`", node.span)?;
+
+    let name = format!("__on_signal_{}", t.next_function());
+
+    let head = match t.target() {
+      Target::Zsh | Target::Bash => format!("function {name}() {{"),
+      Target::Sh => format!("{name}() {{"),
+    };
+
+    // not a subshell, so the block can assign the variables of the script
+    let body = block::transpile_inner(t, block);
+
+    for signal in signals {
+      t.add_handler(signal);
+    }
+
+    let lines = [
+      t.use_indent(&head),
+      body?,
+      t.use_indent("}"),
+      t.use_indent(&format!("trap '{name}' {}", signals.join(" "))),
+    ];
+
+    Ok(lines.join("\n"))
+  } else {
+    Err(Error::invalid(node))
+  }
+}
@@ -1,12 +1,24 @@
 use super::{
   error::{Error, TranspileResult},
+  function, quote,
+  target::Target,
   transpiler::Transpiler,
 };
-use crate::parse::node::Node;
+use crate::parse::node::{Node, NodeKind};
 
-pub fn transpile(_t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::Source(file) = node {
-    Ok(format!("source '{file}'"))
+pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
+  if let NodeKind::Source(file) = &node.kind {
+    function::check_subshell(t, "`source`", node.span)?;
+
+    let command = match t.target() {
+      Target::Zsh | Target::Bash => "source",
+      Target::Sh => ".",
+    };
+
+    Ok(format!(
+      "{command} {}",
+      quote::single_quote(file, t.target())
+    ))
   } else {
     Err(Error::invalid(node))
   }
@@ -1,20 +1,21 @@
 use super::{
   block,
   error::{Error, TranspileResult},
+  quote,
   transpiler::Transpiler,
   value,
 };
 use crate::parse::{
-  node::Node,
+  node::{Node, NodeKind},
   switch::{Case, Switch},
 };
 
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::Switch(Switch { arg, cases }) = node {
+  if let NodeKind::Switch(Switch { arg, cases }) = &node.kind {
     let cases = cases
       .iter()
       .map(|Case { value, block }| {
-        let head = t.use_indent(value);
+        let head = t.use_indent(&quote::word(value));
         let block = block::transpile(t, block)?;
         let end = t.use_indent(";;");
 
@@ -25,7 +26,7 @@ pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
 
     let arg = value::transpile_inner(t, arg, node)?;
 
-    Ok(format!("case {arg} in\n{cases}\nesac",))
+    Ok(format!("case {arg} in\n{cases}\nesac"))
   } else {
     Err(Error::invalid(node))
   }
@@ -0,0 +1,40 @@
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Target {
+  #[default]
+  Zsh,
+  /// bash 4 or newer
+  Bash,
+  /// Strict POSIX shell, e.g. dash or busybox
+  Sh,
+}
+
+impl Target {
+  pub fn shebang(self) -> &'static str {
+    match self {
+      Target::Zsh => "#!/usr/bin/env zsh",
+      Target::Bash => "#!/usr/bin/env bash",
+      Target::Sh => "#!/bin/sh",
+    }
+  }
+
+  pub fn extension(self) -> &'static str {
+    match self {
+      Target::Zsh => "zsh",
+      Target::Bash => "bash",
+      Target::Sh => "sh",
+    }
+  }
+}
+
+/// External command evaluating float arithmetic on targets without floats
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum FloatBackend {
+  /// `bc -l`, printing results with its own number of decimals
+  Bc,
+  Awk,
+}
@@ -1,3 +1,13 @@
+use std::collections::{HashMap, HashSet};
+
+use super::target::{FloatBackend, Target};
+use crate::{
+  diagnostic::Diagnostic,
+  infer::{Types, ValueType},
+  lint::Lint,
+  types::Span,
+};
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum BlockType {
   Foreach,
@@ -10,23 +20,91 @@ pub enum BlockType {
   Raw,
 }
 
+/// Variables declared in a block
+#[derive(Debug, Default)]
+struct Scope {
+  /// names in the source mapped to the names in the output
+  names: HashMap<String, String>,
+  /// body of a function or the file itself, which variables can't outlive
+  function: bool,
+  /// functions the EXIT trap of the function or file calls, the last
+  /// deferred first
+  defers: Vec<String>,
+}
+
+impl Scope {
+  fn function(names: &[String]) -> Self {
+    Self {
+      names: names
+        .iter()
+        .map(|name| (name.clone(), name.clone()))
+        .collect(),
+      function: true,
+      defers: Vec::new(),
+    }
+  }
+}
+
 #[derive(Debug)]
 pub struct Transpiler {
-  indent_char: &'static str,
+  indent: String,
+  target: Target,
+  float: Option<FloatBackend>,
 
   blocks: Vec<BlockType>,
+
+  types: Types,
+
+  /// names local to each of the enclosing functions
+  function_scopes: Vec<HashSet<String>>,
+
+  /// blocks enclosing the current node, the file being the first one
+  scopes: Vec<Scope>,
+  /// variables renamed so far, to give each a distinct name
+  renamed: usize,
+  /// functions made up for `defer` blocks and signal handlers so far, to
+  /// give each a distinct name
+  generated: usize,
+  /// signals with a handler, which `defer` leaves to it
+  handled: HashSet<String>,
+  /// number of scopes outside of each enclosing try block
+  tries: Vec<usize>,
+
+  /// imported files being transpiled
+  files: Vec<String>,
+
+  warnings: Vec<Diagnostic>,
 }
 
 impl Transpiler {
-  pub fn new(indent_char: &'static str) -> Self {
+  pub fn new(indent: &str, target: Target, types: Types, float: Option<FloatBackend>) -> Self {
     Self {
-      indent_char,
+      indent: indent.to_owned(),
+      target,
+      float,
       blocks: vec![],
+      types,
+      function_scopes: vec![],
+      scopes: vec![Scope::function(&[])],
+      renamed: 0,
+      generated: 0,
+      handled: HashSet::new(),
+      tries: vec![],
+      files: vec![],
+      warnings: vec![],
     }
   }
 
+  pub fn target(&self) -> Target {
+    self.target
+  }
+
+  pub fn float_backend(&self) -> Option<FloatBackend> {
+    self.float
+  }
+
   pub fn use_indent(&self, str: &str) -> String {
-    let indent = self.indent_char.repeat(self.blocks.len());
+    let indent = self.indent.repeat(self.blocks.len());
     format!("{indent}{str}")
   }
 
@@ -47,4 +125,171 @@ impl Transpiler {
   pub fn search(&self, block: &BlockType) -> bool {
     self.blocks.iter().any(|b| b == block)
   }
+
+  /// Inferred type of the declaration or identifier at `span`
+  pub fn type_of(&self, span: Span) -> Option<ValueType> {
+    self.types.get(self.files.last().map(String::as_str), span)
+  }
+
+  pub fn push_function_scope(&mut self, params: &[String]) {
+    self.function_scopes.push(params.iter().cloned().collect());
+    self.scopes.push(Scope::function(params));
+  }
+
+  pub fn pop_function_scope(&mut self) {
+    self.function_scopes.pop();
+    self.scopes.pop();
+  }
+
+  pub fn push_scope(&mut self) {
+    self.scopes.push(Scope::default());
+  }
+
+  pub fn pop_scope(&mut self) {
+    debug_assert!(self.scopes.len() > 1, "popped the scope of the file");
+
+    self.scopes.pop();
+  }
+
+  /// Declares `name` in the current block and returns the name to use in
+  /// the output.
+  ///
+  /// The shell only scopes variables to functions, so one shadowing a
+  /// variable of an outer block is renamed, leaving the outer one untouched
+  /// once the block ends.
+  pub fn declare(&mut self, name: &str, span: Span) -> String {
+    let (current, outer) = self.scopes.split_last_mut().expect("no scope");
+
+    if let Some(declared) = current.names.get(name) {
+      return declared.clone();
+    }
+
+    let visible = |scopes: &[Scope]| scopes.iter().any(|scope| scope.names.contains_key(name));
+
+    // shadowing a variable from outside the function is expected, and
+    // `local` already hides it at the top of the function body
+    let function = outer
+      .iter()
+      .rposition(|scope| scope.function)
+      .unwrap_or_default();
+    let shadows = !current.function && visible(&outer[function..]);
+
+    let declared = if !current.function && visible(outer) {
+      self.renamed += 1;
+      format!("{name}__{}", self.renamed)
+    } else {
+      name.to_owned()
+    };
+
+    current.names.insert(name.to_owned(), declared.clone());
+
+    if shadows {
+      self.warn(
+        Diagnostic::warning(&format!("Variable {name} shadows an outer variable"))
+          .with_span(span)
+          .with_note("the outer variable is back once the block ends")
+          .with_lint(Lint::ShadowedVariable),
+      );
+    }
+
+    declared
+  }
+
+  /// Name in the output of the variable `name` refers to
+  pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+    self
+      .scopes
+      .iter()
+      .rev()
+      .find_map(|scope| scope.names.get(name))
+      .map_or(name, String::as_str)
+  }
+
+  /// Whether the current block is the body of a function or the file
+  /// itself, rather than a block nested in them
+  pub fn at_function_level(&self) -> bool {
+    self.scopes.last().is_some_and(|scope| scope.function)
+  }
+
+  /// Number of the next made up function, for its name
+  pub fn next_function(&mut self) -> usize {
+    self.generated += 1;
+    self.generated
+  }
+
+  pub fn add_handler(&mut self, signal: &str) {
+    self.handled.insert(signal.to_owned());
+  }
+
+  pub fn has_handler(&self, signal: &str) -> bool {
+    self.handled.contains(signal)
+  }
+
+  /// Adds `name` to the functions the EXIT trap of the current function or
+  /// file calls, returning them all in the order the trap calls them
+  pub fn add_defer(&mut self, name: &str) -> &[String] {
+    let scope = self.scopes.last_mut().expect("no scope");
+    scope.defers.insert(0, name.to_owned());
+
+    &scope.defers
+  }
+
+  pub fn push_try(&mut self) {
+    self.tries.push(self.scopes.len());
+  }
+
+  pub fn pop_try(&mut self) {
+    self.tries.pop();
+  }
+
+  pub fn in_try(&self) -> bool {
+    !self.tries.is_empty()
+  }
+
+  /// Whether the variable `name` was declared outside of the innermost try
+  /// block, rather than in it
+  pub fn is_outside_try(&self, name: &str) -> bool {
+    self.tries.last().is_some_and(|&outside| {
+      !self.scopes[outside..]
+        .iter()
+        .any(|scope| scope.names.contains_key(name))
+    })
+  }
+
+  pub fn in_function(&self) -> bool {
+    !self.function_scopes.is_empty()
+  }
+
+  pub fn add_local(&mut self, name: &str) {
+    if let Some(scope) = self.function_scopes.last_mut() {
+      scope.insert(name.to_owned());
+    }
+  }
+
+  pub fn is_local(&self, name: &str) -> bool {
+    self
+      .function_scopes
+      .last()
+      .is_some_and(|scope| scope.contains(name))
+  }
+
+  pub fn push_file(&mut self, file: &str) {
+    self.files.push(file.to_owned());
+  }
+
+  pub fn pop_file(&mut self) {
+    self.files.pop();
+  }
+
+  pub fn warn(&mut self, mut warning: Diagnostic) {
+    if let Some(file) = self.files.last() {
+      warning = warning.in_file(file);
+    }
+
+    self.warnings.push(warning);
+  }
+
+  pub fn take_warnings(&mut self) -> Vec<Diagnostic> {
+    std::mem::take(&mut self.warnings)
+  }
 }
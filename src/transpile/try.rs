@@ -0,0 +1,226 @@
+//! `try { ... } catch error { ... } finally { ... }`, the try block stopping
+//! at the first command failing, like with `set -e`
+
+use std::slice;
+
+use super::{
+  block,
+  error::{Error, TranspileResult},
+  target::Target,
+  transpiler::{BlockType, Transpiler},
+};
+use crate::{
+  parse::{
+    node::{Node, NodeKind},
+    r#if::{Else, If},
+    r#try::Try,
+  },
+  types::Span,
+};
+
+/// First `return`, `break` or `continue` in `nodes` jumping out of them,
+/// `looped` if they're in a loop, which `break` and `continue` stay in
+pub fn leaving(nodes: &[Node], looped: bool) -> Option<(&'static str, &Node)> {
+  nodes.iter().find_map(|node| match &node.kind {
+    NodeKind::Return(_) => Some(("return", node)),
+    NodeKind::Break if !looped => Some(("break", node)),
+    NodeKind::Continue if !looped => Some(("continue", node)),
+    NodeKind::Block(nodes) => leaving(nodes, looped),
+    NodeKind::If(r#if) => leaving_if(r#if, looped),
+    NodeKind::IfLet(if_let) => leaving(&if_let.block, looped)
+      .or_else(|| leaving(if_let.r#else.as_deref().unwrap_or_default(), looped)),
+    NodeKind::For(r#for) => leaving(slice::from_ref(&r#for.block), true),
+    NodeKind::Foreach(foreach) => leaving(&foreach.block, true),
+    NodeKind::While(r#while) => leaving(&r#while.block, true),
+    NodeKind::WhileLet(while_let) => leaving(&while_let.block, true),
+    NodeKind::Switch(switch) => switch
+      .cases
+      .iter()
+      .find_map(|case| leaving(slice::from_ref(&case.block), looped)),
+    NodeKind::Try(r#try) => leaving(&r#try.block, looped)
+      .or_else(|| leaving(&r#try.catch, looped))
+      .or_else(|| leaving(r#try.finally.as_deref().unwrap_or_default(), looped)),
+    _ => None,
+  })
+}
+
+fn leaving_if(r#if: &If, looped: bool) -> Option<(&'static str, &Node)> {
+  leaving(&r#if.block, looped).or_else(|| match &r#if.r#else {
+    Some(Else::Else(block)) => leaving(block, looped),
+    Some(Else::Elif(r#if)) => leaving_if(r#if, looped),
+    None => None,
+  })
+}
+
+/// Lines running the try block, which stops at the first failing command,
+/// its exit code becoming the one of the lines
+fn transpile_block(t: &mut Transpiler, block: &[Node]) -> TranspileResult<Vec<String>> {
+  let (open, option, close) = match t.target() {
+    // the function keeps the variables the block assigns
+    Target::Zsh => ("() {", "setopt local_options err_return", "}"),
+    Target::Bash | Target::Sh => ("(", "set -e", ")"),
+  };
+
+  let mut lines = vec![t.use_indent(open)];
+
+  t.push_block(BlockType::Generic);
+  lines.push(t.use_indent(option));
+  t.pop_block();
+
+  t.push_try();
+  let block = block::transpile_inner(t, block);
+  t.pop_try();
+
+  let block = block?;
+  if !block.is_empty() {
+    lines.push(block);
+  }
+
+  lines.push(t.use_indent(close));
+
+  Ok(lines)
+}
+
+/// Rejects assigning to or exporting the variable `name` declared outside of
+/// a try block that runs in a subshell, which keeps the value to itself.
+/// `what` is "assign to" or "export"
+pub fn check_assignment(t: &Transpiler, what: &str, name: &str, span: Span) -> TranspileResult<()> {
+  let target = match t.target() {
+    Target::Zsh => return Ok(()),
+    Target::Bash => "bash",
+    Target::Sh => "POSIX sh",
+  };
+
+  if t.is_outside_try(name) {
+    return Err(
+      Error::new(
+        &format!("Can't {what} '{name}' from a try block, it runs in a subshell on {target}"),
+        span,
+      )
+      .help(&format!("{what} it after the try block instead")),
+    );
+  }
+
+  Ok(())
+}
+
+/// Lines running the catch block if the try block failed, right after it
+fn transpile_catch(
+  t: &mut Transpiler,
+  error: Option<&String>,
+  catch: &[Node],
+  node: &Node,
+) -> TranspileResult<Vec<String>> {
+  // an empty catch block only ignores the failure
+  if catch.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let mut lines = Vec::new();
+
+  // the exit code only exists in the catch block
+  t.push_scope();
+
+  let code = if let Some(error) = error {
+    t.add_local(error);
+    let name = t.declare(error, node.span);
+
+    let declaration = match t.target() {
+      Target::Zsh => format!("local -i {name}=$?"),
+      Target::Bash => format!("declare -i {name}=$?"),
+      Target::Sh => format!("{name}=$?"),
+    };
+    lines.push(t.use_indent(&declaration));
+
+    format!("\"${{{name}}}\"")
+  } else {
+    "$?".to_owned()
+  };
+
+  let test = match t.target() {
+    Target::Sh => format!("[ {code} -ne 0 ]"),
+    _ => format!("[[ {code} -ne 0 ]]"),
+  };
+  lines.push(t.use_indent(&format!("if {test}; then")));
+  lines.push(block::transpile_inner(t, catch)?);
+  lines.push(t.use_indent("fi"));
+
+  t.pop_scope();
+
+  Ok(lines)
+}
+
+fn check_leaving(t: &Transpiler, r#try: &Try) -> TranspileResult<()> {
+  let (target, body) = match t.target() {
+    Target::Zsh => ("zsh", "a function of its own"),
+    Target::Bash => ("bash", "a subshell"),
+    Target::Sh => ("POSIX sh", "a subshell"),
+  };
+
+  if let Some((keyword, node)) = leaving(&r#try.block, false) {
+    return Err(
+      Error::new(&format!("`{keyword}` can't leave a try block"), node.span)
+        .help(&format!("the try block runs in {body} on {target}")),
+    );
+  }
+
+  // zsh has `always` blocks, which run however the blocks before them end
+  if r#try.finally.is_some() && t.target() != Target::Zsh {
+    if let Some((keyword, node)) = leaving(&r#try.catch, false) {
+      return Err(
+        Error::new(
+          &format!("`{keyword}` can't leave a catch block followed by finally on {target}"),
+          node.span,
+        )
+        .help("the finally block only runs once the catch block ends"),
+      );
+    }
+  }
+
+  Ok(())
+}
+
+pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
+  if let NodeKind::Try(r#try) = &node.kind {
+    let Try {
+      block,
+      error,
+      catch,
+      finally,
+    } = r#try;
+
+    check_leaving(t, r#try)?;
+
+    let finally = finally.as_ref().filter(|finally| !finally.is_empty());
+    let always = finally.is_some() && t.target() == Target::Zsh;
+
+    if always {
+      t.push_block(BlockType::Generic); // additional indent
+    }
+
+    let mut lines = transpile_block(t, block)?;
+    lines.extend(transpile_catch(t, error.as_ref(), catch, node)?);
+
+    if always {
+      t.pop_block();
+
+      lines.insert(0, t.use_indent("{"));
+      lines.push(t.use_indent("} always {"));
+    }
+
+    if let Some(finally) = finally {
+      if always {
+        lines.push(block::transpile_inner(t, finally)?);
+        lines.push(t.use_indent("}"));
+      } else {
+        t.push_scope();
+        lines.push(super::inner(finally, t)?);
+        t.pop_scope();
+      }
+    }
+
+    Ok(lines.join("\n"))
+  } else {
+    Err(Error::invalid(node))
+  }
+}
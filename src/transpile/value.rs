@@ -2,48 +2,199 @@ use std::fmt::Write;
 
 use super::{
   error::{Error, TranspileResult},
-  function_call,
+  float, function_call, quote, r#if, r#try, run,
+  target::Target,
   transpiler::{BlockType, Transpiler},
 };
-use crate::parse::{
-  node::Node,
-  value::{AssignmentOperator, BinaryOperator, Literal, UnaryOperator, Value},
+use crate::{
+  diagnostic::Diagnostic,
+  infer::ValueType,
+  lint::Lint,
+  parse::{
+    interpolation::StringPart,
+    node::{Node, NodeKind},
+    value::{AssignmentOperator, BinaryOperator, Literal, UnaryOperator, Value, ValueKind},
+  },
+  types::Span,
 };
 
-fn transpile_identifier(t: &mut Transpiler, name: &String) -> String {
+fn transpile_identifier(t: &mut Transpiler, name: &str, span: Span) -> TranspileResult<String> {
+  run::check_value(t, name, span)?;
+
+  let scalar = !matches!(
+    t.type_of(span),
+    None | Some(ValueType::Array | ValueType::Map)
+  );
+  let name = t.resolve(name).to_owned();
+
+  Ok(transpile_variable(t, &name, scalar))
+}
+
+/// Expansion of the variable `name`, as the context needs it
+fn transpile_variable(t: &Transpiler, name: &str, scalar: bool) -> String {
   if t.search(&BlockType::Identifier) || t.search(&BlockType::Arithmetics) {
-    name.clone()
+    name.to_owned()
   } else if t.search(&BlockType::Raw) {
     format!("${name}")
   } else if t.search(&BlockType::Foreach) {
-    format!(r#""${{{name}[@]}}""#)
+    match t.target() {
+      Target::Zsh if name == "@" => format!(r#""${{{name}[@]}}""#),
+      _ if name == "@" => format!(r#""${{{name}}}""#),
+      // without arrays, the value gets split into words instead
+      Target::Sh => format!("${{{name}}}"),
+      _ if scalar => format!(r#""${{{name}}}""#),
+      Target::Zsh | Target::Bash => format!(r#""${{{name}[@]}}""#),
+    }
   } else {
     format!(r#""${{{name}}}""#)
   }
 }
 
-fn transpile_literal(t: &mut Transpiler, value: &Literal, node: &Node) -> TranspileResult<String> {
+/// Contents of the double quoted string the parts of an interpolated string
+/// become, or the word they become in a subscript
+fn transpile_interpolation(
+  t: &mut Transpiler,
+  parts: &[StringPart],
+  node: &Node,
+) -> TranspileResult<String> {
+  let raw = t.search(&BlockType::Raw);
+  let mut string = String::new();
+
+  for part in parts {
+    match part {
+      StringPart::Text(text) if raw => string += &quote::subscript(text, t.target()),
+      StringPart::Text(text) => string += &quote::escape(text),
+      StringPart::Variable(name, span) => {
+        run::check_value(t, name, *span)?;
+
+        let r#type = t.type_of(*span);
+        let name = t.resolve(name);
+
+        // arrays are joined with spaces, like the shell does for `$*`
+        if r#type == Some(ValueType::Array) {
+          write!(string, "${{{name}[*]}}").unwrap();
+        } else {
+          write!(string, "${{{name}}}").unwrap();
+        }
+      }
+      StringPart::Expression(value) => {
+        t.push_block(BlockType::Expression);
+        let word = transpile_inner(t, value, node);
+        t.pop_block();
+        let word = word?;
+
+        // quoted words and expansions work the same inside the quotes,
+        // anything else closes them around it
+        match word
+          .strip_prefix('"')
+          .and_then(|word| word.strip_suffix('"'))
+        {
+          Some(inner) if !raw => string += inner,
+          _ if raw || word.starts_with('$') => string += &word,
+          _ => write!(string, "\"{word}\"").unwrap(),
+        }
+      }
+    }
+  }
+
+  Ok(string)
+}
+
+/// Body of a heredoc giving the text of the string `value` to a command, and
+/// whether the shell has to expand it
+pub fn transpile_heredoc(
+  t: &mut Transpiler,
+  value: &Value,
+  node: &Node,
+) -> TranspileResult<(String, bool)> {
+  match &value.kind {
+    ValueKind::Literal(Literal::String(text) | Literal::RawString(text)) => {
+      Ok((text.clone(), false))
+    }
+    ValueKind::Literal(Literal::Interpolated(parts)) => {
+      let mut body = String::new();
+
+      for part in parts {
+        match part {
+          StringPart::Text(text) => body += &quote::escape_heredoc(text),
+          StringPart::Variable(..) => {
+            body += &transpile_interpolation(t, std::slice::from_ref(part), node)?;
+          }
+          // quotes are text in a heredoc, so quoted words are printed by a
+          // command substitution instead
+          StringPart::Expression(_) => {
+            let word = transpile_interpolation(t, std::slice::from_ref(part), node)?;
+
+            if word.contains('"') {
+              write!(body, r#"$(printf '%s' "{word}")"#).unwrap();
+            } else {
+              body += &word;
+            }
+          }
+        }
+      }
+
+      Ok((body, true))
+    }
+    _ => Err(Error::invalid(node)),
+  }
+}
+
+fn transpile_literal(
+  t: &mut Transpiler,
+  value: &Literal,
+  span: Span,
+  node: &Node,
+) -> TranspileResult<String> {
   match value {
     Literal::String(string) => {
       if t.search(&BlockType::Arithmetics) {
-        eprintln!("Warning: String literal inside arithmetic context");
+        t.warn(
+          Diagnostic::warning("String literal inside arithmetic context")
+            .with_span(span)
+            .with_note("the shell evaluates the contents of the string as an expression")
+            .with_lint(Lint::StringArithmetic),
+        );
       }
 
       if t.search(&BlockType::Raw) {
-        Ok(string.clone())
+        Ok(quote::subscript(string, t.target()))
+      } else {
+        Ok(quote::quote(string, t.target()))
+      }
+    }
+    Literal::Interpolated(parts) => {
+      if t.search(&BlockType::Arithmetics) {
+        t.warn(
+          Diagnostic::warning("String literal inside arithmetic context")
+            .with_span(span)
+            .with_note("the shell evaluates the contents of the string as an expression")
+            .with_lint(Lint::StringArithmetic),
+        );
+      }
+
+      let string = transpile_interpolation(t, parts, node)?;
+
+      if t.search(&BlockType::Raw) {
+        Ok(string)
       } else {
         Ok(format!("\"{string}\""))
       }
     }
     Literal::RawString(string) => {
       if t.search(&BlockType::Arithmetics) {
-        eprintln!("Warning: RawString literal inside arithmetic context");
+        t.warn(
+          Diagnostic::warning("RawString literal inside arithmetic context")
+            .with_span(span)
+            .with_note("the shell evaluates the contents of the string as an expression")
+            .with_lint(Lint::StringArithmetic),
+        );
       }
 
       if t.search(&BlockType::Raw) {
-        Ok(string.clone())
+        Ok(quote::subscript(string, t.target()))
       } else {
-        Ok(format!("'{string}'"))
+        Ok(quote::single_quote(string, t.target()))
       }
     }
     Literal::Int(num) => {
@@ -54,7 +205,14 @@ fn transpile_literal(t: &mut Transpiler, value: &Literal, node: &Node) -> Transp
         Ok(format!("\"{num}\""))
       }
     }
-    Literal::Float(num) => Ok(num.to_string()),
+    // `{:?}` keeps the decimal point of whole numbers, so they stay floats
+    Literal::Float(num) => {
+      if t.search(&BlockType::Arithmetics) || t.search(&BlockType::Raw) {
+        Ok(format!("{num:?}"))
+      } else {
+        Ok(format!("\"{num:?}\""))
+      }
+    }
     Literal::Bool(boolean) => Ok(if *boolean {
       "0".to_string()
     } else {
@@ -71,17 +229,31 @@ fn transpile_literal(t: &mut Transpiler, value: &Literal, node: &Node) -> Transp
 
       if matches!(t.get_block(), Some(BlockType::Foreach)) {
         Ok(items)
+      } else if t.target() == Target::Sh {
+        Err(Error::new(
+          "Array literal has no POSIX sh equivalent outside of a for loop",
+          span,
+        ))
       } else {
         Ok(format!("({items})"))
       }
     }
+    Literal::Map(_) if t.target() == Target::Sh => {
+      Err(Error::new("Map literal has no POSIX sh equivalent", span))
+    }
     Literal::Map(map) => {
       // typeset -A assoc=([key1]=value1 [key2]=value2)
 
       let mut transpiled = String::new();
 
       for (key, value) in map {
-        write!(transpiled, "[{key}]={} ", transpile_inner(t, value, node)?).unwrap();
+        write!(
+          transpiled,
+          "[{}]={} ",
+          quote::word(key),
+          transpile_inner(t, value, node)?
+        )
+        .unwrap();
       }
 
       Ok(format!("({transpiled})"))
@@ -93,14 +265,61 @@ fn transpile_unary_expression(
   t: &mut Transpiler,
   operator: &UnaryOperator,
   right: &Value,
+  span: Span,
   node: &Node,
 ) -> TranspileResult<String> {
-  let operator = match operator {
-    UnaryOperator::Not => "!",
-    UnaryOperator::Minus => "-",
-  };
+  if matches!(t.get_block(), Some(BlockType::Arithmetics)) {
+    let operator = match operator {
+      UnaryOperator::Not => "!",
+      UnaryOperator::Minus => "-",
+    };
+
+    return Ok(format!("{operator} {}", transpile_inner(t, right, node)?));
+  }
 
-  Ok(format!("{operator} {}", transpile_inner(t, right, node)?))
+  match operator {
+    UnaryOperator::Not if t.search(&BlockType::Condition) => {
+      Ok(format!("! {}", transpile_inner(t, right, node)?))
+    }
+    UnaryOperator::Not => Err(Error::new(
+      "Operator '!' can only be used in a condition or arithmetic context",
+      span,
+    )),
+    UnaryOperator::Minus => match &right.kind {
+      ValueKind::Literal(Literal::Int(num)) => Ok(format!("\"-{num}\"")),
+      _ if float::is_needed(t, std::slice::from_ref(right)) => {
+        let value = Value::new(
+          ValueKind::UnaryExpression(operator.clone(), Box::new(right.clone())),
+          span,
+        );
+
+        float::transpile(t, &[value], span, node)
+      }
+      _ => {
+        let expansion = transpile_arithmetic(t, &|t| {
+          transpile_unary_expression(t, operator, right, span, node)
+        })?;
+        let value = Value::new(
+          ValueKind::UnaryExpression(operator.clone(), Box::new(right.clone())),
+          span,
+        );
+
+        Ok(float::format(t, &[value], expansion))
+      }
+    },
+  }
+}
+
+/// Transpiles `inner` as an arithmetic expansion, `$(( ... ))`
+fn transpile_arithmetic(
+  t: &mut Transpiler,
+  inner: &dyn Fn(&mut Transpiler) -> TranspileResult<String>,
+) -> TranspileResult<String> {
+  t.push_block(BlockType::Arithmetics);
+  let value = inner(t);
+  t.pop_block();
+
+  Ok(format!("$(( {} ))", value?))
 }
 
 pub fn transpile_binary_operator(operator: &BinaryOperator) -> &str {
@@ -123,67 +342,129 @@ pub fn transpile_binary_operator(operator: &BinaryOperator) -> &str {
   }
 }
 
+/// Operator as written inside `(( ))`, where comparisons use C syntax
+pub fn transpile_arithmetic_operator(operator: &BinaryOperator) -> Option<&str> {
+  match operator {
+    BinaryOperator::Greater => Some(">"),
+    BinaryOperator::GreaterEqual => Some(">="),
+    BinaryOperator::Less => Some("<"),
+    BinaryOperator::LessEqual => Some("<="),
+    BinaryOperator::RegexMatch => None,
+    operator => Some(transpile_binary_operator(operator)),
+  }
+}
+
 fn transpile_binary_expression(
   t: &mut Transpiler,
   left: &Value,
   operator: &BinaryOperator,
   right: &Value,
+  span: Span,
   node: &Node,
 ) -> TranspileResult<String> {
   if matches!(t.get_block(), Some(BlockType::Arithmetics)) {
-    let operator = match operator {
-      BinaryOperator::Add
-      | BinaryOperator::Sub
-      | BinaryOperator::Multiply
-      | BinaryOperator::Divide
-      | BinaryOperator::Modulo
-      | BinaryOperator::Power => transpile_binary_operator(operator),
-      op => unimplemented!("{op:?}"),
+    let Some(operator) = transpile_arithmetic_operator(operator) else {
+      return Err(Error::new(
+        "Regex match has no equivalent in arithmetic context",
+        span,
+      ));
     };
 
+    if t.target() == Target::Sh && operator == "**" {
+      return Err(Error::new(
+        "Power operator '**' has no POSIX sh equivalent",
+        span,
+      ));
+    }
+
     Ok(format!(
       "{} {operator} {}",
       transpile_inner(t, left, node)?,
       transpile_inner(t, right, node)?
     ))
+  } else if operator.is_arithmetic()
+    && (*operator != BinaryOperator::Add || t.search(&BlockType::Condition))
+  {
+    let value = Value::new(
+      ValueKind::BinaryExpression(
+        Box::new(left.clone()),
+        operator.clone(),
+        Box::new(right.clone()),
+      ),
+      span,
+    );
+
+    if float::is_needed(t, std::slice::from_ref(&value)) {
+      return float::transpile(t, &[value], span, node);
+    }
+
+    let expansion = transpile_arithmetic(t, &|t| {
+      transpile_binary_expression(t, left, operator, right, span, node)
+    })?;
+
+    Ok(float::format(t, &[value], expansion))
   } else if t.search(&BlockType::Condition) {
-    let operator = match operator {
-      BinaryOperator::Equal
-      | BinaryOperator::NotEqual
-      | BinaryOperator::Greater
-      | BinaryOperator::GreaterEqual
-      | BinaryOperator::Less
-      | BinaryOperator::LessEqual
-      | BinaryOperator::RegexMatch
-      | BinaryOperator::And
-      | BinaryOperator::Or
-      | BinaryOperator::Modulo
-      | BinaryOperator::Power => transpile_binary_operator(operator),
-      op => unimplemented!("{op:?}"),
-    };
+    if matches!(operator, BinaryOperator::And | BinaryOperator::Or) {
+      let left = transpile_logic_operand(t, left, node)?;
+      let right = transpile_logic_operand(t, right, node)?;
+
+      return Ok(format!(
+        "{left} {} {right}",
+        transpile_binary_operator(operator)
+      ));
+    }
 
-    Ok(format!(
-      "{} {operator} {}",
-      transpile_inner(t, left, node)?,
-      transpile_inner(t, right, node)?
-    ))
-  } else {
-    // string mode
-    match operator {
-      BinaryOperator::Add => "",
-      _ => {
-        return Err(Error::new(
-          &format!("Operator not supported in string mode: {operator:?}"),
-          node,
-        ))
+    check_comparison(left, right, span)?;
+
+    let operator = transpile_binary_operator(operator);
+    let left = transpile_inner(t, left, node)?;
+
+    // bash matches quoted patterns literally, so the regex has to come
+    // from an unquoted expansion
+    let right = match (t.target(), operator, &right.kind) {
+      (Target::Bash, "=~", ValueKind::Identifier(..)) => {
+        t.push_block(BlockType::Raw);
+        let right = transpile_inner(t, right, node)?;
+        t.pop_block();
+        right
       }
+      (Target::Bash, "=~", _) => format!("$(printf '%s' {})", transpile_inner(t, right, node)?),
+      _ => transpile_inner(t, right, node)?,
     };
 
+    Ok(format!("{left} {operator} {right}"))
+  } else if *operator == BinaryOperator::Add {
+    // string mode
     Ok(format!(
       "{}{}",
       transpile_inner(t, left, node)?,
       transpile_inner(t, right, node)?
     ))
+  } else {
+    Err(Error::new(
+      &format!(
+        "Operator '{}' can only be used in a condition or arithmetic context",
+        transpile_arithmetic_operator(operator).unwrap_or("~")
+      ),
+      span,
+    ))
+  }
+}
+
+/// Transpiles an operand of `&&` or `||` inside `[[ ]]`, where arithmetic
+/// has to be compared to zero explicitly
+fn transpile_logic_operand(
+  t: &mut Transpiler,
+  value: &Value,
+  node: &Node,
+) -> TranspileResult<String> {
+  let string = transpile_inner(t, value, node)?;
+
+  match &value.kind {
+    ValueKind::BinaryExpression(_, operator, _) if operator.is_arithmetic() => {
+      Ok(format!("{string} -ne 0"))
+    }
+    _ => Ok(string),
   }
 }
 
@@ -194,14 +475,37 @@ fn transpile_ternary_expression(
   right: &Value,
   node: &Node,
 ) -> TranspileResult<String> {
-  t.push_block(BlockType::Condition);
-  let condition = transpile_inner(t, condition, node)?;
-  t.pop_block();
+  if matches!(t.get_block(), Some(BlockType::Arithmetics)) {
+    return Ok(format!(
+      "{} ? {} : {}",
+      transpile_inner(t, condition, node)?,
+      transpile_inner(t, left, node)?,
+      transpile_inner(t, right, node)?
+    ));
+  }
 
-  let left = transpile_inner(t, left, node)?;
-  let right = transpile_inner(t, right, node)?;
+  let condition = if t.target() == Target::Sh {
+    r#if::transpile_posix_condition(t, condition, node)?
+  } else {
+    t.push_block(BlockType::Condition);
+    let condition = transpile_inner(t, condition, node)?;
+    t.pop_block();
 
-  let transpiled = format!("if [[ {condition} ]]; then; echo {left}; else; echo {right}; fi");
+    format!("[[ {condition} ]]")
+  };
+
+  t.push_block(BlockType::Expression);
+  let words =
+    transpile_inner(t, left, node).and_then(|left| Ok((left, transpile_inner(t, right, node)?)));
+  t.pop_block();
+  let (left, right) = words?;
+
+  let transpiled = match t.target() {
+    Target::Zsh => format!("if {condition}; then; echo {left}; else; echo {right}; fi"),
+    Target::Bash | Target::Sh => {
+      format!("if {condition}; then echo {left}; else echo {right}; fi")
+    }
+  };
 
   if matches!(t.get_block(), Some(BlockType::Generic)) {
     Ok(transpiled)
@@ -210,29 +514,176 @@ fn transpile_ternary_expression(
   }
 }
 
+/// Rejects comparing the result of a condition, which the shell's tests
+/// don't give as a value
+pub fn check_comparison(left: &Value, right: &Value, span: Span) -> TranspileResult<()> {
+  // `!a == b` ends up as `! a == b`, which the shell reads as `!(a == b)`
+  let left = match &left.kind {
+    ValueKind::UnaryExpression(UnaryOperator::Not, value) => value,
+    _ => left,
+  };
+
+  if is_condition(left) || is_condition(right) {
+    return Err(
+      Error::new("Can't compare the result of a condition", span)
+        .help("compare the values on their own and combine them with `&&` or `||`"),
+    );
+  }
+
+  Ok(())
+}
+
+/// Whether `value` is a comparison or a logical expression, rather than an
+/// operand of one
+fn is_condition(value: &Value) -> bool {
+  match &value.kind {
+    ValueKind::BinaryExpression(_, operator, _) => !operator.is_arithmetic(),
+    ValueKind::UnaryExpression(UnaryOperator::Not, _) => true,
+    ValueKind::Parenthesized(value) => is_condition(value),
+    _ => false,
+  }
+}
+
+/// The value of an integer literal, including negative ones like `-1`
+fn int_literal(value: &Value) -> Option<i32> {
+  match &value.kind {
+    ValueKind::Literal(Literal::Int(int)) => Some(*int),
+    ValueKind::UnaryExpression(UnaryOperator::Minus, value) => int_literal(value).map(|int| -int),
+    _ => None,
+  }
+}
+
 fn transpile_member_expression(
   t: &mut Transpiler,
   left: &Value,
   right: &Value,
+  span: Span,
   node: &Node,
 ) -> TranspileResult<String> {
+  if t.target() == Target::Sh {
+    return Err(Error::new("Indexing has no POSIX sh equivalent", span));
+  }
+
+  let left_type = t.type_of(left.span);
+  if let (ValueKind::Identifier(name), Some(r#type)) = (&left.kind, left_type) {
+    if !matches!(r#type, ValueType::Array | ValueType::Map) {
+      return Err(Error::new(
+        &format!("Can't index '{name}' of type {type}"),
+        span,
+      ));
+    }
+  }
+
   t.push_block(BlockType::Identifier);
-  let left = transpile_inner(t, left, node)?;
+  let left_string = transpile_inner(t, left, node)?;
   t.pop_block();
 
   t.push_block(BlockType::Raw);
-  let right = transpile_inner(t, right, node)?;
+  let right_string = transpile_inner(t, right, node)?;
   t.pop_block();
 
-  let transpiled = format!("{left}[{right}]");
+  let is_map = left_type == Some(ValueType::Map);
+
+  // AlphaShell indexes arrays from 1 like zsh does, bash indexes from 0.
+  // Negative indexes count from the end in both, so they stay as they are
+  let right = match int_literal(right) {
+    _ if is_map => right_string,
+    Some(index) if t.target() == Target::Bash && index >= 0 => (index - 1).to_string(),
+    Some(index) => index.to_string(),
+    None if t.target() == Target::Bash => {
+      format!("{right_string} < 0 ? {right_string} : {right_string} - 1")
+    }
+    None => right_string,
+  };
+
+  let transpiled = format!("{left_string}[{right}]");
 
   if matches!(
     t.get_block(),
     Some(BlockType::Generic | BlockType::Identifier)
   ) {
     Ok(transpiled)
-  } else {
+  } else if t.search(&BlockType::Arithmetics) || t.search(&BlockType::Raw) {
     Ok(format!("${{{transpiled}}}"))
+  } else {
+    // quoted like variables, so the element stays one word
+    Ok(format!("\"${{{transpiled}}}\""))
+  }
+}
+
+/// `result.code` and the other fields of a `run()` result, which are
+/// variables of their own
+fn transpile_field(
+  t: &mut Transpiler,
+  value: &Value,
+  field: &str,
+  span: Span,
+) -> TranspileResult<String> {
+  let ValueKind::Identifier(name) = &value.kind else {
+    return Err(Error::new(
+      "Only variables holding the result of run() have fields",
+      span,
+    ));
+  };
+
+  if t.type_of(value.span) != Some(ValueType::Result) {
+    return Err(Error::new(
+      &format!("'{name}' has no fields, only the result of run() has"),
+      span,
+    ));
+  }
+
+  if !run::FIELDS.contains(&field) {
+    return Err(
+      Error::new(&format!("The result of run() has no field '{field}'"), span)
+        .help("its fields are `code`, `stdout` and `stderr`"),
+    );
+  }
+
+  let name = run::variable(t.resolve(name), field);
+
+  Ok(transpile_variable(t, &name, true))
+}
+
+/// Ints are declared with their type, which the shell keeps for every value
+/// assigned after, and floats have to stay numbers for arithmetic. Results
+/// are made of a variable per field, so they can't be assigned at all
+fn check_type(
+  name: &str,
+  left: Option<ValueType>,
+  right: Option<ValueType>,
+  span: Span,
+) -> TranspileResult<()> {
+  if left == Some(ValueType::Result) {
+    return Err(
+      Error::new(
+        &format!("Can't assign to '{name}', it holds the result of run()"),
+        span,
+      )
+      .help("declare a new variable for the value instead"),
+    );
+  }
+
+  let (Some(left), Some(right)) = (left, right) else {
+    return Ok(());
+  };
+
+  let stable = match left {
+    ValueType::Int => right == ValueType::Int,
+    ValueType::Float => matches!(right, ValueType::Int | ValueType::Float),
+    _ => true,
+  };
+
+  if stable {
+    Ok(())
+  } else {
+    Err(
+      Error::new(
+        &format!("Can't assign a {right} to '{name}' of type {left}"),
+        span,
+      )
+      .help(&format!("declare a new variable for the {right} instead")),
+    )
   }
 }
 
@@ -241,10 +692,65 @@ fn transpile_assignment(
   left: &Value,
   operator: &AssignmentOperator,
   right: &Value,
+  span: Span,
   node: &Node,
 ) -> TranspileResult<String> {
-  if !matches!(left, Value::Identifier(..) | Value::MemberExpression(..)) {
-    return Err(Error::new("Cannot assign to this expression", node));
+  if !matches!(
+    left.kind,
+    ValueKind::Identifier(..) | ValueKind::MemberExpression(..)
+  ) {
+    return Err(Error::new("Cannot assign to this expression", left.span));
+  }
+
+  let left_type = t.type_of(left.span);
+
+  // the variable an element is assigned to counts as assigned too
+  let variable = match &left.kind {
+    ValueKind::MemberExpression(variable, _) => variable,
+    _ => left,
+  };
+  if let ValueKind::Identifier(name) = &variable.kind {
+    r#try::check_assignment(t, "assign to", name, span)?;
+  }
+
+  if let ValueKind::Identifier(name) = &left.kind {
+    if t.target() == Target::Sh && t.in_function() && !t.is_local(name) {
+      return Err(Error::new(
+        &format!(
+          "Can't assign to '{name}' from a function body, it runs in a subshell in POSIX sh"
+        ),
+        span,
+      ));
+    }
+
+    check_type(name, left_type, t.type_of(right.span), span)?;
+
+    if left_type == Some(ValueType::Map) && *operator != AssignmentOperator::Assignment {
+      return Err(Error::new(
+        &format!("Can't add to '{name}' of type map, only assign to it"),
+        span,
+      ));
+    }
+  }
+
+  // ints are always added to, never appended to as strings
+  let arithmetic = left_type == Some(ValueType::Int) && *operator != AssignmentOperator::Assignment;
+
+  if arithmetic && !matches!(t.get_block(), Some(BlockType::Arithmetics)) {
+    t.push_block(BlockType::Arithmetics);
+    let expression = transpile_assignment(t, left, operator, right, span, node);
+    t.pop_block();
+
+    let statement = match t.target() {
+      Target::Zsh | Target::Bash => format!("(( {} ))", expression?),
+      Target::Sh => format!(": $(( {} ))", expression?),
+    };
+
+    t.pop_block();
+    let string = t.use_indent(&statement);
+    t.push_block(BlockType::Expression);
+
+    return Ok(string);
   }
 
   if matches!(t.get_block(), Some(BlockType::Arithmetics)) {
@@ -258,6 +764,13 @@ fn transpile_assignment(
       AssignmentOperator::PowerAssignment => "**=",
     };
 
+    if t.target() == Target::Sh && operator == "**=" {
+      return Err(Error::new(
+        "Power operator '**=' has no POSIX sh equivalent",
+        span,
+      ));
+    }
+
     Ok(format!(
       "{} {operator} {}",
       transpile_inner(t, left, node)?,
@@ -271,16 +784,33 @@ fn transpile_assignment(
       _ => {
         return Err(Error::new(
           &format!("Operator not supported in string mode: {operator:?}"),
-          node,
+          span,
         ))
       }
     };
 
+    let is_array = left_type == Some(ValueType::Array);
+
     t.push_block(BlockType::Identifier);
     let left = transpile_inner(t, left, node)?;
     t.pop_block();
 
-    let right = transpile_inner(t, right, node)?;
+    let copy = is_array && t.type_of(right.span) == Some(ValueType::Array);
+    let right = transpile_argument(t, right, node)?;
+
+    // bash would append to the first element instead of the array
+    let right = if copy || (t.target() == Target::Bash && is_array && operator == "+=") {
+      format!("({right})")
+    } else {
+      right
+    };
+
+    // POSIX sh has no `+=`, the value is put after the old one instead
+    let (operator, right) = match right.strip_prefix('"') {
+      _ if t.target() != Target::Sh || operator != "+=" => (operator, right),
+      Some(rest) => ("=", format!("\"${{{left}}}{rest}")),
+      None => ("=", format!("\"${{{left}}}\"{right}")),
+    };
 
     t.pop_block();
     let string = t.use_indent(&format!("{left}{operator}{right}"));
@@ -306,25 +836,46 @@ pub fn transpile_inner(t: &mut Transpiler, value: &Value, node: &Node) -> Transp
     t.push_block(BlockType::Expression);
   }
 
-  let value = match value {
-    Value::Literal(value) => transpile_literal(t, value, node),
-    Value::Identifier(name) => Ok(transpile_identifier(t, name)),
-    Value::UnaryExpression(operator, right) => transpile_unary_expression(t, operator, right, node),
-    Value::BinaryExpression(left, operator, right) => {
-      transpile_binary_expression(t, left, operator, right, node)
+  let span = value.span;
+
+  let value = match &value.kind {
+    ValueKind::Literal(value) => transpile_literal(t, value, span, node),
+    ValueKind::Identifier(name) => transpile_identifier(t, name, span),
+    ValueKind::UnaryExpression(operator, right) => {
+      transpile_unary_expression(t, operator, right, span, node)
+    }
+    ValueKind::BinaryExpression(left, operator, right) => {
+      transpile_binary_expression(t, left, operator, right, span, node)
     }
-    Value::TernaryExpression(condition, left, right) => {
+    ValueKind::TernaryExpression(condition, left, right) => {
       transpile_ternary_expression(t, condition, left, right, node)
     }
-    Value::MemberExpression(left, right) => transpile_member_expression(t, left, right, node),
-    Value::Assignment(left, operator, right) => {
-      transpile_assignment(t, left, operator, right, node)
+    ValueKind::MemberExpression(left, right) => {
+      transpile_member_expression(t, left, right, span, node)
     }
-    Value::FunctionCall(function_call) => function_call::transpile_inner(t, function_call, node),
-    Value::Parenthesized(value) => Ok(format!(
-      "({value})",
-      value = transpile_inner(t, value, node)?
-    )),
+    ValueKind::Assignment(left, operator, right) => {
+      transpile_assignment(t, left, operator, right, span, node)
+    }
+    ValueKind::FunctionCall(function_call) => {
+      function_call::transpile_inner(t, function_call, node)
+    }
+    // grouping only exists in arithmetic and around conditions, a word is
+    // already evaluated on its own
+    ValueKind::Parenthesized(value)
+      if matches!(t.get_block(), Some(BlockType::Arithmetics))
+        || (matches!(t.get_block(), Some(BlockType::Condition)) && is_condition(value)) =>
+    {
+      Ok(format!("({})", transpile_inner(t, value, node)?))
+    }
+    ValueKind::Parenthesized(value) => transpile_inner(t, value, node),
+    ValueKind::NamedArgument(name, value) => {
+      Ok(format!("--{name}={}", transpile_inner(t, value, node)?))
+    }
+    ValueKind::Run(_) => Err(
+      Error::new("run() can only be the value of a let declaration", span)
+        .help("declare its result first, like `let result = run(cmd());`"),
+    ),
+    ValueKind::Field(value, field) => transpile_field(t, value, field, span),
   };
 
   if indent {
@@ -334,8 +885,25 @@ pub fn transpile_inner(t: &mut Transpiler, value: &Value, node: &Node) -> Transp
   value
 }
 
+/// Transpiles `value` as a whole argument of a command or the value of a
+/// declaration, where an array variable expands to all of its items
+pub fn transpile_argument(
+  t: &mut Transpiler,
+  value: &Value,
+  node: &Node,
+) -> TranspileResult<String> {
+  match &value.kind {
+    ValueKind::Identifier(name) if t.type_of(value.span) == Some(ValueType::Array) => {
+      run::check_value(t, name, value.span)?;
+
+      Ok(format!(r#""${{{}[@]}}""#, t.resolve(name)))
+    }
+    _ => transpile_inner(t, value, node),
+  }
+}
+
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::Value(value) = node {
+  if let NodeKind::Value(value) = &node.kind {
     transpile_inner(t, value, node)
   } else {
     Err(Error::invalid(node))
@@ -3,16 +3,17 @@ use super::{
   error::{Error, TranspileResult},
   function_call,
   r#if::transpile_condition,
+  target::Target,
   transpiler::{BlockType, Transpiler},
   utils::random_string,
 };
 use crate::parse::{
-  node::Node,
+  node::{Node, NodeKind},
   r#while::{While, WhileLet},
 };
 
 pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::While(While { condition, block }) = node {
+  if let NodeKind::While(While { condition, block }) = &node.kind {
     let condition = transpile_condition(t, condition, node)?;
     let head = t.use_indent(&format!("while {condition}; do"));
     let block = block::transpile_inner(t, block)?;
@@ -27,21 +28,37 @@ pub fn transpile(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
 }
 
 pub fn transpile_let(t: &mut Transpiler, node: &Node) -> TranspileResult<String> {
-  if let Node::WhileLet(WhileLet {
+  if let NodeKind::WhileLet(WhileLet {
     name,
     call: value,
     block,
-  }) = node
+  }) = &node.kind
   {
     t.push_block(BlockType::FunctionCall);
     let call = function_call::transpile_inner(t, value, node)?;
     t.pop_block();
 
+    // the variable only exists in the loop
+    t.push_scope();
+    t.add_local(name);
+    let name = t.declare(name, node.span);
+
+    // without `local`, there is nothing to gain from the wrapper function
+    if t.target() == Target::Sh {
+      let head = t.use_indent(&format!("while {name}=$({call}); do"));
+      let block = block::transpile_inner(t, block)?;
+      let end = t.use_indent("done");
+      t.pop_scope();
+
+      return Ok(format!("{head}\n{block}\n{end}"));
+    }
+
     let temp_name = format!("__tmp_{}", random_string(6));
 
     t.push_block(BlockType::Generic); // additional indent
     let block = block::transpile_inner(t, block)?;
     t.pop_block();
+    t.pop_scope();
 
     let output = format!(
       "
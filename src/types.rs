@@ -1,6 +1,6 @@
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
 pub struct Position(pub usize, pub usize);
 
 impl fmt::Display for Position {
@@ -10,27 +10,72 @@ impl fmt::Display for Position {
   }
 }
 
+/// Region of the source code, `end` being exclusive
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct Span {
+  pub start: Position,
+  pub end: Position,
+}
+
+impl Span {
+  pub fn new(start: Position, end: Position) -> Self {
+    Self { start, end }
+  }
+}
+
+impl fmt::Display for Span {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let Position(line, column) = self.start;
+    let (line, column) = (line + 1, column + 1); // account for zero indexing
+
+    write!(f, "{line}:{column}")
+  }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
   pub r#type: TokenType,
-  pub position: Position,
+  pub span: Span,
 }
 
 impl Token {
   pub fn new(r#type: TokenType, position: Position) -> Self {
-    Self { r#type, position }
+    Self {
+      r#type,
+      span: Span::new(position, position),
+    }
   }
 }
 
-pub type TT = TokenType;
+/// Comment skipped by the lexer, kept for tools that print the source back
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+  /// Full text, including the `//` or `/* */`
+  pub text: String,
+  pub span: Span,
+}
 
+/// Piece of a double quoted string with interpolation
 #[derive(Debug, Clone, PartialEq)]
+pub enum StringToken {
+  Text(String),
+  /// `$name` or `${name}`, the span covering the dollar sign and braces
+  Variable(String, Span),
+  /// `${...}` around anything but a name
+  Expression(Vec<Token>),
+}
+
+pub type TT = TokenType;
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum TokenType {
   Integer(i32),
   Float(f32),
   Boolean(bool),
 
   String(String),
+  /// String with at least one `$name` or `${...}` in it
+  InterpolatedString(Vec<StringToken>),
   RawString(String),
   Identifier(String),
 
@@ -54,6 +99,10 @@ pub enum TokenType {
   Break,
   Switch,
   Case,
+  Try,
+  Catch,
+  Finally,
+  Defer,
 
   // Operators
   Assignment,
@@ -116,6 +165,7 @@ pub enum TokenType {
   LBracket,
   RBracket,
 
+  #[default]
   Invalid,
 }
 
@@ -126,9 +176,186 @@ impl std::fmt::Display for Token {
   }
 }
 
-impl Default for TokenType {
-  fn default() -> Self {
-    Self::Invalid
+impl TokenType {
+  /// One token of each type, the ones carrying a value with an empty one
+  const ALL: [TokenType; 69] = [
+    TT::Integer(0),
+    TT::Float(0.0),
+    TT::Boolean(false),
+    TT::String(String::new()),
+    TT::InterpolatedString(Vec::new()),
+    TT::RawString(String::new()),
+    TT::Identifier(String::new()),
+    TT::Flag(String::new()),
+    TT::Export,
+    TT::Let,
+    TT::External,
+    TT::Import,
+    TT::Source,
+    TT::Function,
+    TT::Return,
+    TT::If,
+    TT::Elif,
+    TT::Else,
+    TT::For,
+    TT::In,
+    TT::While,
+    TT::Continue,
+    TT::Break,
+    TT::Switch,
+    TT::Case,
+    TT::Try,
+    TT::Catch,
+    TT::Finally,
+    TT::Defer,
+    TT::Assignment,
+    TT::Add,
+    TT::Sub,
+    TT::Multiply,
+    TT::Divide,
+    TT::Modulo,
+    TT::Power,
+    TT::AddAssignment,
+    TT::SubAssignment,
+    TT::MultiplyAssignment,
+    TT::DivideAssignment,
+    TT::ModuloAssignment,
+    TT::PowerAssignment,
+    TT::And,
+    TT::Or,
+    TT::Not,
+    TT::Equal,
+    TT::Less,
+    TT::Greater,
+    TT::LessEqual,
+    TT::GreaterEqual,
+    TT::NotEqual,
+    TT::Pipe,
+    TT::Daemon,
+    TT::RegexMatch,
+    TT::Question,
+    TT::Colon,
+    TT::Dollar,
+    TT::At,
+    TT::Dot,
+    TT::Range,
+    TT::Comma,
+    TT::Semicolon,
+    TT::LParen,
+    TT::RParen,
+    TT::LBrace,
+    TT::RBrace,
+    TT::LBracket,
+    TT::RBracket,
+    TT::Invalid,
+  ];
+
+  /// How the token is written in the source, or what it is for the tokens
+  /// carrying a value
+  pub fn spelling(&self) -> &'static str {
+    match self {
+      TT::Integer(_) => "integer",
+      TT::Float(_) => "float",
+      TT::Boolean(_) => "boolean",
+      TT::String(_) | TT::InterpolatedString(_) => "string",
+      TT::RawString(_) => "raw string",
+      TT::Identifier(_) => "identifier",
+      TT::Flag(_) => "flag",
+      TT::Export => "export",
+      TT::Let => "let",
+      TT::External => "external",
+      TT::Import => "import",
+      TT::Source => "source",
+      TT::Function => "fn",
+      TT::Return => "return",
+      TT::If => "if",
+      TT::Elif => "elif",
+      TT::Else => "else",
+      TT::For => "for",
+      TT::In => "in",
+      TT::While => "while",
+      TT::Continue => "continue",
+      TT::Break => "break",
+      TT::Switch => "switch",
+      TT::Case => "case",
+      TT::Try => "try",
+      TT::Catch => "catch",
+      TT::Finally => "finally",
+      TT::Defer => "defer",
+      TT::Assignment => "=",
+      TT::Add => "+",
+      TT::Sub => "-",
+      TT::Multiply => "*",
+      TT::Divide => "/",
+      TT::Modulo => "%",
+      TT::Power => "**",
+      TT::AddAssignment => "+=",
+      TT::SubAssignment => "-=",
+      TT::MultiplyAssignment => "*=",
+      TT::DivideAssignment => "/=",
+      TT::ModuloAssignment => "%=",
+      TT::PowerAssignment => "**=",
+      TT::And => "&&",
+      TT::Or => "||",
+      TT::Not => "!",
+      TT::Equal => "==",
+      TT::Less => "<",
+      TT::Greater => ">",
+      TT::LessEqual => "<=",
+      TT::GreaterEqual => ">=",
+      TT::NotEqual => "!=",
+      TT::Pipe => "|",
+      TT::Daemon => "&",
+      TT::RegexMatch => "~",
+      TT::Question => "?",
+      TT::Colon => ":",
+      TT::Dollar => "$",
+      TT::At => "@",
+      TT::Dot => ".",
+      TT::Range => "..",
+      TT::Comma => ",",
+      TT::Semicolon => ";",
+      TT::LParen => "(",
+      TT::RParen => ")",
+      TT::LBrace => "{",
+      TT::RBrace => "}",
+      TT::LBracket => "[",
+      TT::RBracket => "]",
+      TT::Invalid => "invalid token",
+    }
+  }
+
+  /// What a parser error says it expected, code in backticks
+  pub fn describe(&self) -> String {
+    match self {
+      TT::Integer(_)
+      | TT::Float(_)
+      | TT::Boolean(_)
+      | TT::String(_)
+      | TT::InterpolatedString(_)
+      | TT::RawString(_)
+      | TT::Identifier(_)
+      | TT::Flag(_)
+      | TT::Invalid => self.spelling().to_owned(),
+      _ => format!("`{}`", self.spelling()),
+    }
+  }
+
+  /// Readable list like `` identifier or `)` `` of the token types for which
+  /// `matches` is true
+  pub fn describe_matching(matches: impl Fn(&TokenType) -> bool) -> String {
+    let mut names: Vec<_> = Self::ALL
+      .iter()
+      .filter(|token| matches(token))
+      .map(TokenType::describe)
+      .collect();
+    names.dedup();
+
+    match names.split_last() {
+      Some((last, [])) => last.clone(),
+      Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+      None => String::new(),
+    }
   }
 }
 
@@ -142,8 +369,7 @@ impl std::fmt::Display for TokenType {
       | TokenType::RawString(x)
       | TokenType::Identifier(x)
       | TokenType::Flag(x) => write!(f, "{x}"),
-
-      _ => write!(f, "{self:?}"),
+      _ => write!(f, "{}", self.spelling()),
     }
   }
 }
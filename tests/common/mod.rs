@@ -0,0 +1,139 @@
+// each test binary only uses some of the helpers
+#![allow(dead_code)]
+
+use std::{
+  env, fs,
+  io::Write,
+  path::Path,
+  process::{Command, Output, Stdio},
+};
+
+use regex::Regex;
+
+pub fn ash(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_ash"))
+    .args(args)
+    .output()
+    .expect("failed to run ash")
+}
+
+/// Transpiles `source` for `target`, passing it through stdin
+pub fn transpile_source(target: &str, source: &str) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_ash"))
+    .args(["--quiet", "--target", target, "--output", "-", "-"])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .expect("failed to run ash");
+
+  child
+    .stdin
+    .take()
+    .unwrap()
+    .write_all(source.as_bytes())
+    .unwrap();
+
+  child.wait_with_output().unwrap()
+}
+
+/// Transpiles `file` for `target` and returns the generated code with the
+/// random names of helper functions replaced by a stable placeholder.
+pub fn transpile(target: &str, file: &str) -> String {
+  let output = ash(&["--quiet", "--target", target, "--output", "-", file]);
+
+  assert!(
+    output.status.success(),
+    "ash failed on '{file}':\n{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+
+  let code = String::from_utf8(output.stdout).unwrap();
+
+  Regex::new("__tmp_[[:alnum:]]{6}")
+    .unwrap()
+    .replace_all(&code, "__tmp_XXXXXX")
+    .into_owned()
+}
+
+/// Compares `actual` against `tests/snapshots/{name}`.
+///
+/// Set `UPDATE_SNAPSHOTS=1` to overwrite the stored snapshot instead.
+pub fn assert_snapshot(name: &str, actual: &str) {
+  let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("tests/snapshots")
+    .join(name);
+
+  if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+    fs::write(&path, actual).unwrap();
+    return;
+  }
+
+  let expected = fs::read_to_string(&path)
+    .unwrap_or_else(|e| panic!("Couldn't read snapshot '{}', error: '{e}'", path.display()));
+
+  assert_eq!(expected, actual, "snapshot '{name}' doesn't match");
+}
+
+/// Whether `command` is on the PATH. Set `REQUIRE_SHELLS=1` to fail instead
+/// of returning false, so CI can't skip the tests needing it
+pub fn installed(command: &str) -> bool {
+  let found = Command::new("sh")
+    .arg("-c")
+    .arg(format!("command -v {command}"))
+    .output()
+    .is_ok_and(|output| output.status.success());
+
+  assert!(
+    found || env::var_os("REQUIRE_SHELLS").is_none(),
+    "{command} isn't installed"
+  );
+  if !found {
+    eprintln!("skipping {command}, it isn't installed");
+  }
+
+  found
+}
+
+/// Runs `code` with `shell -c`, checking that it succeeds, and returns what
+/// it printed
+pub fn run(shell: &str, code: &str) -> String {
+  let output = Command::new(shell)
+    .arg("-c")
+    .arg(code)
+    .output()
+    .unwrap_or_else(|e| panic!("Couldn't run {shell}, error: '{e}'"));
+
+  assert!(
+    output.status.success(),
+    "{shell} failed:\n{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+
+  String::from_utf8(output.stdout).unwrap()
+}
+
+/// Transpiles `file` for each of `targets` and runs it in the shell of the
+/// same name, comparing what it prints against `tests/snapshots/{snapshot}`.
+/// zsh is skipped where it isn't installed
+pub fn assert_runs(targets: &[&str], file: &str, snapshot: &str) {
+  for target in targets {
+    if *target == "zsh" && !installed("zsh") {
+      continue;
+    }
+
+    assert_snapshot(snapshot, &run(target, &transpile(target, file)));
+  }
+}
+
+/// Transpiles `source` for `target`, checking that it fails with `error`, and
+/// returns everything ash printed to stderr
+pub fn assert_fails(target: &str, source: &str, error: &str) -> String {
+  let output = transpile_source(target, source);
+  let stderr = String::from_utf8(output.stderr).unwrap();
+
+  assert!(!output.status.success(), "ash accepted:\n{source}");
+  assert!(stderr.contains(error), "'{error}' isn't in:\n{stderr}");
+
+  stderr
+}
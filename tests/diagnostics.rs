@@ -0,0 +1,343 @@
+mod common;
+
+use common::{ash, assert_fails, assert_snapshot, transpile_source};
+
+fn stderr(file: &str) -> String {
+  let output = ash(&["--quiet", "--output", "-", file]);
+
+  String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn undefined_variable() {
+  assert_snapshot("undefined.stderr", &stderr("test/undefined.ash"));
+}
+
+#[test]
+fn assignment_to_undeclared_variable() {
+  assert_fails(
+    "zsh",
+    "total = 1;\n",
+    "error: Assignment to undeclared variable total",
+  );
+}
+
+#[test]
+fn indexing_a_string() {
+  assert_fails(
+    "zsh",
+    "let name = \"ash\";\necho(name[1]);\n",
+    "error: Can't index 'name' of type string",
+  );
+}
+
+#[test]
+fn adding_to_a_map() {
+  assert_fails(
+    "bash",
+    "let ages = { alice: \"30\" };\nages += \"x\";\n",
+    "error: Can't add to 'ages' of type map, only assign to it",
+  );
+}
+
+#[test]
+fn float_arithmetic_needs_a_backend() {
+  assert_fails(
+    "bash",
+    "echo($(1.5 * 2));\n",
+    "error: Float arithmetic has no bash equivalent",
+  );
+}
+
+#[test]
+fn undefined_variable_in_string() {
+  assert_fails(
+    "zsh",
+    "echo(\"hi ${nobody}\");\n",
+    "error: Undefined variable nobody",
+  );
+}
+
+#[test]
+fn unknown_escape_sequence() {
+  assert_fails(
+    "zsh",
+    "echo(\"a\\qb\");\n",
+    "error: Unknown escape sequence '\\q'",
+  );
+}
+
+#[test]
+fn invalid_unicode_escape() {
+  assert_fails(
+    "zsh",
+    "echo(\"\\u{D800}\");\n",
+    "error: 'D800' isn't a unicode character",
+  );
+}
+
+#[test]
+fn unclosed_multiline_string() {
+  assert_fails(
+    "zsh",
+    "let text = \"\"\"\n  text\n",
+    "error: No matching '\"\"\"' found",
+  );
+}
+
+#[test]
+fn redirect_to_a_number() {
+  assert_fails(
+    "zsh",
+    "echo(\"hi\") | > 5;\n",
+    "expected string, raw string or identifier",
+  );
+}
+
+#[test]
+fn unknown_field_of_a_result() {
+  assert_fails(
+    "zsh",
+    "let r = run(ls());\necho(r.status);\n",
+    "error: The result of run() has no field 'status'",
+  );
+}
+
+#[test]
+fn run_outside_of_a_declaration() {
+  assert_fails(
+    "zsh",
+    "echo(run(ls()));\n",
+    "error: run() can only be the value of a let declaration",
+  );
+}
+
+#[test]
+fn result_used_as_a_value() {
+  assert_fails(
+    "zsh",
+    "let r = run(ls());\necho(r);\n",
+    "error: 'r' holds the result of run(), only its fields have a value",
+  );
+  assert_fails(
+    "bash",
+    "let r = run(ls());\nlet s = r;\necho(s.code);\n",
+    "error: 'r' holds the result of run(), only its fields have a value",
+  );
+  assert_fails(
+    "bash",
+    "let r = run(ls());\necho(\"${r}\");\n",
+    "error: 'r' holds the result of run(), only its fields have a value",
+  );
+  assert_fails(
+    "sh",
+    "let r = run(ls());\nr = \"x\";\n",
+    "error: Can't assign to 'r', it holds the result of run()",
+  );
+}
+
+#[test]
+fn return_out_of_a_try_block() {
+  assert_fails(
+    "bash",
+    "fn f() {\n  try {\n    return 1;\n  } catch {}\n}\n",
+    "error: `return` can't leave a try block",
+  );
+}
+
+#[test]
+fn assignment_from_a_try_block() {
+  assert_fails(
+    "bash",
+    "let x = \"a\";\ntry {\n  x = \"b\";\n} catch {}\n",
+    "error: Can't assign to 'x' from a try block, it runs in a subshell on bash",
+  );
+  assert_fails(
+    "sh",
+    "try {\n  export X = \"b\";\n} catch {}\n",
+    "error: Can't export 'X' from a try block, it runs in a subshell on POSIX sh",
+  );
+}
+
+#[test]
+fn changing_the_shell_from_a_try_block() {
+  assert_fails(
+    "bash",
+    "try {\n  exit(3);\n} catch e {\n  echo(\"caught ${e}\");\n}\n",
+    "error: Can't use `exit` in a try block, it runs in a subshell on bash",
+  );
+  assert_fails(
+    "sh",
+    "try {\n  cd(\"/tmp\");\n} catch {}\n",
+    "error: Can't use `cd` in a try block, it runs in a subshell on POSIX sh",
+  );
+  assert_fails(
+    "bash",
+    "try {\n  trap(\"\", \"INT\");\n} catch {}\n",
+    "error: Can't use `trap` in a try block, it runs in a subshell on bash",
+  );
+  assert_fails(
+    "sh",
+    "try {\n  source \"env.sh\";\n} catch {}\n",
+    "error: Can't use `source` in a try block, it runs in a subshell on POSIX sh",
+  );
+}
+
+#[test]
+fn defer_in_a_nested_block() {
+  assert_fails(
+    "zsh",
+    "if true {\n  defer echo(\"done\");\n}\n",
+    "error: defer can only be used in a function body or at the top level",
+  );
+}
+
+#[test]
+fn signal_with_its_prefix() {
+  let stderr = assert_fails(
+    "zsh",
+    "on signal(\"SIGINT\") {\n  echo(\"stop\");\n}\n",
+    "error: Unknown signal 'SIGINT'",
+  );
+  assert!(stderr.contains("signals are named without the SIG prefix, like \"INT\""));
+}
+
+#[test]
+fn comparing_a_condition() {
+  assert_fails(
+    "bash",
+    "let a = 1;\nif a > 1 > 0 {\n  echo(\"yes\");\n}\n",
+    "error: Can't compare the result of a condition",
+  );
+  assert_fails(
+    "sh",
+    "let a = 1;\nif a == !a {\n  echo(\"yes\");\n}\n",
+    "error: Can't compare the result of a condition",
+  );
+}
+
+#[test]
+fn string_in_arithmetic_context() {
+  assert_snapshot(
+    "string_arithmetic.stderr",
+    &stderr("test/string_arithmetic.ash"),
+  );
+}
+
+#[test]
+fn shadowed_variables() {
+  assert_snapshot("scope.stderr", &stderr("test/scope.ash"));
+}
+
+#[test]
+fn reports_every_parse_error() {
+  let output = ash(&["--quiet", "--output", "-", "test/errors.ash"]);
+
+  assert!(!output.status.success());
+  assert!(output.stdout.is_empty());
+  assert_snapshot("errors.stderr", &String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn checks_calls_against_declarations() {
+  assert_snapshot("params_errors.stderr", &stderr("test/params_errors.ash"));
+}
+
+#[test]
+fn strict_mode_rejects_unknown_commands() {
+  let output = ash(&["--strict", "--quiet", "--output", "-", "test/strict.ash"]);
+  assert_snapshot("strict.stderr", &String::from_utf8(output.stderr).unwrap());
+
+  // arity is checked either way
+  let stderr = stderr("test/strict.ash");
+  assert!(!stderr.contains("Unknown command"));
+  assert!(stderr.contains("Function later takes 1 argument but 2 were given"));
+}
+
+#[test]
+fn calls_before_the_declaration() {
+  assert_fails(
+    "bash",
+    "later(\"x\");\nfn later(a) { echo(a); }\n",
+    "error: Function later is called before it's declared",
+  );
+
+  // function bodies and deferred blocks only run once it's declared
+  let source =
+    "fn main() { later(\"x\"); }\ndefer later(\"y\");\nfn later(a) { echo(a); }\nmain();\n";
+  assert!(transpile_source("bash", source).status.success());
+}
+
+#[test]
+fn spreading_arguments_into_a_call() {
+  let source = "fn pair(a, b) { echo(a, b); }\nfn both() { pair(@); }\nlet items = [\"x\", \"y\"];\npair(items);\n";
+  assert!(transpile_source("bash", source).status.success());
+
+  assert_fails(
+    "bash",
+    "fn pair(a, b) { echo(a, b); }\nlet items = \"x\";\npair(items);\n",
+    "error: Function pair takes 2 arguments but 1 was given",
+  );
+}
+
+#[test]
+fn lints() {
+  assert_snapshot("lints.stderr", &stderr("test/lints.ash"));
+}
+
+#[test]
+fn lints_can_be_allowed_or_denied() {
+  let output = ash(&[
+    "--quiet",
+    "--output",
+    "-",
+    "-A",
+    "unused-variable",
+    "-W",
+    "reassigned-export",
+    "test/lints.ash",
+  ]);
+  let stderr = String::from_utf8(output.stderr).unwrap();
+
+  assert!(!output.status.success());
+  assert!(output.stdout.is_empty());
+  assert!(stderr.contains("error[reassigned-export]: Exported variable PREFIX is reassigned"));
+  assert!(!stderr.contains("unused-variable"));
+}
+
+#[test]
+fn exit_from_a_posix_sh_function() {
+  assert_fails(
+    "sh",
+    "fn die() { exit(1); }\ndie();\n",
+    "error: Can't use `exit` in a function body, it runs in a subshell in POSIX sh",
+  );
+}
+
+#[test]
+fn tokens_are_spelled_as_in_the_source() {
+  assert_fails(
+    "zsh",
+    "echo(1)\n",
+    "error: Unexpected end of input after `)`",
+  );
+}
+
+#[test]
+fn changing_the_type_of_a_variable() {
+  assert_fails(
+    "bash",
+    "let n = 1;\nn = \"hello\";\n",
+    "error: Can't assign a string to 'n' of type int",
+  );
+  assert_fails(
+    "zsh",
+    "let n = 1;\nn = 1.5;\n",
+    "error: Can't assign a float to 'n' of type int",
+  );
+  assert_fails(
+    "zsh",
+    "let f = 1.5;\nf += \"x\";\n",
+    "error: Can't assign a string to 'f' of type float",
+  );
+}
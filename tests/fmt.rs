@@ -0,0 +1,100 @@
+mod common;
+
+use std::{env, fs};
+
+use alpha_shell::format;
+use common::{ash, assert_snapshot};
+
+/// Sources the formatter has to handle, all of them parsing
+const FILES: &[&str] = &[
+  "demo.ash",
+  "test/format.ash",
+  "test/heredoc.ash",
+  "test/operators.ash",
+  "test/params.ash",
+  "test/posix.ash",
+  "test/primes.ash",
+  "test/redirect.ash",
+  "test/run.ash",
+  "test/try.ash",
+  "test/defer.ash",
+  "test/signal.ash",
+  "test/string_arithmetic.ash",
+];
+
+#[test]
+fn formats_fixture() {
+  let source = fs::read_to_string("test/format.ash").unwrap();
+  assert_snapshot("format.ash", &format(&source).unwrap());
+}
+
+/// `format` fails rather than change the tokens or lose comments
+#[test]
+fn keeps_tokens_and_comments() {
+  for file in FILES {
+    let source = fs::read_to_string(file).unwrap();
+    assert!(format(&source).is_ok(), "formatting '{file}' changed it");
+  }
+}
+
+#[test]
+fn is_idempotent() {
+  for file in FILES {
+    let formatted = format(&fs::read_to_string(file).unwrap()).unwrap();
+    assert_eq!(
+      formatted,
+      format(&formatted).unwrap(),
+      "formatting '{file}' twice changed it"
+    );
+  }
+}
+
+#[test]
+fn keeps_comments_next_to_list_items() {
+  let source = "let a = [\n  1, // one\n  2,\n];\n";
+  assert_eq!(format(source).unwrap(), source);
+
+  let source = "let m = { a: \"1\" /* first */, b: \"2\" };\n";
+  assert_eq!(format(source).unwrap(), source);
+}
+
+#[test]
+fn keeps_comments_next_to_arguments() {
+  let source = "echo(\"a\", /* mid-arg */ \"b\");\n";
+  assert_eq!(format(source).unwrap(), source);
+
+  // a comment ending the line puts the arguments on lines of their own
+  let source = "echo(\"a\", // first\n  \"b\");\n";
+  assert_eq!(
+    format(source).unwrap(),
+    "echo(\n  \"a\", // first\n  \"b\",\n);\n"
+  );
+}
+
+#[test]
+fn only_checks_the_syntax() {
+  let source = "fn f(a) {\n  echo(a);\n}\nf(1, 2);\necho(nope);\nimport \"missing.ash\";\n";
+  assert_eq!(format(source).unwrap(), source);
+}
+
+#[test]
+fn check_fails_on_unformatted_files() {
+  let dir = env::temp_dir().join(format!("ash-fmt-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+
+  let file = dir.join("check.ash");
+  let file_name = file.to_str().unwrap();
+  fs::write(&file, "let a=[1,2];\n").unwrap();
+
+  let output = ash(&["fmt", "--check", file_name]);
+  assert!(!output.status.success());
+  assert!(String::from_utf8_lossy(&output.stdout).contains("Would reformat"));
+  assert_eq!(fs::read_to_string(&file).unwrap(), "let a=[1,2];\n");
+
+  assert!(ash(&["fmt", "--quiet", file_name]).status.success());
+  assert_eq!(fs::read_to_string(&file).unwrap(), "let a = [1, 2];\n");
+
+  assert!(ash(&["fmt", "--check", file_name]).status.success());
+
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use std::{env, fmt::Write, fs, process::Command};
+
+use common::{ash, installed, transpile_source};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+const BINARY_OPERATORS: &[&str] = &[
+  "+", "-", "*", "/", "%", "**", "==", "!=", ">", ">=", "<", "<=", "~", "&&", "||",
+];
+
+const LEAVES: &[&str] = &["a", "b", "1", "42", "\"s\"", "'r'", "true", "arr[1]"];
+
+/// Random expression source, `depth` levels of operators deep at most
+fn expression(rng: &mut StdRng, depth: u32) -> String {
+  if depth == 0 || rng.gen_bool(0.2) {
+    return (*LEAVES.choose(rng).unwrap()).to_owned();
+  }
+
+  let depth = depth - 1;
+
+  match rng.gen_range(0..7) {
+    0 => format!("!{}", expression(rng, depth)),
+    1 => format!("-({})", expression(rng, depth)),
+    2 => format!("({})", expression(rng, depth)),
+    3 => format!(
+      "{} ? {} : {}",
+      expression(rng, depth),
+      expression(rng, depth),
+      expression(rng, depth)
+    ),
+    4 => format!("$({})", expression(rng, depth)),
+    _ => format!(
+      "{} {} {}",
+      expression(rng, depth),
+      BINARY_OPERATORS.choose(rng).unwrap(),
+      expression(rng, depth)
+    ),
+  }
+}
+
+/// Random statement using an expression in one of the contexts the
+/// transpiler treats differently
+fn statement(rng: &mut StdRng) -> String {
+  let expression = expression(rng, 3);
+
+  match rng.gen_range(0..5) {
+    0 => format!("let c = {expression};"),
+    1 => format!("let c = $({expression});"),
+    2 => format!("if {expression} {{\n  echo(\"yes\");\n}}"),
+    3 => format!("while {expression} {{\n  break;\n}}"),
+    _ => format!("echo({expression});"),
+  }
+}
+
+#[test]
+fn random_expressions_compile_to_valid_shell() {
+  let seed = env::var("FUZZ_SEED").map_or(0, |seed| seed.parse().unwrap());
+  let mut rng = StdRng::seed_from_u64(seed);
+
+  let zsh = installed("zsh");
+
+  let dir = env::temp_dir().join(format!("ash-fuzz-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+
+  for i in 0..200 {
+    let source = format!(
+      "let a = 1;\nlet b = 2;\nlet arr = [1, 2];\n{}\n",
+      statement(&mut rng)
+    );
+
+    let file = dir.join(format!("{i}.ash"));
+    fs::write(&file, &source).unwrap();
+
+    for target in ["zsh", "bash", "sh"] {
+      let output = ash(&[
+        "--quiet",
+        "--target",
+        target,
+        "--output",
+        "-",
+        file.to_str().unwrap(),
+      ]);
+      let stderr = String::from_utf8_lossy(&output.stderr);
+
+      assert!(
+        !stderr.contains("panicked"),
+        "ash panicked for target {target} on:\n{source}\n{stderr}"
+      );
+
+      // what compiles has to be valid shell too
+      if output.status.success() && (target != "zsh" || zsh) {
+        let code = String::from_utf8(output.stdout).unwrap();
+        let check = Command::new(target)
+          .args(["-n", "-c", &code])
+          .output()
+          .unwrap();
+
+        assert!(
+          check.status.success(),
+          "{target} rejected the output for:\n{source}\n{code}\n{}",
+          String::from_utf8_lossy(&check.stderr)
+        );
+      }
+    }
+  }
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Characters the shell or the lexer could take for something else than text
+const TEXT: &[char] = &[
+  'a', 'Z', '0', ' ', ' ', '\t', '\n', '$', '`', '"', '\'', '\\', '!', '*', '?', '~', '#', ';',
+  '&', '|', '<', '>', '(', ')', '{', '}', '[', ']', '%', '=', '-', '@', 'é', '☺', '😀',
+];
+
+/// Random text along with a string literal holding it after a `>`, double
+/// quoted with escape sequences or raw. The `>` keeps echo from reading the
+/// text as options
+fn string(rng: &mut StdRng) -> (String, String) {
+  let text: String = (0..rng.gen_range(0..12))
+    .map(|_| *TEXT.choose(rng).unwrap())
+    .collect();
+
+  // raw strings have no escape for line breaks
+  if !text.contains('\n') && rng.gen_bool(0.3) {
+    let literal = text.replace('\\', r"\\").replace('\'', r"\'");
+    return (text, format!("'>{literal}'"));
+  }
+
+  let mut literal = String::new();
+  for char in text.chars() {
+    match char {
+      '\\' | '"' | '$' => write!(literal, "\\{char}").unwrap(),
+      '\n' => literal += r"\n",
+      '\t' => literal += r"\t",
+      char if !char.is_ascii() && rng.gen_bool(0.5) => {
+        write!(literal, r"\u{{{:X}}}", u32::from(char)).unwrap();
+      }
+      char => literal.push(char),
+    }
+  }
+
+  (text, format!("\">{literal}\""))
+}
+
+/// Checks that random strings reach `echo` as the text they hold in the
+/// output for `shell`, directly, as map keys and values and in interpolated
+/// map keys
+fn assert_strings_reach_commands_unchanged(shell: &str) {
+  let seed = env::var("FUZZ_SEED").map_or(0, |seed| seed.parse().unwrap());
+  let mut rng = StdRng::seed_from_u64(seed);
+
+  let mut source = String::from("let m = { k: \"v\" };\nlet key = \"\";\n");
+  let mut expected = String::new();
+
+  // the subscripts taking every item in the shell
+  for key in ["@", "*"] {
+    writeln!(source, "m[\"{key}\"] = \">{key}\";").unwrap();
+    writeln!(source, "echo(\"-E\", m[\"{key}\"]);").unwrap();
+    writeln!(expected, ">{key}").unwrap();
+  }
+  for _ in 0..200 {
+    let (text, literal) = string(&mut rng);
+
+    writeln!(source, "echo(\"-E\", {literal});").unwrap();
+    writeln!(source, "m[{literal}] = {literal};").unwrap();
+    writeln!(source, "echo(\"-E\", m[{literal}]);").unwrap();
+    writeln!(source, "key = {literal};").unwrap();
+    writeln!(source, "m[\"<(${{key}})\"] = key;").unwrap();
+    writeln!(source, "echo(\"-E\", m[\"<(${{key}})\"]);").unwrap();
+    writeln!(expected, ">{text}\n>{text}\n>{text}").unwrap();
+  }
+
+  let transpiled = transpile_source(shell, &source);
+  assert!(
+    transpiled.status.success(),
+    "ash failed for target {shell}:\n{}",
+    String::from_utf8_lossy(&transpiled.stderr)
+  );
+
+  let output = Command::new(shell)
+    .arg("-c")
+    .arg(String::from_utf8(transpiled.stdout).unwrap())
+    .output()
+    .unwrap();
+
+  assert!(
+    output.status.success() && output.stderr.is_empty(),
+    "{shell} failed:\n{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  assert_eq!(
+    expected,
+    String::from_utf8(output.stdout).unwrap(),
+    "{shell} printed other strings than the ones in:\n{source}"
+  );
+}
+
+#[test]
+fn random_strings_reach_commands_unchanged() {
+  assert_strings_reach_commands_unchanged("bash");
+}
+
+#[test]
+fn random_strings_reach_commands_unchanged_zsh() {
+  if installed("zsh") {
+    assert_strings_reach_commands_unchanged("zsh");
+  }
+}
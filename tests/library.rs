@@ -0,0 +1,83 @@
+use alpha_shell::{
+  compile, parse, FunctionCall, Lint, LintLevel, Literal, Node, NodeKind, Options, Target, Value,
+  ValueKind,
+};
+
+const SOURCE: &str = "if true {\n  echo(\"yes\");\n}\n";
+
+#[test]
+fn compiles_with_options() {
+  let output = compile(SOURCE, &Options::default()).unwrap();
+  assert!(output.code.contains("\n  echo \"yes\"\n"));
+  assert!(output.code.ends_with('\n'));
+  assert!(output.warnings.is_empty());
+
+  let options = Options::new(Target::Bash)
+    .with_indent("\t")
+    .with_shebang(true);
+  let output = compile(SOURCE, &options).unwrap();
+  assert!(output.code.starts_with("#!/usr/bin/env bash\n"));
+  assert!(output.code.contains("\n\techo \"yes\"\n"));
+}
+
+#[test]
+fn returns_every_error() {
+  let diagnostics = compile("let = 1;\nlet = 2;\n", &Options::default()).unwrap_err();
+  assert_eq!(diagnostics.len(), 2);
+  assert_eq!(diagnostics[1].span.unwrap().start.0, 1);
+}
+
+#[test]
+fn lists_imports() {
+  let output = compile("import \"test/test2.ash\";\n", &Options::default());
+  assert_eq!(output.unwrap().imports, ["test/test2.ash"]);
+}
+
+#[test]
+fn strict_mode_accepts_declared_commands() {
+  let source = "external fn git;\ngit(\"status\");\necho(\"done\");\n";
+  assert!(compile(source, &Options::default().with_strict(true)).is_ok());
+
+  let diagnostics = compile("gti(\"status\");\n", &Options::default().with_strict(true));
+  assert_eq!(diagnostics.unwrap_err()[0].message, "Unknown command gti");
+}
+
+#[test]
+fn lint_levels() {
+  let source = "let unused = \"x\";\n";
+
+  let output = compile(source, &Options::default()).unwrap();
+  assert_eq!(output.warnings[0].lint, Some(Lint::UnusedVariable));
+
+  let options = Options::default().with_lint(Lint::UnusedVariable, LintLevel::Allow);
+  assert!(compile(source, &options).unwrap().warnings.is_empty());
+
+  let options = Options::default().with_lint(Lint::UnusedVariable, LintLevel::Deny);
+  assert_eq!(
+    compile(source, &options).unwrap_err()[0].message,
+    "Unused variable unused"
+  );
+}
+
+#[test]
+fn parses_to_a_tree() {
+  let tree = parse("echo(\"hi\");\n", &Options::default()).unwrap();
+  let [Node {
+    kind: NodeKind::FunctionCall(FunctionCall { name, args, .. }),
+    ..
+  }] = tree.as_slice()
+  else {
+    panic!("expected a single call, got {tree:?}");
+  };
+  assert_eq!(name, "echo");
+  assert!(matches!(
+    args.as_slice(),
+    [Value {
+      kind: ValueKind::Literal(Literal::String(text)),
+      ..
+    }] if text == "hi"
+  ));
+
+  let diagnostics = parse("gti(\"status\");\n", &Options::default().with_strict(true));
+  assert_eq!(diagnostics.unwrap_err()[0].message, "Unknown command gti");
+}
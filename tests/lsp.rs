@@ -0,0 +1,266 @@
+use std::{
+  io::{BufRead, BufReader, Read, Write},
+  process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use serde_json::{json, Value};
+
+const URI: &str = "file:///tmp/lsp.ash";
+
+const SOURCE: &str = r#"export greeting = "hi";
+
+fn greet(name, times) {
+  let message = "$greeting $name";
+  for i in 0..times {
+    echo(message);
+  }
+}
+
+greet("you", 2);
+"#;
+
+/// Talks JSON-RPC to `ash-lsp` over its stdio, like an editor would
+struct Client {
+  child: Child,
+  stdin: ChildStdin,
+  stdout: BufReader<ChildStdout>,
+  next_id: u64,
+}
+
+impl Client {
+  fn start() -> Self {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ash-lsp"))
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()
+      .expect("failed to run ash-lsp");
+
+    let stdin = child.stdin.take().unwrap();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let mut client = Self {
+      child,
+      stdin,
+      stdout,
+      next_id: 0,
+    };
+
+    client.request("initialize", json!({ "capabilities": {} }));
+    client.notify("initialized", json!({}));
+    client
+  }
+
+  fn send(&mut self, message: &Value) {
+    let body = message.to_string();
+    write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+    self.stdin.flush().unwrap();
+  }
+
+  fn receive(&mut self) -> Value {
+    let mut length = 0;
+
+    loop {
+      let mut header = String::new();
+      self.stdout.read_line(&mut header).unwrap();
+
+      match header.trim() {
+        "" => break,
+        header => {
+          if let Some(value) = header.strip_prefix("Content-Length: ") {
+            length = value.parse().unwrap();
+          }
+        }
+      }
+    }
+
+    let mut body = vec![0; length];
+    self.stdout.read_exact(&mut body).unwrap();
+    serde_json::from_slice(&body).unwrap()
+  }
+
+  fn notify(&mut self, method: &str, params: Value) {
+    self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+  }
+
+  /// Sends a request and returns its result, skipping notifications
+  fn request(&mut self, method: &str, params: Value) -> Value {
+    self.next_id += 1;
+    let id = self.next_id;
+    self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+
+    loop {
+      let message = self.receive();
+      if message["id"] == id {
+        return message["result"].clone();
+      }
+    }
+  }
+
+  /// Waits for the diagnostics of the document
+  fn diagnostics(&mut self) -> Vec<Value> {
+    loop {
+      let message = self.receive();
+      if message["method"] == "textDocument/publishDiagnostics" {
+        return message["params"]["diagnostics"].as_array().unwrap().clone();
+      }
+    }
+  }
+
+  fn open(&mut self, text: &str) -> Vec<Value> {
+    self.notify(
+      "textDocument/didOpen",
+      json!({
+        "textDocument": { "uri": URI, "languageId": "ash", "version": 1, "text": text }
+      }),
+    );
+    self.diagnostics()
+  }
+
+  fn change(&mut self, text: &str) -> Vec<Value> {
+    self.notify(
+      "textDocument/didChange",
+      json!({
+        "textDocument": { "uri": URI, "version": 2 },
+        "contentChanges": [{ "text": text }]
+      }),
+    );
+    self.diagnostics()
+  }
+
+  fn at(&mut self, method: &str, line: u32, character: u32) -> Value {
+    self.request(
+      method,
+      json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": line, "character": character }
+      }),
+    )
+  }
+
+  fn shutdown(mut self) {
+    self.request("shutdown", Value::Null);
+    self.notify("exit", Value::Null);
+    assert!(self.child.wait().unwrap().success());
+  }
+}
+
+fn range(line: u32, start: u32, end: u32) -> Value {
+  json!({
+    "start": { "line": line, "character": start },
+    "end": { "line": line, "character": end }
+  })
+}
+
+#[test]
+fn reports_diagnostics_while_typing() {
+  let mut client = Client::start();
+
+  assert_eq!(client.open(SOURCE), Vec::<Value>::new());
+
+  let diagnostics = client.change("let a = 1;\necho(b);\n");
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(diagnostics[0]["range"], range(1, 5, 6));
+  assert_eq!(diagnostics[0]["severity"], 1);
+  assert!(diagnostics[0]["message"]
+    .as_str()
+    .unwrap()
+    .starts_with("Undefined variable b"));
+
+  assert_eq!(client.change(SOURCE), Vec::<Value>::new());
+
+  client.shutdown();
+}
+
+#[test]
+fn goes_to_definitions() {
+  let mut client = Client::start();
+  client.open(SOURCE);
+
+  let definition = |client: &mut Client, line, character| {
+    client.at("textDocument/definition", line, character)["range"].clone()
+  };
+
+  // function call
+  assert_eq!(definition(&mut client, 9, 1), range(2, 3, 8));
+  // parameter
+  assert_eq!(definition(&mut client, 4, 17), range(2, 15, 20));
+  // local variable
+  assert_eq!(definition(&mut client, 5, 10), range(3, 6, 13));
+  // interpolated in a string
+  assert_eq!(definition(&mut client, 3, 28), range(2, 9, 13));
+
+  // while the document doesn't parse the last definitions are used
+  client.change(&SOURCE.replace("greet(\"you\", 2);", "greet(\"you\""));
+  assert_eq!(definition(&mut client, 9, 1), range(2, 3, 8));
+
+  client.shutdown();
+}
+
+#[test]
+fn hovers_show_signatures() {
+  let mut client = Client::start();
+  client.open(SOURCE);
+
+  let hover = client.at("textDocument/hover", 9, 2);
+  assert_eq!(
+    hover["contents"]["value"],
+    "```ash\nfn greet(name, times)\n```"
+  );
+  assert_eq!(hover["range"], range(9, 0, 5));
+
+  assert_eq!(client.at("textDocument/hover", 5, 0), Value::Null);
+
+  client.shutdown();
+}
+
+#[test]
+fn completes_names_in_scope() {
+  let mut client = Client::start();
+  client.open(SOURCE);
+
+  let labels = |client: &mut Client, line, character| {
+    let mut labels: Vec<_> = client
+      .at("textDocument/completion", line, character)
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|item| item["label"].as_str().unwrap().to_owned())
+      .collect();
+    labels.sort();
+    labels
+  };
+
+  assert_eq!(
+    labels(&mut client, 5, 4),
+    ["greet", "greeting", "i", "message", "name", "times"]
+  );
+  assert_eq!(labels(&mut client, 9, 0), ["greet", "greeting"]);
+
+  client.shutdown();
+}
+
+#[test]
+fn lists_document_symbols() {
+  let mut client = Client::start();
+  client.open(SOURCE);
+
+  let symbols = client.request(
+    "textDocument/documentSymbol",
+    json!({ "textDocument": { "uri": URI } }),
+  );
+
+  let names = |symbols: &Value| -> Vec<String> {
+    symbols
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|symbol| symbol["name"].as_str().unwrap().to_owned())
+      .collect()
+  };
+
+  assert_eq!(names(&symbols), ["greeting", "greet"]);
+  assert_eq!(names(&symbols[1]["children"]), ["message"]);
+  assert_eq!(symbols[1]["detail"], "fn greet(name, times)");
+
+  client.shutdown();
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use std::process::Command;
+
+use common::transpile_source;
+
+const PRELUDE: &str = "let a = 2;\nlet b = 3;\nlet c = 4;\nlet x = 0;\nlet y = 0;\n";
+
+/// Expression and what echoing it prints, one or more rows per level
+const TABLE: &[(&str, &str)] = &[
+  // `**`, right associative
+  ("$(2 ** 3 ** 2)", "512"),
+  ("$(-(a) ** 2)", "4"),
+  // `* / %`
+  ("$(a + b * c)", "14"),
+  ("$(c / a * b)", "6"),
+  ("$(a * b % c)", "2"),
+  // `+ -`, left associative
+  ("$(c - b - a)", "-1"),
+  ("$(a * b - c)", "2"),
+  // comparisons, relational binding tighter than equality
+  ("$(a + b == 5)", "1"),
+  ("$(a < b == b < c)", "1"),
+  // `~`
+  (r#"a == 2 && "abc" ~ "^b" ? "yes" : "no""#, "no"),
+  // `&&` over `||`
+  ("a == 2 || b == 1 && c == 5 ? \"yes\" : \"no\"", "yes"),
+  ("(a == 2 || b == 1) && c == 5 ? \"yes\" : \"no\"", "no"),
+  // ternary, right associative and below `||`
+  (r#"a == 1 ? "one" : a == 2 ? "two" : "many""#, "two"),
+  (r#"a == 1 || b == 3 ? "yes" : "no""#, "yes"),
+  // assignment, right associative
+  ("$(x = y = a + 1)", "3"),
+];
+
+fn run(shell: &str, target: &str, expression: &str) -> String {
+  let source = format!("{PRELUDE}echo({expression});\n");
+  let output = transpile_source(target, &source);
+
+  assert!(
+    output.status.success(),
+    "ash failed on '{expression}':\n{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+
+  let code = String::from_utf8(output.stdout).unwrap();
+  let output = Command::new(shell).arg("-c").arg(&code).output().unwrap();
+
+  String::from_utf8(output.stdout).unwrap().trim().to_owned()
+}
+
+#[test]
+fn precedence_table_bash() {
+  for (expression, expected) in TABLE {
+    assert_eq!(run("bash", "bash", expression), *expected, "{expression}");
+  }
+}
+
+#[test]
+fn precedence_table_sh() {
+  for (expression, expected) in TABLE {
+    if expression.contains("**") {
+      continue;
+    }
+
+    assert_eq!(run("sh", "sh", expression), *expected, "{expression}");
+  }
+}
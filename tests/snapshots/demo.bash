@@ -0,0 +1,115 @@
+declare a="5"
+export b="6"
+declare person="John"
+declare c="$(pwd)"
+declare -a people=("John" "Peter")
+declare -A map=([foo]="bar" [foo2]="baz" )
+declare d=$(if [[ "${a}" == "2" ]]; then echo "a"; else echo "b"; fi)
+a+="10"
+echo "${a}" "${b}" "${c}" "${d}"
+echo "${people[0]}"
+echo "${map['foo']}"
+map['foo']="bar2"
+echo "${map['foo']}"
+echo $(( a + b ))
+echo "a: ${a}"
+echo 'a: ${a}'
+echo "${PATH}"
+echo "${0}"
+echo "${@}"
+if [[ "${a}" -gt "10" ]]; then
+  echo "a is larger than 10"
+fi
+if [[ "${person}" != "Peter" ]]; then
+  echo "${person} is not Peter"
+fi
+if [[ "${a}" -le "10" && "${b}" -gt "3" || "${person}" == "Jack" ]]; then
+  echo "Complex condition satisfied"
+fi
+if [[ ("0" == "1" && "1" == "1") || "1" == "1" ]]; then
+  echo "Should print"
+fi
+if [[ "0" == "1" && ("1" == "1" || "1" == "1") ]]; then
+  echo "Shouldn't print"
+fi
+if [[ 0 ]]; then
+  echo "True"
+  export xyx="xyz"
+fi
+echo "${xyx}"
+for (( a__1 = 0; a__1 <= 5; a__1 += 1 )); do
+  echo "${a__1}"
+done
+for (( a__2 = 0; a__2 <= 10; a__2 += 2 )); do
+  echo "${a__2}"
+done
+for (( a__3 = 5; a__3 >= 0; a__3 += -1 )); do
+  echo "${a__3}"
+done
+for word in "hello" "world"; do
+  echo "${word}"
+done
+for person__4 in "${people[@]}"; do
+  echo "${person__4}"
+done
+declare x="0"
+while [[ "${x}" -lt "10" ]]; do
+  echo "${x}"
+  (( x += 1 ))
+done
+__tmp_XXXXXX(){
+  local list
+  if list=$(grep "let a" "demo.ash"); then
+    echo "Found something"
+    echo "${list}"
+  else
+  echo "Found nothing"
+  fi
+}
+__tmp_XXXXXX
+__tmp_XXXXXX(){
+  local var
+  while var=$(grep "abcd" "file"); do
+    echo "${var}"
+  done
+}
+__tmp_XXXXXX
+function hello() {
+  local name=$1
+  local age=$2
+  local cwd=$3
+  echo "Hello ${name} aged ${age}, we're in ${cwd}"
+  echo "Hello ${1} aged ${2}, we're in ${3}"
+}
+hello "John" "25" "${c}" | lolcat
+declare fb="$(printf "foobar" | sed "s/bar/baz/g")"
+declare ab="$(echo $(( a - b )) | wc '-c')"
+declare ab2="$(echo $(( a - b )) | wc '--chars')"
+echo "${fb}"
+echo "${ab}"
+echo "${ab2}"
+function arrayFunction() {
+  for a__5 in "${@}"; do
+    echo "${a__5}"
+  done
+  return "0"
+}
+arrayFunction "a" "b" "c" "d" "e"
+declare matched="abcb"
+if [[ "${matched}" =~ $(printf '%s' "abc{2,5}") ]]; then
+  echo "matched"
+elif [[ "${matched}" =~ $(printf '%s' "abc(cb)?") ]]; then
+  echo "matched elif"
+else
+  echo "not matched"
+fi
+firefox "google.com" &
+printf $'loaded test2\n'
+function test2() {
+  for (( i = 0; i <= 3; i += 1 )); do
+    echo "Hello from test2" | lolcat
+  done
+}
+test2
+source 'another_folder/file.zsh'
+source '/system/folder/file.zsh'
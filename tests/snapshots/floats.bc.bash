@@ -0,0 +1,8 @@
+declare price="1.5"
+declare -i count="4"
+echo $(echo '1.5 * 2' | bc -l | sed 's/^\(-\{0,1\}\)\./\10./; s/\(\.[0-9]*[1-9]\)0*$/\1/; s/\.0*$/.0/; s/^-\{0,1\}[0-9]*$/&.0/')
+echo $(printf '%s\n' price="${price}" count="${count}" 'price * count' | bc -l | sed 's/^\(-\{0,1\}\)\./\10./; s/\(\.[0-9]*[1-9]\)0*$/\1/; s/\.0*$/.0/; s/^-\{0,1\}[0-9]*$/&.0/')
+declare half=$(printf '%s\n' count="${count}" 'count / 2.0' | bc -l | sed 's/^\(-\{0,1\}\)\./\10./; s/\(\.[0-9]*[1-9]\)0*$/\1/; s/\.0*$/.0/; s/^-\{0,1\}[0-9]*$/&.0/')
+echo "${half}"
+echo $(( 7 / 2 ))
+echo $(printf '%s\n' price="${price}" 'price > 1' | bc -l)
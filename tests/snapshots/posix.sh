@@ -0,0 +1,66 @@
+a="5"
+export b="6"
+d=$(if [ "${a}" = "2" ]; then echo "a"; else echo "b"; fi)
+echo "${a}" "${b}" "${d}"
+echo $(( a + b ))
+if { [ "${a}" -gt "10" ] && [ "${b}" -gt "3" ]; } || [ "${a}" = "5" ]; then
+  echo "complex"
+fi
+if { [ "${a}" = "1" ] || [ "${a}" = "5" ]; } && [ "${b}" = "6" ]; then
+  echo "grouped"
+fi
+if [ ! "${a}" = "1" ]; then
+  echo "negated"
+fi
+i=$(( 0 - 3 ))
+while i=$(( i + 3 )); [ $(( i <= 10 )) -ne 0 ]; do
+  if [ "${i}" = "3" ]; then
+    continue
+  fi
+  echo "${i}"
+done
+i=$(( 3 - -1 ))
+while i=$(( i + -1 )); [ $(( i >= 0 )) -ne 0 ]; do
+  echo "${i}"
+done
+for w in "x y" "z"; do
+  echo "${w}"
+done
+x="0"
+while [ "${x}" -lt "3" ]; do
+  : $(( x += 1 ))
+done
+echo "${x}"
+m="abcb"
+if printf '%s\n' "${m}" | grep -Eq -- "abc(cb)?"; then
+  echo "matched"
+fi
+hello() (
+  name=$1
+  g="hi"
+  echo "${g} ${name}"
+  return "0"
+)
+if hello "bob"; then
+  echo "ok"
+fi
+if r=$(echo "captured"); then
+  echo "${r}"
+else
+  echo "no"
+fi
+case "${a}" in
+5)
+  echo "five"
+;;
+esac
+args() (
+  for a__1 in "${@}"; do
+    echo "${a__1}"
+  done
+)
+args "1" "2"
+n="a"
+n="${n}b"
+n="${n}"'c'
+echo "${n}"
@@ -0,0 +1,261 @@
+mod common;
+
+use std::process::Command;
+
+use common::{ash, assert_runs, assert_snapshot, installed, run, transpile};
+
+#[test]
+fn demo_zsh() {
+  assert_snapshot("demo.zsh", &transpile("zsh", "demo.ash"));
+}
+
+#[test]
+fn demo_bash() {
+  assert_snapshot("demo.bash", &transpile("bash", "demo.ash"));
+}
+
+#[test]
+fn posix_sh() {
+  assert_snapshot("posix.sh", &transpile("sh", "test/posix.ash"));
+}
+
+#[test]
+fn posix_sh_runs() {
+  assert_runs(&["sh", "bash", "zsh"], "test/posix.ash", "posix.sh.stdout");
+}
+
+#[test]
+fn posix_sh_rejects_arrays() {
+  let output = ash(&["--quiet", "--target", "sh", "--output", "-", "demo.ash"]);
+  let stderr = String::from_utf8(output.stderr).unwrap();
+
+  assert!(!output.status.success());
+  assert!(stderr.contains("Array variable 'people' has no POSIX sh equivalent"));
+  assert!(stderr.contains("--> demo.ash:5:1"));
+}
+
+#[test]
+fn errors_report_imported_file() {
+  let output = ash(&[
+    "--quiet",
+    "--target",
+    "sh",
+    "--output",
+    "-",
+    "test/import.ash",
+  ]);
+  let stderr = String::from_utf8(output.stderr).unwrap();
+
+  assert!(!output.status.success());
+  assert!(stderr.contains("--> demo.ash:5:1"));
+}
+
+#[test]
+fn operators_zsh() {
+  assert_snapshot("operators.zsh", &transpile("zsh", "test/operators.ash"));
+}
+
+#[test]
+fn operators_run() {
+  assert_runs(
+    &["bash", "sh", "zsh"],
+    "test/operators.ash",
+    "operators.stdout",
+  );
+}
+
+#[test]
+fn params_zsh() {
+  assert_snapshot("params.zsh", &transpile("zsh", "test/params.ash"));
+}
+
+#[test]
+fn params_run() {
+  assert_runs(&["bash", "sh", "zsh"], "test/params.ash", "params.stdout");
+}
+
+#[test]
+fn scope_zsh() {
+  assert_snapshot("scope.zsh", &transpile("zsh", "test/scope.ash"));
+}
+
+#[test]
+fn scope_run() {
+  assert_runs(&["bash", "sh", "zsh"], "test/scope.ash", "scope.stdout");
+}
+
+#[test]
+fn types_zsh() {
+  assert_snapshot("types.zsh", &transpile("zsh", "test/types.ash"));
+}
+
+#[test]
+fn types_run() {
+  assert_runs(&["bash", "zsh"], "test/types.ash", "types.stdout");
+}
+
+#[test]
+fn floats_zsh() {
+  assert_snapshot("floats.zsh", &transpile("zsh", "test/floats.ash"));
+}
+
+/// Output for `target` evaluating float arithmetic with `float`
+fn transpile_floats(target: &str, float: &str) -> String {
+  let output = ash(&[
+    "--quiet",
+    "--target",
+    target,
+    "--float",
+    float,
+    "--output",
+    "-",
+    "test/floats.ash",
+  ]);
+
+  assert!(output.status.success());
+  String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn floats_bc() {
+  assert_snapshot("floats.bc.bash", &transpile_floats("bash", "bc"));
+}
+
+#[test]
+fn floats_run() {
+  for shell in ["bash", "sh"] {
+    assert_snapshot(
+      "floats.stdout",
+      &run(shell, &transpile_floats(shell, "awk")),
+    );
+  }
+
+  assert_runs(&["zsh"], "test/floats.ash", "floats.stdout");
+}
+
+#[test]
+fn floats_run_bc() {
+  if !installed("bc") {
+    return;
+  }
+
+  for shell in ["bash", "sh"] {
+    assert_snapshot("floats.stdout", &run(shell, &transpile_floats(shell, "bc")));
+  }
+}
+
+#[test]
+fn interpolation_zsh() {
+  assert_snapshot(
+    "interpolation.zsh",
+    &transpile("zsh", "test/interpolation.ash"),
+  );
+}
+
+#[test]
+fn interpolation_run() {
+  assert_runs(
+    &["bash", "sh", "zsh"],
+    "test/interpolation.ash",
+    "interpolation.stdout",
+  );
+}
+
+#[test]
+fn heredoc_zsh() {
+  assert_snapshot("heredoc.zsh", &transpile("zsh", "test/heredoc.ash"));
+}
+
+#[test]
+fn heredoc_run() {
+  assert_runs(&["bash", "sh", "zsh"], "test/heredoc.ash", "heredoc.stdout");
+}
+
+#[test]
+fn redirect_zsh() {
+  assert_snapshot("redirect.zsh", &transpile("zsh", "test/redirect.ash"));
+}
+
+#[test]
+fn redirect_run() {
+  assert_runs(
+    &["bash", "sh", "zsh"],
+    "test/redirect.ash",
+    "redirect.stdout",
+  );
+}
+
+#[test]
+fn run_zsh() {
+  assert_snapshot("run.zsh", &transpile("zsh", "test/run.ash"));
+}
+
+#[test]
+fn run_run() {
+  assert_runs(&["bash", "sh", "zsh"], "test/run.ash", "run.stdout");
+}
+
+#[test]
+fn try_zsh() {
+  assert_snapshot("try.zsh", &transpile("zsh", "test/try.ash"));
+}
+
+#[test]
+fn try_run() {
+  assert_runs(&["bash", "sh", "zsh"], "test/try.ash", "try.stdout");
+}
+
+#[test]
+fn defer_zsh() {
+  assert_snapshot("defer.zsh", &transpile("zsh", "test/defer.ash"));
+}
+
+#[test]
+fn defer_run() {
+  assert_runs(&["bash", "sh", "zsh"], "test/defer.ash", "defer.stdout");
+}
+
+/// Checks what the output of `test/defer_exit.ash` for `shell` prints and
+/// exits with, for each way its function can end
+fn assert_defers_on_exit(shell: &str) {
+  let code = transpile(shell, "test/defer_exit.ash");
+
+  for (how, stdout, status) in [
+    (
+      "return",
+      "returned\nfunction cleanup\nend\nscript cleanup\n",
+      0,
+    ),
+    ("exit", "function cleanup\nscript cleanup\n", 3),
+    ("kill", "function cleanup\nscript cleanup\n", 143),
+  ] {
+    let output = Command::new(shell)
+      .arg("-c")
+      .arg(&code)
+      .env("HOW", how)
+      .output()
+      .unwrap();
+
+    assert_eq!(stdout, String::from_utf8(output.stdout).unwrap(), "{how}");
+    assert_eq!(Some(status), output.status.code(), "{how}");
+  }
+}
+
+#[test]
+fn defer_on_exit_run() {
+  assert_defers_on_exit("bash");
+
+  if installed("zsh") {
+    assert_defers_on_exit("zsh");
+  }
+}
+
+#[test]
+fn signal_zsh() {
+  assert_snapshot("signal.zsh", &transpile("zsh", "test/signal.ash"));
+}
+
+#[test]
+fn signal_run() {
+  assert_runs(&["bash", "sh", "zsh"], "test/signal.ash", "signal.stdout");
+}
@@ -0,0 +1,103 @@
+use std::{
+  env, fs,
+  path::Path,
+  process::{Child, Command, Stdio},
+  thread,
+  time::{Duration, Instant},
+};
+
+/// Kills `ash --watch` when the test ends, even if it fails
+struct Watcher(Child);
+
+impl Drop for Watcher {
+  fn drop(&mut self) {
+    let _ = self.0.kill();
+    let _ = self.0.wait();
+  }
+}
+
+/// Waits until the file at `path` exists and contains `text`
+fn wait_for(path: &Path, text: &str) {
+  let start = Instant::now();
+
+  while !fs::read_to_string(path).is_ok_and(|contents| contents.contains(text)) {
+    assert!(
+      start.elapsed() < Duration::from_secs(10),
+      "'{}' never contained '{text}'",
+      path.display()
+    );
+    thread::sleep(Duration::from_millis(50));
+  }
+}
+
+#[test]
+fn retranspiles_on_changes() {
+  let dir = env::temp_dir().join(format!("ash-watch-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+
+  fs::write(dir.join("lib.ash"), "export greeting = \"first\";\n").unwrap();
+  fs::write(
+    dir.join("main.ash"),
+    "import \"lib.ash\";\necho(greeting);\n",
+  )
+  .unwrap();
+
+  let mut watcher = Watcher(
+    Command::new(env!("CARGO_BIN_EXE_ash"))
+      .args(["--watch", "--quiet", "main.ash"])
+      .current_dir(&dir)
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .spawn()
+      .expect("failed to run ash"),
+  );
+
+  let output = dir.join("main.zsh");
+  wait_for(&output, "first");
+
+  // imported files are watched too
+  fs::write(dir.join("lib.ash"), "export greeting = \"second\";\n").unwrap();
+  wait_for(&output, "second");
+
+  // errors are reported without stopping the watcher, or touching the output
+  fs::write(dir.join("main.ash"), "echo(\n").unwrap();
+  thread::sleep(Duration::from_millis(500));
+  assert!(watcher.0.try_wait().unwrap().is_none());
+  assert!(fs::read_to_string(&output).unwrap().contains("second"));
+
+  fs::write(dir.join("main.ash"), "echo(\"third\");\n").unwrap();
+  wait_for(&output, "third");
+
+  drop(watcher);
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn watches_imports_that_never_parsed() {
+  let dir = env::temp_dir().join(format!("ash-watch-import-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+
+  fs::write(dir.join("lib.ash"), "export greeting = ;\n").unwrap();
+  fs::write(
+    dir.join("main.ash"),
+    "import \"lib.ash\";\necho(greeting);\n",
+  )
+  .unwrap();
+
+  let watcher = Watcher(
+    Command::new(env!("CARGO_BIN_EXE_ash"))
+      .args(["--watch", "--quiet", "main.ash"])
+      .current_dir(&dir)
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .spawn()
+      .expect("failed to run ash"),
+  );
+
+  thread::sleep(Duration::from_millis(500));
+  fs::write(dir.join("lib.ash"), "export greeting = \"fixed\";\n").unwrap();
+  wait_for(&dir.join("main.zsh"), "fixed");
+
+  drop(watcher);
+  fs::remove_dir_all(&dir).unwrap();
+}